@@ -1,44 +1,65 @@
 use core::fmt;
 
+use crate::token::Position;
+
 // --- Errores de Sintaxis ---
-#[derive(Debug, PartialEq, Clone)] // Añadido `Clone` para un mejor manejo de errores
+// Cada error indica qué se esperaba, qué se encontró en su lugar y dónde.
+// `expected` es una descripción fija de la gramática; `found` ya viene listo para
+// mostrarse (p. ej. `';'` o `el final del archivo`).
+#[derive(Debug, PartialEq, Clone)]
 pub enum SyntaxError {
-    UnexpectedToken(String, usize, usize),
-    UnexpectedEndOfFile,
-    InvalidAssignmentTarget,
-    MissingSemicolon,
-    MissingColon,
-    MissingType,
-    MissingInKeyword,
-    MissingLoopVariable,
-    MissingStructName,
-    MissingFieldName,
+    UnexpectedToken { expected: &'static str, found: String, span: Span },
+    InvalidAssignmentTarget { found: String, span: Span },
+    NumberOutOfRange { found: String, span: Span },
+}
+
+impl SyntaxError {
+    /// Nombre estable del tipo de error, usado en las respuestas gRPC.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SyntaxError::UnexpectedToken { .. } => "UnexpectedToken",
+            SyntaxError::InvalidAssignmentTarget { .. } => "InvalidAssignmentTarget",
+            SyntaxError::NumberOutOfRange { .. } => "NumberOutOfRange",
+        }
+    }
+
+    pub fn expected(&self) -> &'static str {
+        match self {
+            SyntaxError::UnexpectedToken { expected, .. } => expected,
+            SyntaxError::InvalidAssignmentTarget { .. } => "una variable",
+            SyntaxError::NumberOutOfRange { .. } => "un número que quepa en 64 bits",
+        }
+    }
+
+    pub fn found(&self) -> &str {
+        match self {
+            SyntaxError::UnexpectedToken { found, .. }
+            | SyntaxError::InvalidAssignmentTarget { found, .. }
+            | SyntaxError::NumberOutOfRange { found, .. } => found,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxError::UnexpectedToken { span, .. }
+            | SyntaxError::InvalidAssignmentTarget { span, .. }
+            | SyntaxError::NumberOutOfRange { span, .. } => *span,
+        }
+    }
 }
 
 impl fmt::Display for SyntaxError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            SyntaxError::UnexpectedToken(t, line, col) => 
-                write!(f, "Token inesperado '{}' en la línea {}, columna {}", t, line, col),
-            SyntaxError::UnexpectedEndOfFile => 
-                write!(f, "Final inesperado del archivo"),
-            SyntaxError::InvalidAssignmentTarget => 
-                write!(f, "El objetivo de la asignación no es válido"),
-            SyntaxError::MissingSemicolon => 
-                write!(f, "Falta punto y coma"),
-            SyntaxError::MissingColon => 
-                write!(f, "Faltan dos puntos"),
-            SyntaxError::MissingType => 
-                write!(f, "Falta anotación de tipo"),
-            SyntaxError::MissingInKeyword => 
-                write!(f, "Se esperaba la palabra clave 'in' en el bucle 'for'"),
-            SyntaxError::MissingLoopVariable => 
-                write!(f, "Se esperaba una variable en el bucle 'for'"),
-            SyntaxError::MissingStructName => 
-                write!(f, "Se esperaba un nombre de struct después de la palabra clave 'struct'"),
-            SyntaxError::MissingFieldName => 
-                write!(f, "Se esperaba un nombre de campo en la declaración del struct"),
+            SyntaxError::UnexpectedToken { expected, found, .. } =>
+                write!(f, "Se esperaba {}, pero se encontró {}", expected, found)?,
+            SyntaxError::InvalidAssignmentTarget { found, .. } =>
+                write!(f, "Solo se puede asignar a una variable, pero se encontró {}", found)?,
+            SyntaxError::NumberOutOfRange { found, .. } =>
+                write!(f, "El literal numérico {} está fuera de rango", found)?,
         }
+        let start = self.span().start;
+        write!(f, " en la línea {}, columna {}", start.line, start.column)
     }
 }
 
@@ -51,7 +72,14 @@ pub enum Type {
     Float,
     String,
     Bool,
+    Char,
     Void, 
+    Array(Box<Type>), // Arreglo de elementos del tipo indicado, se escribe `[Int]`
+    FixedArray(Box<Type>, usize), // Arreglo con una longitud conocida al compilar, `[Int; 4]`
+    Struct(String),   // Struct declarado por el usuario, por su nombre
+    Enum(String),     // Enum declarado por el usuario, por su nombre
+    Function(Vec<Type>, Box<Type>), // Función con esos parámetros y ese retorno, `fn(Int) -> Bool`
+    Optional(Box<Type>), // Un valor del tipo o ninguno, `Int?`
 }
 
 impl Type {
@@ -61,89 +89,252 @@ impl Type {
             Type::Float => "Float".to_string(),
             Type::String => "String".to_string(),
             Type::Bool => "Bool".to_string(),
+            Type::Char => "Char".to_string(),
             Type::Void => "Void".to_string(),
+            Type::Array(element) => format!("[{}]", element.to_string()),
+            Type::FixedArray(element, length) => format!("[{}; {}]", element.to_string(), length),
+            Type::Struct(name) | Type::Enum(name) => name.clone(),
+            Type::Function(parameters, return_type) => {
+                let parameters: Vec<String> = parameters.iter().map(Type::to_string).collect();
+                format!("fn({}) -> {}", parameters.join(", "), return_type.to_string())
+            }
+            // Sin paréntesis, el `?` quedaría en el tipo de retorno de la función.
+            Type::Optional(inner) if matches!(**inner, Type::Function(..)) => format!("({})?", inner.to_string()),
+            Type::Optional(inner) => format!("{}?", inner.to_string()),
         }
     }
 
+    /// Lee un tipo escrito como lo deja `to_string`.
     pub fn from_str(s: &str) -> Option<Self> {
-        match s {
-            "Int" => Some(Type::Int),
-            "Float" => Some(Type::Float),
-            "String" => Some(Type::String),
-            "Bool" => Some(Type::Bool),
-            "Void" => Some(Type::Void),
+        match Type::parse_prefix(s)? {
+            (type_, "") => Some(type_),
             _ => None,
         }
     }
+
+    /// Lee el tipo con el que empieza `s` y devuelve también el texto que le sigue.
+    fn parse_prefix(s: &str) -> Option<(Self, &str)> {
+        let s = s.trim_start();
+        let (mut type_, mut rest) = if let Some(rest) = s.strip_prefix('[') {
+            let (element, rest) = Type::parse_prefix(rest)?;
+            match rest.trim_start().strip_prefix(';') {
+                Some(rest) => {
+                    let rest = rest.trim_start();
+                    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+                    let length = rest[..digits].parse().ok()?;
+                    (Type::FixedArray(Box::new(element), length), rest[digits..].strip_prefix(']')?)
+                }
+                None => (Type::Array(Box::new(element)), rest.trim_start().strip_prefix(']')?),
+            }
+        } else if let Some(mut rest) = s.strip_prefix("fn(") {
+            let mut parameters = Vec::new();
+            while !rest.trim_start().starts_with(')') {
+                let (parameter, after) = Type::parse_prefix(rest)?;
+                parameters.push(parameter);
+                rest = after.trim_start().strip_prefix(',').unwrap_or(after);
+            }
+            let rest = rest.trim_start()[1..].trim_start().strip_prefix("->")?;
+            let (return_type, rest) = Type::parse_prefix(rest)?;
+            (Type::Function(parameters, Box::new(return_type)), rest)
+        } else if let Some(rest) = s.strip_prefix('(') {
+            let (inner, rest) = Type::parse_prefix(rest)?;
+            (inner, rest.trim_start().strip_prefix(')')?)
+        } else {
+            let end = s.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(s.len());
+            let (name, rest) = s.split_at(end);
+            let type_ = match name {
+                "Int" => Type::Int,
+                "Float" => Type::Float,
+                "String" => Type::String,
+                "Bool" => Type::Bool,
+                "Char" => Type::Char,
+                "Void" => Type::Void,
+                // Cualquier otro nombre válido se toma como un struct; si existe, o si es un enum,
+                // lo decide el análisis semántico.
+                _ if name.starts_with(|c: char| c.is_alphabetic() || c == '_') => Type::Struct(name.to_string()),
+                _ => return None,
+            };
+            (type_, rest)
+        };
+        while let Some(after) = rest.strip_prefix('?') {
+            type_ = Type::Optional(Box::new(type_));
+            rest = after;
+        }
+        Some((type_, rest))
+    }
+}
+
+// --- Ubicación en el código fuente ---
+
+/// Tramo del código fuente que ocupa un nodo: desde el inicio de su primer
+/// token hasta el final del último. Un `Span` por defecto (línea 0) indica
+/// que el nodo no tiene ubicación conocida.
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
+    }
+
+    /// Tramo que va desde el inicio de `self` hasta el final de `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+}
+
+// Formato compacto (`línea:columna..línea:columna`) para que los volcados del AST sigan siendo legibles.
+impl fmt::Debug for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}..{}:{}", self.start.line, self.start.column, self.end.line, self.end.column)
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Identifier {
     pub name: String,
-    pub line: usize,
-    pub column: usize,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
     Identifier(Identifier),
-    Literal(Literal),
+    Literal(Literal, Span),
     Binary {
         left: Box<Expression>,
         op: BinaryOp,
         right: Box<Expression>,
+        span: Span,
     },
     Unary {
         op: UnaryOp,
         expr: Box<Expression>,
+        span: Span,
     },
+    // `target` es una variable, un elemento (`a[i]`) o un campo (`p.x`), o una cadena de ellos.
     Assignment {
-        target: Identifier,
+        target: Box<Expression>,
+        value: Box<Expression>,
+        span: Span,
+    },
+    // `target op= value` (ej. `a[i] += 1`); `x++` y `x--` también se construyen así.
+    // Se mantiene aparte de `Assignment` para que el destino se evalúe una sola vez.
+    CompoundAssignment {
+        target: Box<Expression>,
+        op: BinaryOp,
         value: Box<Expression>,
+        span: Span,
     },
-    Grouped(Box<Expression>),
+    Grouped(Box<Expression>, Span),
     FunctionCall {
         function: Box<Expression>,
         arguments: Vec<Expression>,
+        span: Span,
+    },
+    Array(Vec<Expression>, Span),
+    // Acceso a un elemento: `object[index]`.
+    Index {
+        object: Box<Expression>,
+        index: Box<Expression>,
+        span: Span,
     },
-    Array(Vec<Expression>),
-    Object(Vec<(Identifier, Expression)>),
-    Splat(Box<Expression>),
+    Object(Vec<(Identifier, Expression)>, Span),
+    Splat(Box<Expression>, Span),
     StructInstantiation {
         name: Identifier,
         fields: Vec<(Identifier, Expression)>,
+        span: Span,
     },
     MemberAccess {
         object: Box<Expression>,
         property: Identifier,
+        span: Span,
     },
+    InterpolatedString(Vec<InterpolationPart>, Span),
+    // Rango de enteros `start..end` o `start..=end`, con un paso opcional (`0..10 step 2`).
+    Range {
+        start: Box<Expression>,
+        end: Box<Expression>,
+        inclusive: bool,
+        step: Option<Box<Expression>>,
+        span: Span,
+    },
+    // `if cond { ... a } else { ... b }` o `cond ? a : b`: vale `a` o `b` según la condición.
+    // En la forma con `if`, las ramas son `Expression::Block` (o, con `else if`, otro `If`).
+    If {
+        condition: Box<Expression>,
+        then_branch: Box<Expression>,
+        else_branch: Box<Expression>,
+        span: Span,
+    },
+    // Bloque cuyo valor es su última expresión, escrita sin ';' (`{ let y = x * 2; y + 1 }`).
+    Block {
+        block: Block,
+        value: Box<Expression>,
+    },
+    // Variante de un enum, con su carga útil si la tiene: `Shape::Circle(r)` o `Shape::Empty`.
+    EnumVariant {
+        enum_name: Identifier,
+        variant: Identifier,
+        arguments: Vec<Expression>,
+        span: Span,
+    },
+    // `value is Shape::Circle`: si el valor es esa variante, sin mirar su carga útil.
+    Is {
+        value: Box<Expression>,
+        enum_name: Identifier,
+        variant: Identifier,
+        span: Span,
+    },
+    // Hueco que deja una expresión que no se pudo analizar; el error ya se reportó.
+    Error(Span),
 }
 
 impl Expression {
-    pub fn get_line_col(&self) -> (usize, usize) {
+    pub fn span(&self) -> Span {
         match self {
-            Expression::Identifier(ident) => (ident.line, ident.column),
-            Expression::Literal(_) => (0, 0), // Placeholder, refine if literals need specific line/col
-            Expression::Binary { left, .. } => left.get_line_col(),
-            Expression::Unary { expr, .. } => expr.get_line_col(),
-            Expression::Assignment { target, .. } => (target.line, target.column),
-            Expression::Grouped(expr) => expr.get_line_col(),
-            Expression::FunctionCall { function, .. } => function.get_line_col(),
-            Expression::Array(elements) => elements.first().map_or((0, 0), |e| e.get_line_col()),
-            Expression::Object(fields) => fields.first().map_or((0, 0), |(ident, _)| (ident.line, ident.column)),
-            Expression::Splat(expr) => expr.get_line_col(),
-            Expression::StructInstantiation { name, .. } => (name.line, name.column),
-            Expression::MemberAccess { object, .. } => object.get_line_col(),
+            Expression::Identifier(ident) => ident.span,
+            Expression::Block { block, .. } => block.span,
+            Expression::Literal(_, span)
+            | Expression::Grouped(_, span)
+            | Expression::Array(_, span)
+            | Expression::Object(_, span)
+            | Expression::Splat(_, span)
+            | Expression::InterpolatedString(_, span)
+            | Expression::Error(span)
+            | Expression::Binary { span, .. }
+            | Expression::Unary { span, .. }
+            | Expression::Assignment { span, .. }
+            | Expression::CompoundAssignment { span, .. }
+            | Expression::FunctionCall { span, .. }
+            | Expression::Index { span, .. }
+            | Expression::StructInstantiation { span, .. }
+            | Expression::MemberAccess { span, .. }
+            | Expression::Range { span, .. }
+            | Expression::If { span, .. }
+            | Expression::EnumVariant { span, .. }
+            | Expression::Is { span, .. } => *span,
         }
     }
 }
 
+// Fragmento de una cadena interpolada (ej. "nivel ${n}").
+#[derive(Debug, PartialEq, Clone)]
+pub enum InterpolationPart {
+    Text(String),
+    Expression(Box<Expression>),
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Literal {
     Int(i64),
     Float(f64),
     String(String),
     Bool(bool),
+    Char(char),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -153,6 +344,7 @@ pub enum BinaryOp {
     Minus,
     Asterisk,
     Slash,
+    Percent,
     // Relacionales
     Greater,
     Less,
@@ -163,16 +355,52 @@ pub enum BinaryOp {
     // Lógicos
     DoubleAmpersand,
     DoubleBar,
+    // De bits (solo para Int)
+    Ampersand,
+    Bar,
+    Caret,
+    ShiftLeft,
+    ShiftRight,
     // --- NUEVOS OPERADORES ---
     Pipe,   // |>
     Spread, // ...+
     Swap,   // <=>
 }
 
+impl BinaryOp {
+    /// El operador tal como se escribe en el código fuente.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Plus => "+",
+            BinaryOp::Minus => "-",
+            BinaryOp::Asterisk => "*",
+            BinaryOp::Slash => "/",
+            BinaryOp::Percent => "%",
+            BinaryOp::Greater => ">",
+            BinaryOp::Less => "<",
+            BinaryOp::GreaterEqual => ">=",
+            BinaryOp::LessEqual => "<=",
+            BinaryOp::DoubleEqual => "==",
+            BinaryOp::NotEqual => "!=",
+            BinaryOp::DoubleAmpersand => "&&",
+            BinaryOp::DoubleBar => "||",
+            BinaryOp::Ampersand => "&",
+            BinaryOp::Bar => "|",
+            BinaryOp::Caret => "^",
+            BinaryOp::ShiftLeft => "<<",
+            BinaryOp::ShiftRight => ">>",
+            BinaryOp::Pipe => "|>",
+            BinaryOp::Spread => "...+",
+            BinaryOp::Swap => "<=>",
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum UnaryOp {
     Minus,
     Exclamation,
+    Tilde, // Negación a nivel de bit
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -185,11 +413,34 @@ pub enum Statement {
     For(ForStatement),
     // --- NUEVA VARIANTE DE SENTENCIA ---
     DoUntil(DoUntilStatement),
+    Break(JumpStatement),
+    Continue(JumpStatement),
+    Match(MatchStatement),
+    // Tokens descartados al recuperarse de un error de sintaxis.
+    Error(Span),
+}
+
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Expression(expr) => expr.span(),
+            Statement::Return(stmt) => stmt.span,
+            Statement::If(stmt) => stmt.span,
+            Statement::Block(block) => block.span,
+            Statement::While(stmt) => stmt.span,
+            Statement::For(stmt) => stmt.span,
+            Statement::DoUntil(stmt) => stmt.span,
+            Statement::Break(stmt) | Statement::Continue(stmt) => stmt.span,
+            Statement::Match(stmt) => stmt.span,
+            Statement::Error(span) => *span,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Block {
     pub statements: Vec<Declaration>, // Un bloque puede tener declaraciones y sentencias
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -198,9 +449,19 @@ pub enum ElseBranch {
     Block(Box<Statement>),
 }
 
+impl ElseBranch {
+    pub fn span(&self) -> Span {
+        match self {
+            ElseBranch::If(if_stmt) => if_stmt.span,
+            ElseBranch::Block(stmt) => stmt.span(),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct ReturnStatement {
     pub value: Expression,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -208,26 +469,95 @@ pub struct IfStatement {
     pub condition: Expression,
     pub then_block: Block,
     pub else_block: Option<ElseBranch>,
+    pub span: Span,
 }
 
+// `label` es la etiqueta opcional del bucle (`'outer: while ...`), sin la comilla.
 #[derive(Debug, PartialEq, Clone)]
 pub struct WhileStatement {
+    pub label: Option<Identifier>,
     pub condition: Expression,
     pub body: Block,
+    pub span: Span,
 }
 
 // --- NUEVA ESTRUCTURA PARA DO-UNTIL ---
 #[derive(Debug, PartialEq, Clone)]
 pub struct DoUntilStatement {
+    pub label: Option<Identifier>,
     pub body: Block,
     pub condition: Expression,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct ForStatement {
+    pub label: Option<Identifier>,
     pub variable: Identifier,
     pub iterable: Expression,
     pub body: Block,
+    pub span: Span,
+}
+
+// `break` o `continue`, con la etiqueta del bucle al que salta si no es el más interno.
+#[derive(Debug, PartialEq, Clone)]
+pub struct JumpStatement {
+    pub label: Option<Identifier>,
+    pub span: Span,
+}
+
+// `match valor { patrón => { ... } ... }`: se ejecuta el primer brazo cuyo patrón encaja.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchStatement {
+    pub subject: Expression,
+    pub arms: Vec<MatchArm>,
+    pub span: Span,
+}
+
+// Brazo de un `match`; con guarda (`patrón if condición`) solo se elige si además se cumple la condición.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expression>,
+    pub body: Block,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    Wildcard(Span),      // `_`: encaja con cualquier valor
+    Binding(Identifier), // Encaja con cualquier valor y lo guarda en una variable
+    Literal(Literal, Span),
+    // Límites literales, como en `1..=9` o `'a'..'z'`
+    Range { start: Literal, end: Literal, inclusive: bool, span: Span },
+    // `Point { x = 0, y }`: los campos que no se nombran encajan con cualquier valor
+    Struct { name: Identifier, fields: Vec<FieldPattern>, span: Span },
+    // `Shape::Rect(w, _)`: la variante y un patrón por cada valor de su carga útil
+    Variant { enum_name: Identifier, variant: Identifier, payload: Vec<Pattern>, span: Span },
+    // Patrón que no se pudo analizar.
+    Error(Span),
+}
+
+impl Pattern {
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Binding(ident) => ident.span,
+            Pattern::Wildcard(span)
+            | Pattern::Literal(_, span)
+            | Pattern::Range { span, .. }
+            | Pattern::Struct { span, .. }
+            | Pattern::Variant { span, .. }
+            | Pattern::Error(span) => *span,
+        }
+    }
+}
+
+// Campo de un patrón de struct; `campo` a secas equivale a `campo = campo`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FieldPattern {
+    pub name: Identifier,
+    pub pattern: Pattern,
+    pub span: Span,
 }
 
 // --- Declaraciones de Alto Nivel ---
@@ -237,15 +567,41 @@ pub enum Declaration {
     Function(Function),
     Variable(VariableDeclaration),
     Struct(StructDeclaration),
+    Enum(EnumDeclaration),
     Constant(ConstantDeclaration),
+    Import(ImportDeclaration),
     Statement(Statement), 
 }
 
+impl Declaration {
+    pub fn span(&self) -> Span {
+        match self {
+            Declaration::Function(func) => func.span,
+            Declaration::Variable(var) => var.span,
+            Declaration::Struct(decl) => decl.span,
+            Declaration::Enum(decl) => decl.span,
+            Declaration::Constant(decl) => decl.span,
+            Declaration::Import(decl) => decl.span,
+            Declaration::Statement(stmt) => stmt.span(),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct ConstantDeclaration {
     pub identifier: Identifier,
     pub const_type: Option<Type>,
     pub value: Expression,
+    pub doc: Option<String>, // Comentario de documentación que precede a la declaración
+    pub span: Span,
+}
+
+/// `import "geometry.dream";` o `import geometry.shapes;`. Una ruta de módulo
+/// se guarda ya convertida en la del archivo (`geometry/shapes.dream`).
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImportDeclaration {
+    pub path: String,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -253,6 +609,8 @@ pub struct VariableDeclaration {
     pub identifier: Identifier,
     pub var_type: Option<Type>,
     pub value: Expression,
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -261,24 +619,48 @@ pub struct Function {
     pub parameters: Vec<Parameter>,
     pub return_type: Type,
     pub body: Block,
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Parameter {
     pub name: Identifier,
     pub param_type: Type,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct StructDeclaration {
     pub name: Identifier,
     pub fields: Vec<FieldDeclaration>,
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct FieldDeclaration {
     pub name: Identifier,
     pub field_type: Type,
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct EnumDeclaration {
+    pub name: Identifier,
+    pub variants: Vec<VariantDeclaration>,
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+// Variante de un enum; `payload` son los tipos de los valores que lleva, en orden.
+#[derive(Debug, PartialEq, Clone)]
+pub struct VariantDeclaration {
+    pub name: Identifier,
+    pub payload: Vec<Type>,
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
 // --- Raíz del AST y Resultado del Parseo ---
@@ -286,6 +668,7 @@ pub struct FieldDeclaration {
 #[derive(Debug, PartialEq)]
 pub struct Program {
     pub declarations: Vec<Declaration>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
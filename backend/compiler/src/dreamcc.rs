@@ -4,8 +4,8 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use compiler::lexer::LexicalAnalyzer;
-use compiler::parser::parse_tokens;
+use compiler::lexer::{LexError, LexicalAnalyzer};
+use compiler::modules::{link, load_program};
 use compiler::token::TokenType;
 use compiler::semantic_analyzer::SemanticAnalyzer;
 use compiler::llvm_compiler::compile_to_llvm_ir;
@@ -16,7 +16,7 @@ use compiler::llvm_compiler::compile_to_llvm_ir;
 #[command(version = "1.0")]
 #[command(about = "Dream Language Compiler - Compiles .dream files to native executables", long_about = None)]
 struct Cli {
-    /// Input source file (.dream)
+    /// Input source file (.dream); the files it imports are resolved relative to it
     #[arg(value_name = "FILE")]
     input: PathBuf,
 
@@ -111,69 +111,69 @@ fn print_success(msg: &str) {
     println!("{} {}", "✓".green().bold(), msg.green());
 }
 
-fn compile(cli: Cli) -> Result<(), String> {
-    // Read source file
-    let source = fs::read_to_string(&cli.input)
-        .map_err(|e| format!("Failed to read input file: {}", e))?;
+fn check_lex_errors(path: &Path, errors: &[LexError]) -> Result<(), String> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    print_error(&format!("Lexical errors found in {}:", path.display()));
+    for error in errors {
+        eprintln!("  {}", error);
+    }
+    Err("Compilation failed due to lexical errors".to_string())
+}
 
+fn compile(cli: Cli) -> Result<(), String> {
     print_stage(&format!("Reading {}", cli.input.display()), cli.verbose);
 
-    // Stage 1: Lexical Analysis
-    print_stage("Lexical Analysis", cli.verbose);
-    let mut lexer = LexicalAnalyzer::new(&source);
-    let tokens = lexer.scan_tokens();
-
-    if cli.verbose {
-        let token_count = tokens.iter()
-            .filter(|t| !matches!(t.token_type, 
-                TokenType::Whitespace | TokenType::NewLine | 
-                TokenType::CommentSingle | TokenType::CommentMultiLine))
-            .count();
-        println!("  {} tokens found", token_count);
-    }
-
     if cli.lex_only {
-        for token in tokens.iter().filter(|t| !matches!(t.token_type, 
+        let source = fs::read_to_string(&cli.input)
+            .map_err(|e| format!("Failed to read input file: {}", e))?;
+        let mut lexer = LexicalAnalyzer::new(&source);
+        for token in lexer.by_ref().filter(|t| !matches!(t.token_type,
             TokenType::Whitespace | TokenType::NewLine)) {
             println!("{:?} '{}'", token.token_type, token.lexeme);
         }
-        return Ok(());
+        return check_lex_errors(&cli.input, &lexer.errors);
     }
 
-    let filtered_tokens: Vec<_> = tokens
-        .into_iter()
-        .filter(|t| !matches!(
-            t.token_type,
-            TokenType::Whitespace | TokenType::NewLine | 
-            TokenType::CommentSingle | TokenType::CommentMultiLine | TokenType::Unknown
-        ))
-        .collect();
-
-    // Stage 2: Parsing
-    print_stage("Parsing", cli.verbose);
-    let parse_result = parse_tokens(&filtered_tokens);
-
-    if !parse_result.errors.is_empty() {
-        print_error("Syntax errors found:");
-        for error in &parse_result.errors {
-            eprintln!("  {:?}", error);
+    // Stage 1-2: Lexical Analysis y Parsing de cada archivo, siguiendo los import
+    // desde el principal (el parser pide los tokens al lexer a medida que los necesita)
+    print_stage("Lexical Analysis and Parsing", cli.verbose);
+    let (modules, import_errors) = load_program(&cli.input, &|path: &Path| fs::read_to_string(path).ok());
+    if !import_errors.is_empty() {
+        print_error("Import errors found:");
+        for error in &import_errors {
+            eprintln!("  {}", error);
         }
-        return Err("Compilation failed due to syntax errors".to_string());
+        return Err("Compilation failed due to import errors".to_string());
     }
 
-    if cli.verbose {
-        println!("  {} declarations parsed", parse_result.ast.declarations.len());
+    for module in &modules {
+        if cli.verbose {
+            println!("  {}: {} declarations", module.path.display(), module.ast.declarations.len());
+        }
+        check_lex_errors(&module.path, &module.lex_errors)?;
+
+        if !module.syntax_errors.is_empty() {
+            print_error(&format!("Syntax errors found in {}:", module.path.display()));
+            for error in &module.syntax_errors {
+                eprintln!("  {}", error);
+            }
+            return Err("Compilation failed due to syntax errors".to_string());
+        }
     }
 
+    let program = link(&modules);
+
     if cli.parse_only {
-        println!("{:#?}", parse_result.ast);
+        println!("{:#?}", program);
         return Ok(());
     }
 
     // Stage 3: Semantic Analysis
     print_stage("Semantic Analysis", cli.verbose);
     let mut semantic_analyzer = SemanticAnalyzer::new();
-    semantic_analyzer.analyze(&parse_result.ast);
+    semantic_analyzer.analyze_modules(&modules);
 
     if !semantic_analyzer.errors.is_empty() {
         print_error("Semantic errors found:");
@@ -184,8 +184,10 @@ fn compile(cli: Cli) -> Result<(), String> {
     }
 
     if cli.verbose {
-        println!("  {} symbols in table", 
-            semantic_analyzer.symbol_table.current_scope.symbols.len());
+        // Cada archivo tiene su ámbito, hijo del global
+        let root = semantic_analyzer.symbol_table.get_root_scope();
+        println!("  {} symbols in {} modules",
+            root.children.iter().map(|module| module.symbols.len()).sum::<usize>(), root.children.len());
     }
 
     if cli.semantic_only {
@@ -200,7 +202,7 @@ fn compile(cli: Cli) -> Result<(), String> {
 
     // Stage 4: LLVM IR Generation
     print_stage("LLVM IR Generation", cli.verbose);
-    let llvm_ir = compile_to_llvm_ir(&parse_result.ast)?;
+    let llvm_ir = compile_to_llvm_ir(&program)?;
 
     // Write LLVM IR
     fs::write(&ctx.llvm_ir_path, &llvm_ir)
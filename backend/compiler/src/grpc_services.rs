@@ -2,11 +2,14 @@
 
 use crate::ast;
 use crate::ast::*;
-use crate::lexer::LexicalAnalyzer;
-use crate::parser::parse_tokens;
+use crate::lexer::{LexError, LexicalAnalyzer};
+use crate::modules::{link, load_program, normalize, Module, ModuleError};
+use crate::parser::{self, parse_tokens};
 use crate::semantic_analyzer::{SemanticAnalyzer, SemanticError as AstSemanticError};
-use crate::symbol_table::{Scope, Symbol, SymbolTable};
-use crate::token::{LexerToken, TokenType};
+use crate::symbol_table::{Scope, Symbol, SymbolTable, MODULE_SCOPE_PREFIX};
+use crate::token::{LexerToken, LineIndex, Position, PositionEncoding, TokenType};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use tonic::{Request, Response, Status};
 
 pub mod compiler {
@@ -17,8 +20,8 @@ use compiler::{
     compiler_server::Compiler,
     lexer_server::Lexer,
     parser_server::Parser,
-    AnalyzeRequest, AnnotatedNode, AstNode, CompilerRequest, CompilerResponse, ParseRequest,
-    ParseResponse, ParseSourceRequest, ParserError, SemanticAnalysisResponse,
+    AnalyzeRequest, AnnotatedNode, AstNode, CompilerRequest, CompilerResponse, LexerError, ParseRequest,
+    ParseResponse, ParseSourceRequest, ParserError, PositionEncoding as ProtoPositionEncoding, SemanticAnalysisResponse,
     SemanticError as ProtoSemanticError, Token, TokenList, Program as ProtoProgram,
     LlvmTranslateResponse, LlvmOptimizeResponse, ExecuteResponse
 };
@@ -34,7 +37,8 @@ impl Lexer for LexerService {
         &self,
         request: Request<AnalyzeRequest>,
     ) -> std::result::Result<Response<TokenList>, Status> {
-        let input_str = request.into_inner().input;
+        let AnalyzeRequest { input: input_str, position_encoding } = request.into_inner();
+        let encoding = position_encoding_from_proto(position_encoding);
         let mut analyzer = LexicalAnalyzer::new(&input_str);
         let tokens = analyzer.scan_tokens();
 
@@ -52,14 +56,19 @@ impl Lexer for LexerService {
             })
             .map(|t| Token {
                 token_type: t.token_type.to_string(),
-                lexeme: t.lexeme,
                 line: t.line as u32,
-                column: t.column as u32,
+                column: t.start_position().column_in(encoding) as u32,
+                end_line: t.end_line as u32,
+                end_column: t.end_position().column_in(encoding) as u32,
+                start_offset: t.start as u32,
+                end_offset: t.end as u32,
+                lexeme: t.lexeme.into_owned(),
             })
             .collect::<Vec<_>>();
 
         Ok(Response::new(TokenList {
             tokens: token_list_proto,
+            errors: lex_errors_to_proto(&analyzer.errors, encoding),
         }))
     }
 }
@@ -78,11 +87,21 @@ impl Parser for ParserService {
         let proto_tokens = request.into_inner().tokens;
         let tokens: Vec<LexerToken> = proto_tokens
             .into_iter()
-            .map(|t| LexerToken {
-                token_type: TokenType::from_str(&t.token_type).unwrap_or(TokenType::Unknown),
-                lexeme: t.lexeme,
-                line: t.line as usize,
-                column: t.column as usize,
+            .map(|t| {
+                // Sin el texto fuente no se conocen las demás unidades: las columnas se usan tal cual.
+                let position = |line: u32, column: u32, offset: u32| Position {
+                    line: line as usize,
+                    column: column as usize,
+                    offset: offset as usize,
+                    utf8_column: column as usize,
+                    utf16_column: column as usize,
+                };
+                LexerToken::new(
+                    TokenType::from_str(&t.token_type).unwrap_or(TokenType::Unknown),
+                    t.lexeme,
+                    position(t.line, t.column, t.start_offset),
+                    position(t.end_line, t.end_column, t.end_offset),
+                )
             })
             .collect();
 
@@ -97,27 +116,29 @@ impl Parser for ParserService {
         &self,
         request: Request<ParseSourceRequest>,
     ) -> Result<Response<ParseResponse>, Status> {
-        let source_code = request.into_inner().source;
-        let mut lexer = LexicalAnalyzer::new(&source_code);
-        let tokens = lexer.scan_tokens();
-        let filtered_tokens: Vec<LexerToken> = tokens
-            .into_iter()
-            .filter(|t| {
-                !matches!(
-                    t.token_type,
-                    TokenType::Whitespace
-                        | TokenType::NewLine
-                        | TokenType::CommentSingle
-                        | TokenType::CommentMultiLine
-                        | TokenType::Unknown
-                )
-            })
-            .collect();
-        let ParseResult { ast, errors } = parse_tokens(&filtered_tokens);
-        Ok(Response::new(ParseResponse {
+        let ParseSourceRequest { source: source_code, position_encoding } = request.into_inner();
+        let encoding = position_encoding_from_proto(position_encoding);
+        let (ParseResult { ast, errors }, lex_errors) = parser::parse_source(&source_code);
+
+        let mut response = ParseResponse {
             ast: Some(program_to_proto(&ast)),
             errors: errors_to_proto(&errors),
-        }))
+        };
+        encode_parse_response(&mut response, &LineIndex::new(&source_code), encoding);
+
+        // Los errores léxicos se reportan junto a los de sintaxis para no perderlos.
+        let lex_errors = lex_errors.iter().map(|e| ParserError {
+            error_type: e.kind.name().to_string(),
+            message: e.kind.to_string(),
+            line: e.start.line as u32,
+            column: e.start.column_in(encoding) as u32,
+            end_line: e.end.line as u32,
+            end_column: e.end.column_in(encoding) as u32,
+            ..Default::default()
+        });
+        response.errors.splice(0..0, lex_errors);
+
+        Ok(Response::new(response))
     }
 }
 
@@ -132,48 +153,36 @@ impl Compiler for CompilerService {
         &self,
         request: Request<CompilerRequest>,
     ) -> Result<Response<CompilerResponse>, Status> {
-        let source_code = request.into_inner().source;
-
-        // 1. Lexer
-        let mut lexer = LexicalAnalyzer::new(&source_code);
-        let tokens = lexer.scan_tokens();
-        let filtered_tokens: Vec<LexerToken> = tokens
-            .into_iter()
-            .filter(|t| {
-                !matches!(
-                    t.token_type,
-                    TokenType::Whitespace
-                        | TokenType::NewLine
-                        | TokenType::CommentSingle
-                        | TokenType::CommentMultiLine
-                        | TokenType::Unknown
-                )
-            })
-            .collect();
-
-        // 2. Parser
-        let ParseResult {
-            ast,
-            errors: parse_errors,
-        } = parse_tokens(&filtered_tokens);
-        let parse_response = ParseResponse {
-            ast: Some(program_to_proto(&ast)),
-            errors: errors_to_proto(&parse_errors),
+        let request = request.into_inner();
+        let encoding = position_encoding_from_proto(request.position_encoding);
+
+        // 1-2. Lexer y Parser de cada archivo, siguiendo los import desde el principal
+        let (modules, load_errors) = load_request(&request);
+        let entry = modules.last().ok_or_else(|| Status::internal("Failed to load the entry file"))?;
+        let indexes = LineIndexes::new(&modules);
+        let mut parse_response = ParseResponse {
+            ast: Some(program_to_proto(&entry.ast)),
+            errors: errors_to_proto(&entry.syntax_errors),
         };
+        encode_parse_response(&mut parse_response, indexes.get(""), encoding);
 
-        // 3. Semantic Analyzer
+        // 3. Semantic Analyzer, con todos los módulos en la misma tabla de símbolos
         let mut semantic_analyzer = SemanticAnalyzer::new();
-        let annotated_ast = semantic_analyzer.analyze(&ast); // Ahora analyze devuelve el AST anotado
+        let annotated_ast = semantic_analyzer.analyze_modules(&modules);
 
-        let semantic_response = SemanticAnalysisResponse {
-            errors: semantic_errors_to_proto(&semantic_analyzer.errors),
+        let mut errors = module_errors_to_proto(&modules, &load_errors);
+        errors.extend(semantic_errors_to_proto(&semantic_analyzer.errors));
+        let mut semantic_response = SemanticAnalysisResponse {
+            errors,
             symbol_table: Some(symbol_table_to_proto(&semantic_analyzer.symbol_table)),
             annotated_ast: Some(annotated_ast),
         };
+        encode_semantic_response(&mut semantic_response, &indexes, encoding);
 
         Ok(Response::new(CompilerResponse {
             parse_response: Some(parse_response),
             semantic_response: Some(semantic_response),
+            lexer_errors: lex_errors_to_proto(&entry.lex_errors, encoding),
         }))
     }
 
@@ -188,26 +197,12 @@ impl Compiler for CompilerService {
         &self,
         request: Request<CompilerRequest>,
     ) -> Result<Response<AnnotatedNode>, Status> {
-        let source_code = request.into_inner().source;
-        let mut lexer = LexicalAnalyzer::new(&source_code);
-        let tokens = lexer.scan_tokens();
-        let filtered_tokens: Vec<LexerToken> = tokens
-            .into_iter()
-            .filter(|t| {
-                !matches!(
-                    t.token_type,
-                    TokenType::Whitespace
-                        | TokenType::NewLine
-                        | TokenType::CommentSingle
-                        | TokenType::CommentMultiLine
-                        | TokenType::Unknown
-                )
-            })
-            .collect();
-
-        let ParseResult { ast, .. } = parse_tokens(&filtered_tokens);
+        let request = request.into_inner();
+        let (modules, _) = load_request(&request);
         let mut semantic_analyzer = SemanticAnalyzer::new();
-        let annotated_ast = semantic_analyzer.analyze(&ast);
+        let mut annotated_ast = semantic_analyzer.analyze_modules(&modules);
+        let encoding = position_encoding_from_proto(request.position_encoding);
+        encode_annotated_columns(&mut annotated_ast, LineIndexes::new(&modules).get(""), encoding);
 
         Ok(Response::new(annotated_ast))
     }
@@ -216,48 +211,8 @@ impl Compiler for CompilerService {
         &self,
         request: Request<CompilerRequest>,
     ) -> Result<Response<LlvmTranslateResponse>, Status> {
-        let source_code = request.into_inner().source;
-
-        // 1. Lexer
-        let mut lexer = LexicalAnalyzer::new(&source_code);
-        let tokens = lexer.scan_tokens();
-        let filtered_tokens: Vec<LexerToken> = tokens
-            .into_iter()
-            .filter(|t| {
-                !matches!(
-                    t.token_type,
-                    TokenType::Whitespace
-                        | TokenType::NewLine
-                        | TokenType::CommentSingle
-                        | TokenType::CommentMultiLine
-                        | TokenType::Unknown
-                )
-            })
-            .collect();
-
-        // 2. Parser
-        let ParseResult {
-            ast,
-            errors: parse_errors,
-        } = parse_tokens(&filtered_tokens);
-
-        if !parse_errors.is_empty() {
-            return Err(Status::invalid_argument(format!(
-                "Syntax errors found: {:?}",
-                parse_errors
-            )));
-        }
-
-        // 3. Semantic Analyzer
-        let mut semantic_analyzer = SemanticAnalyzer::new();
-        semantic_analyzer.analyze(&ast);
-
-        if !semantic_analyzer.errors.is_empty() {
-            return Err(Status::invalid_argument(format!(
-                "Semantic errors found: {:?}",
-                semantic_analyzer.errors
-            )));
-        }
+        // 1-3. Lexer, Parser y Semantic Analyzer de todos los archivos del programa
+        let ast = check_program(&request.into_inner()).map_err(Status::invalid_argument)?;
 
         // 4. LLVM Compilation
         match compile_to_llvm_ir(&ast) {
@@ -274,48 +229,8 @@ impl Compiler for CompilerService {
         &self,
         request: Request<CompilerRequest>,
     ) -> Result<Response<LlvmOptimizeResponse>, Status> {
-        let source_code = request.into_inner().source;
-
-        // 1. Lexer
-        let mut lexer = LexicalAnalyzer::new(&source_code);
-        let tokens = lexer.scan_tokens();
-        let filtered_tokens: Vec<LexerToken> = tokens
-            .into_iter()
-            .filter(|t| {
-                !matches!(
-                    t.token_type,
-                    TokenType::Whitespace
-                        | TokenType::NewLine
-                        | TokenType::CommentSingle
-                        | TokenType::CommentMultiLine
-                        | TokenType::Unknown
-                )
-            })
-            .collect();
-
-        // 2. Parser
-        let ParseResult {
-            ast,
-            errors: parse_errors,
-        } = parse_tokens(&filtered_tokens);
-
-        if !parse_errors.is_empty() {
-            return Err(Status::invalid_argument(format!(
-                "Syntax errors found: {:?}",
-                parse_errors
-            )));
-        }
-
-        // 3. Semantic Analyzer
-        let mut semantic_analyzer = SemanticAnalyzer::new();
-        semantic_analyzer.analyze(&ast);
-
-        if !semantic_analyzer.errors.is_empty() {
-            return Err(Status::invalid_argument(format!(
-                "Semantic errors found: {:?}",
-                semantic_analyzer.errors
-            )));
-        }
+        // 1-3. Lexer, Parser y Semantic Analyzer de todos los archivos del programa
+        let ast = check_program(&request.into_inner()).map_err(Status::invalid_argument)?;
 
         // 4. LLVM Compilation
         let llvm_ir = match compile_to_llvm_ir(&ast) {
@@ -374,48 +289,8 @@ impl Compiler for CompilerService {
         &self,
         request: Request<CompilerRequest>,
     ) -> Result<Response<ExecuteResponse>, Status> {
-        let source_code = request.into_inner().source;
-
-        // 1. Lexer
-        let mut lexer = LexicalAnalyzer::new(&source_code);
-        let tokens = lexer.scan_tokens();
-        let filtered_tokens: Vec<LexerToken> = tokens
-            .into_iter()
-            .filter(|t| {
-                !matches!(
-                    t.token_type,
-                    TokenType::Whitespace
-                        | TokenType::NewLine
-                        | TokenType::CommentSingle
-                        | TokenType::CommentMultiLine
-                        | TokenType::Unknown
-                )
-            })
-            .collect();
-
-        // 2. Parser
-        let ParseResult {
-            ast,
-            errors: parse_errors,
-        } = parse_tokens(&filtered_tokens);
-
-        if !parse_errors.is_empty() {
-            return Err(Status::invalid_argument(format!(
-                "Syntax errors found: {:?}",
-                parse_errors
-            )));
-        }
-
-        // 3. Semantic Analyzer
-        let mut semantic_analyzer = SemanticAnalyzer::new();
-        semantic_analyzer.analyze(&ast);
-
-        if !semantic_analyzer.errors.is_empty() {
-            return Err(Status::invalid_argument(format!(
-                "Semantic errors found: {:?}",
-                semantic_analyzer.errors
-            )));
-        }
+        // 1-3. Lexer, Parser y Semantic Analyzer de todos los archivos del programa
+        let ast = check_program(&request.into_inner()).map_err(Status::invalid_argument)?;
 
         // 4. LLVM Compilation
         let llvm_ir = match compile_to_llvm_ir(&ast) {
@@ -460,10 +335,192 @@ impl Compiler for CompilerService {
     }
 }
 
+// --- Programas de Varios Archivos ---
+
+/// Carga el programa de la petición: `source` es el archivo principal y
+/// `files`, los que puede importar.
+fn load_request(request: &CompilerRequest) -> (Vec<Module>, Vec<ModuleError>) {
+    let entry = normalize(Path::new(if request.path.is_empty() { "main.dream" } else { &request.path }));
+    let mut files: HashMap<PathBuf, String> = request
+        .files
+        .iter()
+        .map(|(path, source)| (normalize(Path::new(path)), source.clone()))
+        .collect();
+    files.insert(entry.clone(), request.source.clone());
+    load_program(&entry, &files)
+}
+
+/// Lleva el programa de la petición por el lexer, el parser y el analizador
+/// semántico y, si no hay errores, lo devuelve con todos sus archivos unidos.
+/// Si los hay, devuelve el mensaje con los de la primera etapa que falló.
+fn check_program(request: &CompilerRequest) -> Result<Program, String> {
+    let (modules, load_errors) = load_request(request);
+    if !load_errors.is_empty() {
+        return Err(format!("Import errors found: {}", join_errors(&load_errors)));
+    }
+
+    let lex_errors: Vec<_> = modules.iter().flat_map(|m| &m.lex_errors).collect();
+    if !lex_errors.is_empty() {
+        return Err(format!("Lexical errors found: {}", join_errors(&lex_errors)));
+    }
+
+    let parse_errors: Vec<_> = modules.iter().flat_map(|m| &m.syntax_errors).collect();
+    if !parse_errors.is_empty() {
+        return Err(format!("Syntax errors found: {}", join_errors(&parse_errors)));
+    }
+
+    let mut semantic_analyzer = SemanticAnalyzer::new();
+    semantic_analyzer.analyze_modules(&modules);
+
+    if !semantic_analyzer.errors.is_empty() {
+        // Los errores semánticos no implementan Display: se usa el mensaje que va en la respuesta.
+        let messages: Vec<String> = semantic_errors_to_proto(&semantic_analyzer.errors)
+            .into_iter()
+            .map(|e| format!("{} at line {}, column {}", e.message, e.line, e.column))
+            .collect();
+        return Err(format!("Semantic errors found: {}", messages.join("; ")));
+    }
+
+    Ok(link(&modules))
+}
+
+fn join_errors(errors: &[impl std::fmt::Display]) -> String {
+    errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+}
+
+/// Errores que no son del archivo principal ni semánticos: los `import` que no
+/// se pudieron cargar y los errores léxicos y de sintaxis de los archivos importados.
+fn module_errors_to_proto(modules: &[Module], load_errors: &[ModuleError]) -> Vec<ProtoSemanticError> {
+    let entry = modules.last().map(Module::name).unwrap_or_default();
+    let error = |message: String, span: Span, file: String| ProtoSemanticError {
+        message,
+        line: span.start.line as u32,
+        column: span.start.column as u32,
+        end_line: span.end.line as u32,
+        end_column: span.end.column as u32,
+        file: if file == entry { String::new() } else { file },
+    };
+
+    let mut errors: Vec<_> = load_errors
+        .iter()
+        .map(|e| {
+            let (importer, span) = match e {
+                ModuleError::FileNotFound(_, imported_from) => imported_from.clone().unwrap_or_default(),
+                ModuleError::ImportCycle(cycle, span) => (cycle[cycle.len() - 2].clone(), *span),
+            };
+            error(e.to_string(), span, importer.display().to_string())
+        })
+        .collect();
+    for module in &modules[..modules.len().saturating_sub(1)] {
+        errors.extend(module.lex_errors.iter().map(|e| error(e.kind.to_string(), Span::new(e.start, e.end), module.name())));
+        errors.extend(module.syntax_errors.iter().map(|e| error(e.to_string(), e.span(), module.name())));
+    }
+    errors
+}
+
+// --- Codificación de Columnas ---
+// El lexer conoce las columnas en todas las unidades, pero el AST y los errores
+// de sintaxis y semánticos solo las guardan en caracteres: se convierten al
+// final con el texto fuente.
+
+fn position_encoding_from_proto(value: i32) -> PositionEncoding {
+    match ProtoPositionEncoding::try_from(value) {
+        Ok(ProtoPositionEncoding::Utf16) => PositionEncoding::Utf16,
+        Ok(ProtoPositionEncoding::Utf8) => PositionEncoding::Utf8,
+        _ => PositionEncoding::Utf32,
+    }
+}
+
+fn encode_column(index: &LineIndex, encoding: PositionEncoding, line: u32, column: u32) -> u32 {
+    index.column(line as usize, column as usize, encoding) as u32
+}
+
+fn encode_parse_response(response: &mut ParseResponse, index: &LineIndex, encoding: PositionEncoding) {
+    if let Some(ast) = response.ast.as_mut() {
+        encode_ast_columns(ast, index, encoding);
+    }
+    for error in &mut response.errors {
+        error.column = encode_column(index, encoding, error.line, error.column);
+        error.end_column = encode_column(index, encoding, error.end_line, error.end_column);
+    }
+}
+
+fn encode_ast_columns(node: &mut AstNode, index: &LineIndex, encoding: PositionEncoding) {
+    node.start_column = encode_column(index, encoding, node.start_line, node.start_column);
+    node.end_column = encode_column(index, encoding, node.end_line, node.end_column);
+    for child in &mut node.children {
+        encode_ast_columns(child, index, encoding);
+    }
+}
+
+/// Texto de cada archivo del programa, por nombre de módulo, para convertir sus
+/// columnas. Las posiciones sin archivo, o de un archivo desconocido, son del
+/// archivo principal.
+struct LineIndexes<'src> {
+    entry: LineIndex<'src>,
+    indexes: HashMap<String, LineIndex<'src>>,
+}
+
+impl<'src> LineIndexes<'src> {
+    fn new(modules: &'src [Module]) -> Self {
+        LineIndexes {
+            entry: LineIndex::new(modules.last().map_or("", |m| m.source.as_str())),
+            indexes: modules.iter().map(|m| (m.name(), LineIndex::new(&m.source))).collect(),
+        }
+    }
+
+    fn get(&self, file: &str) -> &LineIndex<'src> {
+        self.indexes.get(file).unwrap_or(&self.entry)
+    }
+}
+
+fn encode_semantic_response(response: &mut SemanticAnalysisResponse, indexes: &LineIndexes, encoding: PositionEncoding) {
+    for error in &mut response.errors {
+        let index = indexes.get(&error.file);
+        error.column = encode_column(index, encoding, error.line, error.column);
+        error.end_column = encode_column(index, encoding, error.end_line, error.end_column);
+    }
+    if let Some(scope) = response.symbol_table.as_mut().and_then(|table| table.root_scope.as_mut()) {
+        encode_scope_columns(scope, indexes, "", encoding);
+    }
+    if let Some(ast) = response.annotated_ast.as_mut() {
+        encode_annotated_columns(ast, indexes.get(""), encoding);
+    }
+}
+
+// Los símbolos de cada módulo están en su ámbito y los de sus hijos.
+fn encode_scope_columns(scope: &mut compiler::Scope, indexes: &LineIndexes, file: &str, encoding: PositionEncoding) {
+    let file = scope.scope_name.strip_prefix(MODULE_SCOPE_PREFIX).unwrap_or(file);
+    let index = indexes.get(file);
+    for symbol in &mut scope.symbols {
+        symbol.column = encode_column(index, encoding, symbol.line, symbol.column);
+    }
+    for child in &mut scope.children {
+        encode_scope_columns(child, indexes, file, encoding);
+    }
+}
+
+fn encode_annotated_columns(node: &mut AnnotatedNode, index: &LineIndex, encoding: PositionEncoding) {
+    node.start_column = encode_column(index, encoding, node.start_line, node.start_column);
+    node.end_column = encode_column(index, encoding, node.end_line, node.end_column);
+    for child in &mut node.children {
+        encode_annotated_columns(child, index, encoding);
+    }
+}
+
 // --- Funciones de Conversión de AST a Protobuf ---
 
+/// Copia en el nodo la ubicación del fragmento de código del que proviene.
+fn with_span(mut node: AstNode, span: Span) -> AstNode {
+    node.start_line = span.start.line as u32;
+    node.start_column = span.start.column as u32;
+    node.end_line = span.end.line as u32;
+    node.end_column = span.end.column as u32;
+    node
+}
+
 fn program_to_proto(program: &Program) -> AstNode {
-    AstNode {
+    let node = AstNode {
         node_type: "Program".to_string(),
         value: "".to_string(),
         children: program
@@ -472,21 +529,25 @@ fn program_to_proto(program: &Program) -> AstNode {
             .map(declaration_to_proto)
             .collect(),
         ..Default::default()
-    }
+    };
+    with_span(node, program.span)
 }
 
 fn declaration_to_proto(decl: &Declaration) -> AstNode {
-    match decl {
+    let node = match decl {
         Declaration::Function(f) => function_to_proto(f),
         Declaration::Variable(v) => variable_decl_to_proto(v),
         Declaration::Struct(s) => struct_decl_to_proto(s),
+        Declaration::Enum(e) => enum_decl_to_proto(e),
         Declaration::Constant(c) => constant_decl_to_proto(c),
+        Declaration::Import(i) => import_decl_to_proto(i),
         Declaration::Statement(s) => statement_to_proto(s),
-    }
+    };
+    with_span(node, decl.span())
 }
 
 fn statement_to_proto(stmt: &Statement) -> AstNode {
-    match stmt {
+    let node = match stmt {
         Statement::Expression(e) => expression_to_proto(e),
         Statement::Return(r) => return_stmt_to_proto(r),
         Statement::If(i) => if_stmt_to_proto(i),
@@ -494,26 +555,50 @@ fn statement_to_proto(stmt: &Statement) -> AstNode {
         Statement::While(w) => while_stmt_to_proto(w),
         Statement::For(f) => for_stmt_to_proto(f),
         Statement::DoUntil(d) => do_until_stmt_to_proto(d),
-    }
+        Statement::Break(b) => jump_stmt_to_proto("Break", b),
+        Statement::Continue(c) => jump_stmt_to_proto("Continue", c),
+        Statement::Match(m) => match_stmt_to_proto(m),
+        Statement::Error(_) => error_to_proto(),
+    };
+    with_span(node, stmt.span())
 }
 
 fn expression_to_proto(expr: &Expression) -> AstNode {
-    match expr {
+    let node = match expr {
         Expression::Identifier(id) => identifier_to_proto(id),
-        Expression::Literal(lit) => literal_to_proto(lit),
-        Expression::Binary { left, op, right } => binary_expr_to_proto(left, op, right),
-        Expression::Unary { op, expr } => unary_expr_to_proto(op, expr),
-        Expression::Assignment { target, value } => assignment_to_proto(target, value),
-        Expression::Grouped(expr) => grouped_expr_to_proto(expr),
+        Expression::Literal(lit, _) => literal_to_proto(lit),
+        Expression::Binary { left, op, right, .. } => binary_expr_to_proto(left, op, right),
+        Expression::Unary { op, expr, .. } => unary_expr_to_proto(op, expr),
+        Expression::Assignment { target, value, .. } => assignment_to_proto(target, value),
+        Expression::CompoundAssignment { target, op, value, .. } => compound_assignment_to_proto(target, op, value),
+        Expression::Grouped(expr, _) => grouped_expr_to_proto(expr),
         Expression::FunctionCall {
             function,
             arguments,
+            ..
         } => func_call_to_proto(function, arguments),
-        Expression::Array(elements) => array_to_proto(elements),
-        Expression::Object(fields) => object_to_proto(fields),
-        Expression::Splat(expr) => splat_to_proto(expr),
-        Expression::StructInstantiation { name, fields } => struct_inst_to_proto(name, fields),
-        Expression::MemberAccess { object, property } => member_access_to_proto(object, property),
+        Expression::Array(elements, _) => array_to_proto(elements),
+        Expression::Index { object, index, .. } => index_to_proto(object, index),
+        Expression::Object(fields, _) => object_to_proto(fields),
+        Expression::Splat(expr, _) => splat_to_proto(expr),
+        Expression::StructInstantiation { name, fields, .. } => struct_inst_to_proto(name, fields),
+        Expression::MemberAccess { object, property, .. } => member_access_to_proto(object, property),
+        Expression::Range { start, end, inclusive, step, .. } => range_to_proto(start, end, *inclusive, step.as_deref()),
+        Expression::InterpolatedString(parts, _) => interpolated_string_to_proto(parts),
+        Expression::If { condition, then_branch, else_branch, .. } => if_expr_to_proto(condition, then_branch, else_branch),
+        Expression::Block { block, value } => block_expr_to_proto(block, value),
+        Expression::EnumVariant { enum_name, variant, arguments, .. } => enum_variant_to_proto(enum_name, variant, arguments),
+        Expression::Is { value, enum_name, variant, .. } => is_expr_to_proto(value, enum_name, variant),
+        Expression::Error(_) => error_to_proto(),
+    };
+    with_span(node, expr.span())
+}
+
+// Hueco que dejó el parser al recuperarse de un error de sintaxis.
+fn error_to_proto() -> AstNode {
+    AstNode {
+        node_type: "Error".to_string(),
+        ..Default::default()
     }
 }
 
@@ -540,11 +625,12 @@ fn function_to_proto(func: &Function) -> AstNode {
             .map(|p| {
                 let mut param_children = vec![identifier_to_proto(&p.name)];
                 param_children.push(type_to_proto(&p.param_type));
-                AstNode {
+                let node = AstNode {
                     node_type: "Parameter".to_string(),
                     children: param_children,
                     ..Default::default()
-                }
+                };
+                with_span(node, p.span)
             })
             .collect(),
         ..Default::default()
@@ -557,8 +643,6 @@ fn function_to_proto(func: &Function) -> AstNode {
             type_to_proto(&func.return_type),
             block_to_proto(&func.body),
         ],
-        start_line: func.name.line as u32,
-        start_column: func.name.column as u32,
         ..Default::default()
     }
 }
@@ -574,8 +658,6 @@ fn variable_decl_to_proto(decl: &VariableDeclaration) -> AstNode {
         node_type: "VariableDeclaration".to_string(),
         value: "let".to_string(),
         children,
-        start_line: decl.identifier.line as u32,
-        start_column: decl.identifier.column as u32,
         ..Default::default()
     }
 }
@@ -591,8 +673,14 @@ fn constant_decl_to_proto(decl: &ConstantDeclaration) -> AstNode {
         node_type: "ConstantDeclaration".to_string(),
         value: "const".to_string(),
         children,
-        start_line: decl.identifier.line as u32,
-        start_column: decl.identifier.column as u32,
+        ..Default::default()
+    }
+}
+
+fn import_decl_to_proto(decl: &ImportDeclaration) -> AstNode {
+    AstNode {
+        node_type: "ImportDeclaration".to_string(),
+        value: decl.path.clone(),
         ..Default::default()
     }
 }
@@ -603,10 +691,13 @@ fn struct_decl_to_proto(decl: &StructDeclaration) -> AstNode {
         children: decl
             .fields
             .iter()
-            .map(|f| AstNode {
-                node_type: "Field".to_string(),
-                children: vec![identifier_to_proto(&f.name), type_to_proto(&f.field_type)],
-                ..Default::default()
+            .map(|f| {
+                let node = AstNode {
+                    node_type: "Field".to_string(),
+                    children: vec![identifier_to_proto(&f.name), type_to_proto(&f.field_type)],
+                    ..Default::default()
+                };
+                with_span(node, f.span)
             })
             .collect(),
         ..Default::default()
@@ -615,8 +706,27 @@ fn struct_decl_to_proto(decl: &StructDeclaration) -> AstNode {
         node_type: "StructDeclaration".to_string(),
         value: decl.name.name.clone(),
         children: vec![fields_node],
-        start_line: decl.name.line as u32,
-        start_column: decl.name.column as u32,
+        ..Default::default()
+    }
+}
+
+fn enum_decl_to_proto(decl: &EnumDeclaration) -> AstNode {
+    AstNode {
+        node_type: "EnumDeclaration".to_string(),
+        value: decl.name.name.clone(),
+        children: decl
+            .variants
+            .iter()
+            .map(|v| {
+                let node = AstNode {
+                    node_type: "Variant".to_string(),
+                    value: v.name.name.clone(),
+                    children: v.payload.iter().map(type_to_proto).collect(),
+                    ..Default::default()
+                };
+                with_span(node, v.span)
+            })
+            .collect(),
         ..Default::default()
     }
 }
@@ -639,11 +749,12 @@ fn if_stmt_to_proto(if_stmt: &IfStatement) -> AstNode {
             ElseBranch::If(nested_if) => if_stmt_to_proto(nested_if),
             ElseBranch::Block(block) => statement_to_proto(block),
         };
-        children.push(AstNode {
+        let node = AstNode {
             node_type: "Else".to_string(),
             children: vec![else_node],
             ..Default::default()
-        });
+        };
+        children.push(with_span(node, else_branch.span()));
     }
     AstNode {
         node_type: "If".to_string(),
@@ -652,9 +763,15 @@ fn if_stmt_to_proto(if_stmt: &IfStatement) -> AstNode {
     }
 }
 
+/// Nombre de la etiqueta de un bucle, o una cadena vacía si no tiene.
+fn label_name(label: &Option<Identifier>) -> String {
+    label.as_ref().map(|label| label.name.clone()).unwrap_or_default()
+}
+
 fn while_stmt_to_proto(while_stmt: &WhileStatement) -> AstNode {
     AstNode {
         node_type: "While".to_string(),
+        value: label_name(&while_stmt.label),
         children: vec![
             expression_to_proto(&while_stmt.condition),
             block_to_proto(&while_stmt.body),
@@ -666,6 +783,7 @@ fn while_stmt_to_proto(while_stmt: &WhileStatement) -> AstNode {
 fn do_until_stmt_to_proto(do_until_stmt: &DoUntilStatement) -> AstNode {
     AstNode {
         node_type: "DoUntil".to_string(),
+        value: label_name(&do_until_stmt.label),
         children: vec![
             block_to_proto(&do_until_stmt.body),
             expression_to_proto(&do_until_stmt.condition),
@@ -677,27 +795,111 @@ fn do_until_stmt_to_proto(do_until_stmt: &DoUntilStatement) -> AstNode {
 fn for_stmt_to_proto(for_stmt: &ForStatement) -> AstNode {
     AstNode {
         node_type: "For".to_string(),
+        value: label_name(&for_stmt.label),
         children: vec![
             identifier_to_proto(&for_stmt.variable),
             expression_to_proto(&for_stmt.iterable),
             block_to_proto(&for_stmt.body),
         ],
-        start_line: for_stmt.variable.line as u32,
-        start_column: for_stmt.variable.column as u32,
         ..Default::default()
     }
 }
 
-fn identifier_to_proto(id: &Identifier) -> AstNode {
+fn jump_stmt_to_proto(node_type: &str, jump: &JumpStatement) -> AstNode {
+    AstNode {
+        node_type: node_type.to_string(),
+        value: label_name(&jump.label),
+        ..Default::default()
+    }
+}
+
+fn match_stmt_to_proto(match_stmt: &MatchStatement) -> AstNode {
+    let mut children = vec![expression_to_proto(&match_stmt.subject)];
+    children.extend(match_stmt.arms.iter().map(match_arm_to_proto));
     AstNode {
+        node_type: "Match".to_string(),
+        children,
+        ..Default::default()
+    }
+}
+
+fn match_arm_to_proto(arm: &MatchArm) -> AstNode {
+    let mut children = vec![pattern_to_proto(&arm.pattern)];
+    if let Some(guard) = &arm.guard {
+        let node = AstNode {
+            node_type: "Guard".to_string(),
+            children: vec![expression_to_proto(guard)],
+            ..Default::default()
+        };
+        children.push(with_span(node, guard.span()));
+    }
+    children.push(block_to_proto(&arm.body));
+    let node = AstNode {
+        node_type: "MatchArm".to_string(),
+        children,
+        ..Default::default()
+    };
+    with_span(node, arm.span)
+}
+
+fn pattern_to_proto(pattern: &Pattern) -> AstNode {
+    let node = match pattern {
+        Pattern::Wildcard(_) => AstNode {
+            node_type: "WildcardPattern".to_string(),
+            value: "_".to_string(),
+            ..Default::default()
+        },
+        Pattern::Binding(id) => AstNode {
+            node_type: "BindingPattern".to_string(),
+            value: id.name.clone(),
+            ..Default::default()
+        },
+        Pattern::Literal(literal, _) => AstNode {
+            node_type: "LiteralPattern".to_string(),
+            children: vec![literal_to_proto(literal)],
+            ..Default::default()
+        },
+        Pattern::Range { start, end, inclusive, .. } => AstNode {
+            node_type: "RangePattern".to_string(),
+            value: if *inclusive { "..=" } else { ".." }.to_string(),
+            children: vec![literal_to_proto(start), literal_to_proto(end)],
+            ..Default::default()
+        },
+        Pattern::Struct { name, fields, .. } => AstNode {
+            node_type: "StructPattern".to_string(),
+            value: name.name.clone(),
+            children: fields
+                .iter()
+                .map(|field| {
+                    let node = AstNode {
+                        node_type: "FieldPattern".to_string(),
+                        value: field.name.name.clone(),
+                        children: vec![pattern_to_proto(&field.pattern)],
+                        ..Default::default()
+                    };
+                    with_span(node, field.span)
+                })
+                .collect(),
+            ..Default::default()
+        },
+        Pattern::Variant { enum_name, variant, payload, .. } => AstNode {
+            node_type: "VariantPattern".to_string(),
+            value: variant_path(enum_name, variant),
+            children: payload.iter().map(pattern_to_proto).collect(),
+            ..Default::default()
+        },
+        Pattern::Error(_) => error_to_proto(),
+    };
+    with_span(node, pattern.span())
+}
+
+fn identifier_to_proto(id: &Identifier) -> AstNode {
+    let node = AstNode {
         node_type: "Identifier".to_string(),
         value: id.name.clone(),
-        start_line: id.line as u32,
-        start_column: id.column as u32,
-        end_line: id.line as u32,
-        end_column: (id.column + id.name.len()) as u32,
         ..Default::default()
-    }
+    };
+    with_span(node, id.span)
 }
 
 fn literal_to_proto(lit: &Literal) -> AstNode {
@@ -706,6 +908,7 @@ fn literal_to_proto(lit: &Literal) -> AstNode {
         Literal::Float(f) => (f.to_string(), "FloatLiteral"),
         Literal::String(s) => (s.clone(), "StringLiteral"),
         Literal::Bool(b) => (b.to_string(), "BoolLiteral"),
+        Literal::Char(c) => (c.to_string(), "CharLiteral"),
     };
     AstNode {
         node_type: node_type.to_string(),
@@ -715,16 +918,9 @@ fn literal_to_proto(lit: &Literal) -> AstNode {
 }
 
 fn type_to_proto(ty: &Type) -> AstNode {
-    let type_str = match ty {
-        Type::Int => "int",
-        Type::Float => "float",
-        Type::String => "string",
-        Type::Bool => "bool",
-        Type::Void => "void",
-    };
     AstNode {
         node_type: "Type".to_string(),
-        value: type_str.to_string(),
+        value: type_name(ty),
         ..Default::default()
     }
 }
@@ -747,13 +943,120 @@ fn unary_expr_to_proto(op: &UnaryOp, expr: &Expression) -> AstNode {
     }
 }
 
-fn assignment_to_proto(target: &Identifier, value: &Expression) -> AstNode {
+fn type_name(ty: &Type) -> String {
+    match ty {
+        Type::Int => "int".to_string(),
+        Type::Float => "float".to_string(),
+        Type::String => "string".to_string(),
+        Type::Bool => "bool".to_string(),
+        Type::Char => "char".to_string(),
+        Type::Void => "void".to_string(),
+        Type::Array(element) => format!("[{}]", type_name(element)),
+        Type::FixedArray(element, length) => format!("[{}; {}]", type_name(element), length),
+        Type::Struct(name) | Type::Enum(name) => name.clone(),
+        Type::Function(parameters, return_type) => {
+            let parameters: Vec<String> = parameters.iter().map(type_name).collect();
+            format!("fn({}) -> {}", parameters.join(", "), type_name(return_type))
+        }
+        Type::Optional(inner) if matches!(**inner, Type::Function(..)) => format!("({})?", type_name(inner)),
+        Type::Optional(inner) => format!("{}?", type_name(inner)),
+    }
+}
+
+impl From<&Type> for compiler::Type {
+    fn from(ty: &Type) -> Self {
+        use compiler::{r#type::Kind, ArrayType, FunctionType, PrimitiveType};
+        let kind = match ty {
+            Type::Int => Kind::Primitive(PrimitiveType::Int as i32),
+            Type::Float => Kind::Primitive(PrimitiveType::Float as i32),
+            Type::String => Kind::Primitive(PrimitiveType::String as i32),
+            Type::Bool => Kind::Primitive(PrimitiveType::Bool as i32),
+            Type::Char => Kind::Primitive(PrimitiveType::Char as i32),
+            Type::Void => Kind::Primitive(PrimitiveType::Void as i32),
+            Type::Array(element) => Kind::Array(Box::new(ArrayType { element: Some(Box::new(element.as_ref().into())), length: None })),
+            Type::FixedArray(element, length) => Kind::Array(Box::new(ArrayType {
+                element: Some(Box::new(element.as_ref().into())),
+                length: Some(*length as u64),
+            })),
+            Type::Struct(name) | Type::Enum(name) => Kind::Named(name.clone()),
+            Type::Function(parameters, return_type) => Kind::Function(Box::new(FunctionType {
+                parameters: parameters.iter().map(compiler::Type::from).collect(),
+                return_type: Some(Box::new(return_type.as_ref().into())),
+            })),
+            Type::Optional(inner) => Kind::Optional(Box::new(inner.as_ref().into())),
+        };
+        compiler::Type { kind: Some(kind) }
+    }
+}
+
+fn assignment_to_proto(target: &Expression, value: &Expression) -> AstNode {
     AstNode {
         node_type: "Assignment".to_string(),
         value: "=".to_string(),
-        children: vec![identifier_to_proto(target), expression_to_proto(value)],
-        start_line: target.line as u32,
-        start_column: target.column as u32,
+        children: vec![expression_to_proto(target), expression_to_proto(value)],
+        ..Default::default()
+    }
+}
+
+fn compound_assignment_to_proto(target: &Expression, op: &BinaryOp, value: &Expression) -> AstNode {
+    AstNode {
+        node_type: "CompoundAssignment".to_string(),
+        value: format!("{}=", op.symbol()),
+        children: vec![expression_to_proto(target), expression_to_proto(value)],
+        ..Default::default()
+    }
+}
+
+fn range_to_proto(start: &Expression, end: &Expression, inclusive: bool, step: Option<&Expression>) -> AstNode {
+    AstNode {
+        node_type: "Range".to_string(),
+        value: if inclusive { "..=" } else { ".." }.to_string(),
+        children: [start, end].into_iter().chain(step).map(expression_to_proto).collect(),
+        ..Default::default()
+    }
+}
+
+fn if_expr_to_proto(condition: &Expression, then_branch: &Expression, else_branch: &Expression) -> AstNode {
+    AstNode {
+        node_type: "IfExpression".to_string(),
+        children: [condition, then_branch, else_branch].into_iter().map(expression_to_proto).collect(),
+        ..Default::default()
+    }
+}
+
+/// Las sentencias del bloque seguidas de la expresión que le da valor.
+fn block_expr_to_proto(block: &Block, value: &Expression) -> AstNode {
+    AstNode {
+        node_type: "BlockExpression".to_string(),
+        children: block
+            .statements
+            .iter()
+            .map(declaration_to_proto)
+            .chain(std::iter::once(expression_to_proto(value)))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+// `Enum::Variante`, tal como se escribe.
+fn variant_path(enum_name: &Identifier, variant: &Identifier) -> String {
+    format!("{}::{}", enum_name.name, variant.name)
+}
+
+fn enum_variant_to_proto(enum_name: &Identifier, variant: &Identifier, arguments: &[Expression]) -> AstNode {
+    AstNode {
+        node_type: "EnumVariant".to_string(),
+        value: variant_path(enum_name, variant),
+        children: arguments.iter().map(expression_to_proto).collect(),
+        ..Default::default()
+    }
+}
+
+fn is_expr_to_proto(value: &Expression, enum_name: &Identifier, variant: &Identifier) -> AstNode {
+    AstNode {
+        node_type: "IsExpression".to_string(),
+        value: variant_path(enum_name, variant),
+        children: vec![expression_to_proto(value)],
         ..Default::default()
     }
 }
@@ -785,15 +1088,41 @@ fn array_to_proto(elements: &[Expression]) -> AstNode {
     }
 }
 
+fn index_to_proto(object: &Expression, index: &Expression) -> AstNode {
+    AstNode {
+        node_type: "Index".to_string(),
+        value: "[]".to_string(),
+        children: vec![expression_to_proto(object), expression_to_proto(index)],
+        ..Default::default()
+    }
+}
+
+fn interpolated_string_to_proto(parts: &[InterpolationPart]) -> AstNode {
+    AstNode {
+        node_type: "InterpolatedString".to_string(),
+        children: parts
+            .iter()
+            .map(|part| match part {
+                InterpolationPart::Text(text) => literal_to_proto(&Literal::String(text.clone())),
+                InterpolationPart::Expression(expr) => expression_to_proto(expr),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
 fn object_to_proto(fields: &[(Identifier, Expression)]) -> AstNode {
     AstNode {
         node_type: "ObjectLiteral".to_string(),
         children: fields
             .iter()
-            .map(|(key, val)| AstNode {
-                node_type: "ObjectField".to_string(),
-                children: vec![identifier_to_proto(key), expression_to_proto(val)],
-                ..Default::default()
+            .map(|(key, val)| {
+                let node = AstNode {
+                    node_type: "ObjectField".to_string(),
+                    children: vec![identifier_to_proto(key), expression_to_proto(val)],
+                    ..Default::default()
+                };
+                with_span(node, key.span.to(val.span()))
             })
             .collect(),
         ..Default::default()
@@ -815,43 +1144,56 @@ fn struct_inst_to_proto(name: &Identifier, fields: &[(Identifier, Expression)])
         value: name.name.clone(),
         children: fields
             .iter()
-            .map(|(key, val)| AstNode {
-                node_type: "StructFieldInit".to_string(),
-                children: vec![identifier_to_proto(key), expression_to_proto(val)],
-                start_line: key.line as u32,
-                start_column: key.column as u32,
-                ..Default::default()
+            .map(|(key, val)| {
+                let node = AstNode {
+                    node_type: "StructFieldInit".to_string(),
+                    children: vec![identifier_to_proto(key), expression_to_proto(val)],
+                    ..Default::default()
+                };
+                with_span(node, key.span.to(val.span()))
             })
             .collect(),
-        start_line: name.line as u32,
-        start_column: name.column as u32,
         ..Default::default()
     }
 }
 
 fn block_to_proto(block: &Block) -> AstNode {
-    AstNode {
+    let node = AstNode {
         node_type: "Block".to_string(),
         children: block.statements.iter().map(declaration_to_proto).collect(),
         ..Default::default()
-    }
+    };
+    with_span(node, block.span)
+}
+
+fn lex_errors_to_proto(errors: &[LexError], encoding: PositionEncoding) -> Vec<LexerError> {
+    errors
+        .iter()
+        .map(|e| LexerError {
+            error_type: e.kind.name().to_string(),
+            message: e.kind.to_string(),
+            line: e.start.line as u32,
+            column: e.start.column_in(encoding) as u32,
+            end_line: e.end.line as u32,
+            end_column: e.end.column_in(encoding) as u32,
+        })
+        .collect()
 }
 
 fn errors_to_proto(errors: &[SyntaxError]) -> Vec<ParserError> {
     errors
         .iter()
         .map(|e| {
-            let (error_type, message, line, column) = match e {
-                SyntaxError::UnexpectedToken(msg, l, c) => {
-                    ("UnexpectedToken", msg.clone(), *l, *c)
-                }
-                _ => ("GenericError", format!("{}", e), 0, 0),
-            };
+            let span = e.span();
             ParserError {
-                error_type: error_type.to_string(),
-                message,
-                line: line as u32,
-                column: column as u32,
+                error_type: e.name().to_string(),
+                message: e.to_string(),
+                line: span.start.line as u32,
+                column: span.start.column as u32,
+                end_line: span.end.line as u32,
+                end_column: span.end.column as u32,
+                expected: e.expected().to_string(),
+                found: e.found().to_string(),
             }
         })
         .collect()
@@ -860,131 +1202,143 @@ fn errors_to_proto(errors: &[SyntaxError]) -> Vec<ParserError> {
 fn semantic_errors_to_proto(errors: &[AstSemanticError]) -> Vec<ProtoSemanticError> {
     errors
         .iter()
-        .map(|e| match e {
-            AstSemanticError::UndeclaredVariable(name, line, column) => ProtoSemanticError {
-                message: format!("Undeclared variable: {}", name),
-                line: *line as u32,
-                column: *column as u32,
-            },
-            AstSemanticError::RedeclaredVariable(name, line, column) => ProtoSemanticError {
-                message: format!("Redeclared variable: {}", name),
-                line: *line as u32,
-                column: *column as u32,
-            },
-            AstSemanticError::TypeMismatch(expected, found, line, column) => ProtoSemanticError {
-                message: format!("Type mismatch: expected {}, found {}", expected, found),
-                line: *line as u32,
-                column: *column as u32,
-            },
-            AstSemanticError::InvalidAssignment(name, line, column) => ProtoSemanticError {
-                message: format!("Invalid assignment to constant: {}", name),
-                line: *line as u32,
-                column: *column as u32,
-            },
-            AstSemanticError::UndefinedStruct(name, line, column) => ProtoSemanticError {
-                message: format!("Undefined struct: {}", name),
-                line: *line as u32,
-                column: *column as u32,
-            },
-            AstSemanticError::RedeclaredStruct(name, line, column) => ProtoSemanticError {
-                message: format!("Redeclared struct: {}", name),
-                line: *line as u32,
-                column: *column as u32,
-            },
-            AstSemanticError::RedeclaredField(struct_name, field_name, line, column) => {
-                ProtoSemanticError {
-                    message: format!(
-                        "Redeclared field '{}' in struct '{}'",
-                        field_name, struct_name
-                    ),
-                    line: *line as u32,
-                    column: *column as u32,
+        .map(|e| {
+            let (message, span) = match e {
+                AstSemanticError::UndeclaredVariable(name, span) => {
+                    (format!("Undeclared variable: {}", name), *span)
                 }
-            }
-            AstSemanticError::FieldNotFound(struct_name, field_name, line, column) => {
-                ProtoSemanticError {
-                    message: format!(
-                        "Field '{}' not found in struct '{}'",
-                        field_name, struct_name
+                AstSemanticError::RedeclaredVariable(name, span) => {
+                    (format!("Redeclared variable: {}", name), *span)
+                }
+                AstSemanticError::TypeMismatch(expected, found, span) => {
+                    (format!("Type mismatch: expected {}, found {}", expected, found), *span)
+                }
+                AstSemanticError::InvalidAssignment(name, span) => {
+                    (format!("Invalid assignment to constant: {}", name), *span)
+                }
+                AstSemanticError::UndefinedStruct(name, span) => {
+                    (format!("Undefined struct: {}", name), *span)
+                }
+                AstSemanticError::RedeclaredStruct(name, span) => {
+                    (format!("Redeclared struct: {}", name), *span)
+                }
+                AstSemanticError::RedeclaredField(struct_name, field_name, span) => (
+                    format!("Redeclared field '{}' in struct '{}'", field_name, struct_name),
+                    *span,
+                ),
+                AstSemanticError::FieldNotFound(struct_name, field_name, span) => (
+                    format!("Field '{}' not found in struct '{}'", field_name, struct_name),
+                    *span,
+                ),
+                AstSemanticError::InvalidMemberAccess(name, span) => {
+                    (format!("Invalid member access: {}", name), *span)
+                }
+                AstSemanticError::InvalidFunctionCallTarget(span) => {
+                    ("Invalid function call target".to_string(), *span)
+                }
+                AstSemanticError::UndefinedFunction(name, span) => {
+                    (format!("Undefined function: {}", name), *span)
+                }
+                AstSemanticError::ArgumentCountMismatch(func_name, expected, found, span) => (
+                    format!(
+                        "Argument count mismatch in function '{}': expected {}, found {}",
+                        func_name, expected, found
+                    ),
+                    *span,
+                ),
+                AstSemanticError::ArgumentTypeMismatch(func_name, arg_index, expected, found, span) => (
+                    format!(
+                        "Argument type mismatch in function '{}' at argument {}: expected {}, found {}",
+                        func_name, arg_index, expected, found
                     ),
-                    line: *line as u32,
-                    column: *column as u32,
+                    *span,
+                ),
+                AstSemanticError::ReturnOutsideFunction(span) => {
+                    ("Return statement outside function".to_string(), *span)
                 }
-            }
-            AstSemanticError::InvalidMemberAccess(name, line, column) => ProtoSemanticError {
-                message: format!("Invalid member access: {}", name),
-                line: *line as u32,
-                column: *column as u32,
-            },
-            AstSemanticError::InvalidFunctionCallTarget(line, column) => ProtoSemanticError {
-                message: "Invalid function call target".to_string(),
-                line: *line as u32,
-                column: *column as u32,
-            },
-            AstSemanticError::UndefinedFunction(name, line, column) => ProtoSemanticError {
-                message: format!("Undefined function: {}", name),
-                line: *line as u32,
-                column: *column as u32,
-            },
-            AstSemanticError::ArgumentCountMismatch(
-                func_name,
-                expected,
-                found,
-                line,
-                column,
-            ) => ProtoSemanticError {
-                message: format!(
-                    "Argument count mismatch in function '{}': expected {}, found {}",
-                    func_name, expected, found
+                AstSemanticError::ReturnTypeMismatch(expected, found, span) => (
+                    format!("Return type mismatch: expected {}, found {}", expected, found),
+                    *span,
                 ),
-                line: *line as u32,
-                column: *column as u32,
-            },
-            AstSemanticError::ArgumentTypeMismatch(
-                func_name,
-                arg_index,
-                expected,
-                found,
-                line,
-                column,
-            ) => ProtoSemanticError {
-                message: format!(
-                    "Argument type mismatch in function '{}' at argument {}: expected {}, found {}",
-                    func_name, arg_index, expected, found
+                AstSemanticError::MissingReturnStatement(func_name, span) => (
+                    format!("Missing return statement in function '{}'", func_name),
+                    *span,
                 ),
-                line: *line as u32,
-                column: *column as u32,
-            },
-            AstSemanticError::ReturnOutsideFunction(line, column) => ProtoSemanticError {
-                message: "Return statement outside function".to_string(),
-                line: *line as u32,
-                column: *column as u32,
-            },
-            AstSemanticError::ReturnTypeMismatch(expected, found, line, column) => {
-                ProtoSemanticError {
-                    message: format!("Return type mismatch: expected {}, found {}", expected, found),
-                    line: *line as u32,
-                    column: *column as u32,
+                AstSemanticError::MissingMainFunction => {
+                    ("Missing 'main' function".to_string(), Span::default())
                 }
-            }
-            AstSemanticError::MissingReturnStatement(func_name, line, column) => {
-                ProtoSemanticError {
-                    message: format!("Missing return statement in function '{}'", func_name),
-                    line: *line as u32,
-                    column: *column as u32,
+                AstSemanticError::InvalidMainFunctionSignature(reason, span) => {
+                    (format!("Invalid 'main' function signature: {}", reason), *span)
                 }
-            }
-            AstSemanticError::MissingMainFunction => ProtoSemanticError {
-                message: "Missing 'main' function".to_string(),
-                line: 0,
-                column: 0,
-            },
-            AstSemanticError::InvalidMainFunctionSignature(reason, line, column) => {
-                ProtoSemanticError {
-                    message: format!("Invalid 'main' function signature: {}", reason),
-                    line: *line as u32,
-                    column: *column as u32,
+                AstSemanticError::InvalidInterpolation(type_name, span) => {
+                    (format!("Cannot interpolate a value of type '{}'", type_name), *span)
+                }
+                AstSemanticError::MissingField(struct_name, field_name, span) => (
+                    format!("Missing field '{}' in instance of struct '{}'", field_name, struct_name),
+                    *span,
+                ),
+                AstSemanticError::InvalidIndexTarget(type_name, span) => {
+                    (format!("Cannot index a value of type '{}'", type_name), *span)
+                }
+                AstSemanticError::InvalidOperand(op, type_name, span) => {
+                    (format!("Operator '{}' cannot be applied to a value of type '{}'", op, type_name), *span)
+                }
+                AstSemanticError::JumpOutsideLoop(keyword, span) => {
+                    (format!("'{}' outside of a loop", keyword), *span)
+                }
+                AstSemanticError::UndefinedLabel(label, span) => {
+                    (format!("Undefined loop label '{}'", label), *span)
+                }
+                AstSemanticError::NotIterable(type_name, span) => {
+                    (format!("Cannot iterate over a value of type '{}'", type_name), *span)
+                }
+                AstSemanticError::RangeOutsideFor(span) => {
+                    ("A range can only be used as the iterable of a 'for' loop".to_string(), *span)
+                }
+                AstSemanticError::NonExhaustiveMatch(type_name, span) => {
+                    (format!("Match on a value of type '{}' does not cover every case; add a '_' arm", type_name), *span)
+                }
+                AstSemanticError::UnreachablePattern(span) => {
+                    ("Unreachable pattern: earlier arms already cover it".to_string(), *span)
                 }
+                AstSemanticError::UndefinedEnum(name, span) => {
+                    (format!("Undefined enum: {}", name), *span)
+                }
+                AstSemanticError::RedeclaredEnum(name, span) => {
+                    (format!("Redeclared enum: {}", name), *span)
+                }
+                AstSemanticError::UndefinedVariant(enum_name, variant, span) => (
+                    format!("Variant '{}' not found in enum '{}'", variant, enum_name),
+                    *span,
+                ),
+                AstSemanticError::RedeclaredVariant(enum_name, variant, span) => (
+                    format!("Redeclared variant '{}' in enum '{}'", variant, enum_name),
+                    *span,
+                ),
+                AstSemanticError::PayloadCountMismatch(variant, expected, found, span) => (
+                    format!("Variant '{}' carries {} value(s), found {}", variant, expected, found),
+                    *span,
+                ),
+                AstSemanticError::UndefinedType(name, span) => (
+                    format!("Undefined type '{}'", name),
+                    *span,
+                ),
+                AstSemanticError::MisplacedImport(span) => {
+                    ("Imports are only allowed at the top level of a file".to_string(), *span)
+                }
+                AstSemanticError::InModule(module, error) => {
+                    let mut error = semantic_errors_to_proto(std::slice::from_ref(error.as_ref())).remove(0);
+                    error.file = module.clone();
+                    return error;
+                }
+            };
+            ProtoSemanticError {
+                message,
+                line: span.start.line as u32,
+                column: span.start.column as u32,
+                end_line: span.end.line as u32,
+                end_column: span.end.column as u32,
+                file: String::new(),
             }
         })
         .collect()
@@ -1012,15 +1366,22 @@ fn symbol_to_proto(symbol: &Symbol, scope_level: usize) -> compiler::Symbol {
             ast::Literal::Float(f) => f.to_string(),
             ast::Literal::String(s) => s.clone(),
             ast::Literal::Bool(b) => b.to_string(),
+            ast::Literal::Char(c) => c.to_string(),
         }),
         Symbol::Constant { value, .. } => value.as_ref().map(|v| match v {
             ast::Literal::Int(i) => i.to_string(),
             ast::Literal::Float(f) => f.to_string(),
             ast::Literal::String(s) => s.clone(),
             ast::Literal::Bool(b) => b.to_string(),
+            ast::Literal::Char(c) => c.to_string(),
         }),
         _ => None,
     };
+    let doc = symbol.doc().map(str::to_string);
+    let r#type = match symbol {
+        Symbol::Struct { .. } | Symbol::Enum { .. } => None,
+        _ => Some(compiler::Type::from(&symbol.get_type())),
+    };
 
     match symbol {
         Symbol::Variable {
@@ -1037,6 +1398,8 @@ fn symbol_to_proto(symbol: &Symbol, scope_level: usize) -> compiler::Symbol {
             column: *column as u32,
             value: value_str,
             scope_level: scope_level as u32,
+            doc: doc.clone(),
+            r#type: r#type.clone(),
         },
         Symbol::Function {
             name,
@@ -1052,6 +1415,8 @@ fn symbol_to_proto(symbol: &Symbol, scope_level: usize) -> compiler::Symbol {
             column: *column as u32,
             value: None,
             scope_level: scope_level as u32,
+            doc: doc.clone(),
+            r#type: r#type.clone(),
         },
         Symbol::Struct {
             name,
@@ -1066,6 +1431,24 @@ fn symbol_to_proto(symbol: &Symbol, scope_level: usize) -> compiler::Symbol {
             column: *column as u32,
             value: None,
             scope_level: scope_level as u32,
+            doc: doc.clone(),
+            r#type: r#type.clone(),
+        },
+        Symbol::Enum {
+            name,
+            line,
+            column,
+            ..
+        } => compiler::Symbol {
+            name: name.clone(),
+            symbol_type: "Enum".to_string(),
+            data_type: "".to_string(),
+            line: *line as u32,
+            column: *column as u32,
+            value: None,
+            scope_level: scope_level as u32,
+            doc: doc.clone(),
+            r#type: r#type.clone(),
         },
         Symbol::Constant {
             name,
@@ -1081,6 +1464,8 @@ fn symbol_to_proto(symbol: &Symbol, scope_level: usize) -> compiler::Symbol {
             column: *column as u32,
             value: value_str,
             scope_level: scope_level as u32,
+            doc: doc.clone(),
+            r#type: r#type.clone(),
         },
     }
 }
\ No newline at end of file
@@ -0,0 +1,109 @@
+// Re-escaneo incremental: tras una edición solo se vuelve a escanear la región
+// afectada y el resto de los tokens anteriores se reutiliza, desplazado.
+
+use std::ops::Range;
+
+use crate::lexer::{LexError, LexicalAnalyzer};
+use crate::token::{LexerToken, Position};
+
+/// Cambio sobre el texto anterior: los bytes de `range` se sustituyen por `text`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// Resultado de aplicar una edición a una lista de tokens.
+#[derive(Debug, Clone)]
+pub struct RelexResult {
+    pub tokens: Vec<LexerToken>,
+    pub changed: Range<usize>,  // Índices de `tokens` que se escanearon de nuevo
+    pub replaced: Range<usize>, // Índices de la lista anterior a los que sustituyen
+    pub errors: Vec<LexError>,  // Errores léxicos de todo el texto nuevo
+}
+
+// Caracteres que el lexer puede examinar más allá del final de un token (ej. `1e+5`).
+// Como cada token ocupa al menos un carácter, basta con retroceder otros tantos tokens.
+const MAX_LOOKAHEAD: usize = 3;
+
+/// Actualiza `previous` y `previous_errors` (el resultado de `scan_tokens` sobre
+/// el texto anterior) para reflejar `edit`. `source` es el texto completo después
+/// de la edición.
+///
+/// El escaneo empieza unos tokens antes de la edición y se detiene en el primer
+/// token que, ya pasada la edición, empieza donde empezaba un token anterior:
+/// a partir de ahí el texto es idéntico y el lexer produciría lo mismo.
+pub fn relex(source: &str, previous: &[LexerToken], previous_errors: &[LexError], edit: &TextEdit) -> RelexResult {
+    let delta = edit.text.len() as isize - edit.range.len() as isize;
+    let edit_end = edit.range.start + edit.text.len(); // Fin de la edición en el texto nuevo
+
+    let touched = previous
+        .iter()
+        .position(|t| t.end >= edit.range.start)
+        .unwrap_or(previous.len());
+    let first = touched.saturating_sub(MAX_LOOKAHEAD);
+    let start = previous.get(first).map_or(Position::START, LexerToken::start_position);
+
+    let mut lexer = LexicalAnalyzer::starting_at(source, start);
+    let mut fresh = Vec::new();
+    let mut sync = None;
+    for token in lexer.by_ref() {
+        if token.start >= edit_end {
+            let old_start = (token.start as isize - delta) as usize;
+            if let Ok(index) = previous.binary_search_by_key(&old_start, |t| t.start) {
+                sync = Some((index, token));
+                break;
+            }
+        }
+        fresh.push(token.into_owned());
+    }
+
+    let resume = sync.as_ref().map_or(previous.len(), |(index, _)| *index);
+    // Los errores anteriores a la región re-escaneada no cambian.
+    let mut errors: Vec<LexError> =
+        previous_errors.iter().filter(|e| e.start.offset < start.offset).cloned().collect();
+    let mut fresh_errors = lexer.errors;
+    let mut tokens = previous[..first].to_vec();
+    tokens.extend(fresh);
+    let changed = first..tokens.len();
+
+    if let Some((index, anchor)) = sync {
+        // El token de sincronización puede haber registrado errores que ya existían.
+        fresh_errors.retain(|e| e.start.offset < anchor.start);
+        errors.append(&mut fresh_errors);
+        let old_anchor = previous[index].start_position();
+        let anchor = anchor.start_position();
+        errors.extend(previous_errors.iter().filter(|e| e.start.offset >= old_anchor.offset).map(|e| LexError {
+            kind: e.kind.clone(),
+            start: shift(e.start, &old_anchor, &anchor, delta),
+            end: shift(e.end, &old_anchor, &anchor, delta),
+        }));
+        tokens.extend(previous[resume..].iter().map(|t| {
+            let start = shift(t.start_position(), &old_anchor, &anchor, delta);
+            let end = shift(t.end_position(), &old_anchor, &anchor, delta);
+            LexerToken::new(t.token_type, t.lexeme.clone(), start, end)
+        }));
+    } else {
+        errors.append(&mut fresh_errors);
+    }
+
+    RelexResult { tokens, changed, replaced: first..resume, errors }
+}
+
+/// Traslada una posición posterior al punto de sincronización, que pasó de
+/// `old_anchor` a `anchor`. Solo cambian de columna las posiciones que
+/// comparten línea con él.
+fn shift(position: Position, old_anchor: &Position, anchor: &Position, delta: isize) -> Position {
+    let moved = |value: usize, old: usize, new: usize| (value as isize + new as isize - old as isize) as usize;
+    let mut shifted = Position {
+        line: moved(position.line, old_anchor.line, anchor.line),
+        offset: (position.offset as isize + delta) as usize,
+        ..position
+    };
+    if position.line == old_anchor.line {
+        shifted.column = moved(position.column, old_anchor.column, anchor.column);
+        shifted.utf8_column = moved(position.utf8_column, old_anchor.utf8_column, anchor.utf8_column);
+        shifted.utf16_column = moved(position.utf16_column, old_anchor.utf16_column, anchor.utf16_column);
+    }
+    shifted
+}
@@ -1,67 +1,531 @@
-use std::str::Chars;
-use std::iter::Peekable;
-use crate::token::TokenType;
-use std::collections::HashMap;
-use crate::token::LexerToken;
-
-pub struct LexicalAnalyzer<'a> {
-    input: Peekable<Chars<'a>>,
+use core::fmt;
+use std::borrow::Cow;
+use crate::token::{LexerToken, Position, Token, TokenType};
+
+// --- Errores Léxicos ---
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    UnterminatedString,
+    UnterminatedChar,
+    UnterminatedComment,
+    InvalidCharacter(char),
+    InvalidEscape(String),
+    MalformedNumber(String),
+    InvalidCharLiteral(String),
+}
+
+impl LexErrorKind {
+    /// Nombre estable del tipo de error, usado en las respuestas gRPC.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LexErrorKind::UnterminatedString => "UnterminatedString",
+            LexErrorKind::UnterminatedChar => "UnterminatedChar",
+            LexErrorKind::UnterminatedComment => "UnterminatedComment",
+            LexErrorKind::InvalidCharacter(_) => "InvalidCharacter",
+            LexErrorKind::InvalidEscape(_) => "InvalidEscape",
+            LexErrorKind::MalformedNumber(_) => "MalformedNumber",
+            LexErrorKind::InvalidCharLiteral(_) => "InvalidCharLiteral",
+        }
+    }
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexErrorKind::UnterminatedString =>
+                write!(f, "Cadena de caracteres sin cerrar"),
+            LexErrorKind::UnterminatedChar =>
+                write!(f, "Literal de carácter sin cerrar"),
+            LexErrorKind::UnterminatedComment =>
+                write!(f, "Comentario de bloque sin cerrar"),
+            LexErrorKind::InvalidCharacter(c) =>
+                write!(f, "Carácter inválido '{}'", c),
+            LexErrorKind::InvalidEscape(seq) =>
+                write!(f, "Secuencia de escape inválida '{}'", seq),
+            LexErrorKind::MalformedNumber(lexeme) =>
+                write!(f, "Número mal formado '{}'", lexeme),
+            LexErrorKind::InvalidCharLiteral(lexeme) =>
+                write!(f, "Literal de carácter inválido {}: debe contener un único carácter ASCII", lexeme),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub start: Position,
+    pub end: Position,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} en la línea {}, columna {}", self.kind, self.start.line, self.start.column)
+    }
+}
+
+/// Fragmento del contenido de una cadena interpolada.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringPart<'src> {
+    Text(Cow<'src, str>),           // Texto literal, con los escapes ya decodificados
+    Expression(Vec<Token<'src>>),   // Tokens de una expresión `${...}`, sin las llaves
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StringKind {
+    Quoted,     // "...", de una sola línea
+    Triple,     // """...""", puede ocupar varias líneas
+    Raw,        // r"...", sin escapes ni interpolaciones
+}
+
+/// Analizador léxico. Recorre el código fuente como bytes y produce los tokens
+/// bajo demanda como un `Iterator`; los lexemas son porciones del propio fuente.
+pub struct LexicalAnalyzer<'src> {
+    source: &'src str,
+    bytes: &'src [u8],
     line: usize,
-    column: usize,
-    keywords: HashMap<String, TokenType>,
+    column: usize,         // Columna en caracteres
+    utf8_column: usize,    // La misma columna, en bytes
+    utf16_column: usize,   // La misma columna, en unidades UTF-16
+    offset: usize,         // Desplazamiento en bytes del siguiente carácter
+    token_start: Position, // Inicio del token que se está escaneando
+    finished: bool,        // Ya se emitió el token EndOfFile
+    pub errors: Vec<LexError>,
 }
 
-impl<'a> LexicalAnalyzer<'a> {
-    pub fn new(source: &'a str) -> Self {
-        let mut keywords = HashMap::new();
-        keywords.insert("let".to_string(), TokenType::Keyword);
-        keywords.insert("const".to_string(), TokenType::Keyword);
-        keywords.insert("fn".to_string(), TokenType::Keyword);
-        keywords.insert("if".to_string(), TokenType::Keyword);
-        keywords.insert("else".to_string(), TokenType::Keyword);
-        keywords.insert("while".to_string(), TokenType::Keyword);
-        keywords.insert("struct".to_string(), TokenType::Keyword);
-        keywords.insert("return".to_string(), TokenType::Keyword);
-        keywords.insert("for".to_string(), TokenType::Keyword);
-        keywords.insert("in".to_string(), TokenType::Keyword);
-        keywords.insert("true".to_string(), TokenType::Boolean);
-        keywords.insert("false".to_string(), TokenType::Boolean);
-        // --- NUEVAS PALABRAS CLAVE ---
-        keywords.insert("do".to_string(), TokenType::Keyword);
-        keywords.insert("until".to_string(), TokenType::Keyword);
+impl<'src> LexicalAnalyzer<'src> {
+    pub fn new(source: &'src str) -> Self {
+        Self::starting_at(source, Position::START)
+    }
 
+    /// Crea un lexer que empieza a escanear en `start`, que debe ser el inicio
+    /// de un token. Los desplazamientos siguen siendo relativos a todo `source`.
+    pub fn starting_at(source: &'src str, start: Position) -> Self {
         Self {
-            input: source.chars().peekable(),
-            line: 1,
-            column: 1,
-            keywords,
+            source,
+            bytes: source.as_bytes(),
+            line: start.line,
+            column: start.column,
+            utf8_column: start.utf8_column,
+            utf16_column: start.utf16_column,
+            offset: start.offset,
+            token_start: start,
+            finished: false,
+            errors: Vec::new(),
         }
     }
 
     fn advance(&mut self) -> Option<char> {
-        if let Some(ch) = self.input.next() {
-            if ch == '\n' {
-                self.line += 1;
-                self.column = 1;
-            } else {
-                self.column += 1;
-            }
-            Some(ch)
+        let ch = match self.peek()? {
+            byte if byte.is_ascii() => byte as char,
+            _ => self.source[self.offset..].chars().next()?,
+        };
+        self.offset += ch.len_utf8();
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+            self.utf8_column = 1;
+            self.utf16_column = 1;
         } else {
-            None
+            self.column += 1;
+            self.utf8_column += ch.len_utf8();
+            self.utf16_column += ch.len_utf16();
+        }
+        Some(ch)
+    }
+
+    fn current_position(&self) -> Position {
+        Position {
+            line: self.line,
+            column: self.column,
+            offset: self.offset,
+            utf8_column: self.utf8_column,
+            utf16_column: self.utf16_column,
         }
     }
 
-    fn peek(&mut self) -> Option<&char> {
-        self.input.peek()
+    /// Texto del fuente desde el inicio del token actual hasta la posición actual.
+    fn lexeme(&self) -> &'src str {
+        &self.source[self.token_start.offset..self.offset]
+    }
+
+    /// Crea un token que abarca desde el inicio del token actual hasta la posición actual.
+    fn make_token(&self, token_type: TokenType, lexeme: impl Into<Cow<'src, str>>) -> Token<'src> {
+        Token::new(token_type, lexeme, self.token_start, self.current_position())
     }
 
-    fn is_at_end(&mut self) -> bool {
-        self.peek().is_none()
+    /// Registra un error que abarca el token actual hasta la posición actual.
+    fn error(&mut self, kind: LexErrorKind) {
+        let end = self.current_position();
+        self.error_at(kind, self.token_start, end);
     }
 
-    fn match_next(&mut self, expected: char) -> bool {
-        if self.peek() == Some(&expected) {
+    fn error_at(&mut self, kind: LexErrorKind, start: Position, end: Position) {
+        self.errors.push(LexError { kind, start, end });
+    }
+
+    /// Decodifica una secuencia de escape cuyo `\` ya fue consumido.
+    /// Si la secuencia no es válida registra un error y devuelve `None`.
+    fn scan_escape(&mut self, escape_start: Position) -> Option<char> {
+        let decoded = match self.advance() {
+            Some('n') => Some('\n'),
+            Some('t') => Some('\t'),
+            Some('r') => Some('\r'),
+            Some('0') => Some('\0'),
+            Some('\\') => Some('\\'),
+            Some('"') => Some('"'),
+            Some('\'') => Some('\''),
+            Some('$') => Some('$'),
+            Some('x') => self.scan_hex_escape(),
+            Some('u') => self.scan_unicode_escape(),
+            _ => None,
+        };
+        if decoded.is_none() {
+            let end = self.current_position();
+            let sequence = self.source[escape_start.offset..end.offset].to_string();
+            self.error_at(LexErrorKind::InvalidEscape(sequence), escape_start, end);
+        }
+        decoded
+    }
+
+    /// `\xNN`: exactamente dos dígitos hexadecimales, limitado a ASCII.
+    fn scan_hex_escape(&mut self) -> Option<char> {
+        let mut value = 0;
+        for _ in 0..2 {
+            let digit = self.peek().and_then(|b| (b as char).to_digit(16))?;
+            self.advance();
+            value = value * 16 + digit;
+        }
+        if value <= 0x7F { char::from_u32(value) } else { None }
+    }
+
+    /// `\u{...}`: de uno a seis dígitos hexadecimales que formen un escalar Unicode válido.
+    fn scan_unicode_escape(&mut self) -> Option<char> {
+        if !self.match_next(b'{') {
+            return None;
+        }
+        let mut value: u32 = 0;
+        let mut digits = 0;
+        while let Some(digit) = self.peek().and_then(|b| (b as char).to_digit(16)) {
+            self.advance();
+            value = value.saturating_mul(16).saturating_add(digit);
+            digits += 1;
+        }
+        if !self.match_next(b'}') || digits == 0 || digits > 6 {
+            return None;
+        }
+        char::from_u32(value)
+    }
+
+    /// Consume el delimitador de apertura de una cadena cuyo primer carácter ya
+    /// fue consumido. Tras `"""` se descarta el salto de línea inmediato.
+    fn open_string(&mut self, first: char) -> StringKind {
+        match first {
+            'r' => {
+                self.advance(); // Consume '"'
+                StringKind::Raw
+            }
+            '"' if self.peek() == Some(b'"') && self.peek_nth(1) == Some(b'"') => {
+                self.advance();
+                self.advance();
+                self.match_next(b'\r');
+                self.match_next(b'\n');
+                StringKind::Triple
+            }
+            _ => StringKind::Quoted,
+        }
+    }
+
+    fn at_closing_quote(&self, kind: StringKind) -> bool {
+        match kind {
+            StringKind::Quoted | StringKind::Raw => self.peek() == Some(b'"'),
+            StringKind::Triple => (0..3).all(|n| self.peek_nth(n) == Some(b'"')),
+        }
+    }
+
+    /// Escanea una cadena cuyo delimitador de apertura ya fue consumido. Sin
+    /// interpolaciones el lexema es el contenido, copiado solo si contiene
+    /// escapes; con ellas es el literal completo (ver `interpolation_parts`).
+    fn scan_string(&mut self, kind: StringKind) -> Token<'src> {
+        let mut parts = self.scan_string_parts(kind);
+        if parts.iter().any(|part| matches!(part, StringPart::Expression(_))) {
+            return self.make_token(TokenType::InterpolatedString, self.lexeme());
+        }
+        match parts.pop() {
+            Some(StringPart::Text(text)) => self.make_token(TokenType::String, text),
+            _ => self.make_token(TokenType::String, ""),
+        }
+    }
+
+    /// Separa el contenido de una cadena en texto y expresiones `${...}`, hasta
+    /// el delimitador de cierre. Las cadenas crudas no tienen escapes ni
+    /// interpolaciones y solo las de comillas simples no admiten saltos de línea.
+    fn scan_string_parts(&mut self, kind: StringKind) -> Vec<StringPart<'src>> {
+        let single_line = kind == StringKind::Quoted;
+        let mut parts = Vec::new();
+        let mut text_start = self.offset;
+        let mut decoded: Option<String> = None;
+        loop {
+            if self.at_closing_quote(kind) {
+                self.push_text(&mut parts, &mut decoded, text_start);
+                let closing = if kind == StringKind::Triple { 3 } else { 1 };
+                for _ in 0..closing {
+                    self.advance();
+                }
+                return parts;
+            }
+            match self.peek() {
+                None | Some(b'\n') if self.is_at_end() || single_line => {
+                    // Se conserva el contenido leído para que el parser pueda continuar.
+                    self.push_text(&mut parts, &mut decoded, text_start);
+                    self.error(LexErrorKind::UnterminatedString);
+                    return parts;
+                }
+                Some(b'\\') if kind != StringKind::Raw => {
+                    let escape_start = self.current_position();
+                    decoded.get_or_insert_with(|| self.source[text_start..self.offset].to_string());
+                    self.advance(); // Consume '\'
+                    if self.is_at_end() || (single_line && self.peek() == Some(b'\n')) { continue; }
+                    let escaped = self.scan_escape(escape_start);
+                    let content = decoded.as_mut().unwrap();
+                    match escaped {
+                        Some(ch) => content.push(ch),
+                        // Se conserva el texto original para no perder información.
+                        None => content.push_str(&self.source[escape_start.offset..self.offset]),
+                    }
+                }
+                Some(b'$') if kind != StringKind::Raw && self.peek_nth(1) == Some(b'{') => {
+                    self.push_text(&mut parts, &mut decoded, text_start);
+                    self.advance(); // Consume '$'
+                    self.advance(); // Consume '{'
+                    match self.scan_interpolation(single_line) {
+                        Some(tokens) => parts.push(StringPart::Expression(tokens)),
+                        None => {
+                            self.error(LexErrorKind::UnterminatedString);
+                            return parts;
+                        }
+                    }
+                    text_start = self.offset;
+                }
+                _ => {
+                    let ch = self.advance().unwrap();
+                    if let Some(content) = decoded.as_mut() { content.push(ch); }
+                }
+            }
+        }
+    }
+
+    /// Añade a `parts` el texto pendiente desde `text_start`, si no está vacío.
+    fn push_text(&self, parts: &mut Vec<StringPart<'src>>, decoded: &mut Option<String>, text_start: usize) {
+        let text = match decoded.take() {
+            Some(content) => Cow::Owned(content),
+            None => Cow::Borrowed(&self.source[text_start..self.offset]),
+        };
+        if !text.is_empty() {
+            parts.push(StringPart::Text(text));
+        }
+    }
+
+    /// Escanea los tokens de una interpolación cuyo `${` ya fue consumido, hasta
+    /// la llave que la cierra, que no se incluye. Devuelve `None` si la cadena
+    /// termina antes (o la línea, en las cadenas que no admiten varias).
+    fn scan_interpolation(&mut self, single_line: bool) -> Option<Vec<Token<'src>>> {
+        let token_start = self.token_start;
+        let mut tokens = Vec::new();
+        let mut depth = 0;
+        let closed = loop {
+            if single_line && self.peek() == Some(b'\n') {
+                break false;
+            }
+            let token = self.scan_token();
+            match token.token_type {
+                TokenType::EndOfFile => break false,
+                TokenType::RightBrace if depth == 0 => break true,
+                TokenType::RightBrace => depth -= 1,
+                TokenType::LeftBrace => depth += 1,
+                _ => {}
+            }
+            tokens.push(token);
+        };
+        self.token_start = token_start;
+        closed.then_some(tokens)
+    }
+
+    /// Si lo que sigue a la comilla es una etiqueta de bucle (`'outer`), devuelve
+    /// la longitud en bytes de su nombre. Un nombre cerrado por otra comilla es un
+    /// carácter (`'a'`, o un error si son varios), y una sola letra al final de la
+    /// línea se sigue tratando como un carácter sin cerrar.
+    fn label_len(&self) -> Option<usize> {
+        let rest = &self.source[self.offset..];
+        let first = rest.chars().next().filter(|c| c.is_alphabetic() || *c == '_')?;
+        let len = rest
+            .char_indices()
+            .find(|(_, c)| !(c.is_alphanumeric() || *c == '_'))
+            .map_or(rest.len(), |(i, _)| i);
+        match rest[len..].chars().next() {
+            Some('\'') => None,
+            None | Some('\n') if len == first.len_utf8() => None,
+            _ => Some(len),
+        }
+    }
+
+    /// Escanea un literal de carácter cuya comilla de apertura ya fue consumida.
+    /// Debe contener exactamente un carácter ASCII, que puede ser un escape.
+    fn scan_char(&mut self) -> Token<'src> {
+        let mut value = None;
+        let mut count = 0;
+        loop {
+            match self.peek() {
+                Some(b'\'') => {
+                    self.advance();
+                    break;
+                }
+                None | Some(b'\n') => {
+                    self.error(LexErrorKind::UnterminatedChar);
+                    return self.make_token(TokenType::Unknown, self.lexeme());
+                }
+                Some(b'\\') => {
+                    let escape_start = self.current_position();
+                    self.advance(); // Consume '\'
+                    if matches!(self.peek(), None | Some(b'\n')) { continue; }
+                    value = self.scan_escape(escape_start);
+                    if value.is_none() {
+                        // `scan_escape` ya reportó el error; se descarta el literal.
+                        while !matches!(self.peek(), None | Some(b'\n' | b'\'')) {
+                            self.advance();
+                        }
+                        self.match_next(b'\'');
+                        return self.make_token(TokenType::Unknown, self.lexeme());
+                    }
+                    count += 1;
+                }
+                Some(_) => {
+                    value = self.advance();
+                    count += 1;
+                }
+            }
+        }
+        match value {
+            Some(ch) if count == 1 && ch.is_ascii() => {
+                let content = &self.source[self.token_start.offset + 1..self.offset - 1];
+                if content.len() == 1 {
+                    self.make_token(TokenType::Char, content)
+                } else {
+                    self.make_token(TokenType::Char, ch.to_string())
+                }
+            }
+            _ => {
+                self.error(LexErrorKind::InvalidCharLiteral(self.lexeme().to_string()));
+                self.make_token(TokenType::Unknown, self.lexeme())
+            }
+        }
+    }
+
+    /// Escanea un literal numérico: enteros decimales, hexadecimales (`0x`),
+    /// octales (`0o`) y binarios (`0b`), flotantes con exponente opcional
+    /// (`1.5e-3`) y separadores `_` entre dígitos (`1_000_000`).
+    fn scan_number(&mut self, first: char) -> Token<'src> {
+        let radix = match (first, self.peek()) {
+            ('0', Some(b'x' | b'X')) => 16,
+            ('0', Some(b'o' | b'O')) => 8,
+            ('0', Some(b'b' | b'B')) => 2,
+            _ => 10,
+        };
+
+        if radix != 10 {
+            self.advance(); // Consume el prefijo
+            let digits = self.scan_digits(radix);
+            if digits == 0 || self.peek_is_identifier_char() {
+                return self.malformed_number();
+            }
+            return self.make_token(TokenType::Integer, self.lexeme());
+        }
+
+        let mut token_type = TokenType::Integer;
+        self.scan_digits(10);
+        if self.peek() == Some(b'.') && self.peek_nth(1).is_some_and(|b| b.is_ascii_digit()) {
+            self.advance(); // Consume '.'
+            self.scan_digits(10);
+            token_type = TokenType::Float;
+        }
+        // El exponente solo se consume si va seguido de dígitos (con signo opcional).
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            let exponent_follows = match self.peek_nth(1) {
+                Some(b'+' | b'-') => self.peek_nth(2).is_some_and(|b| b.is_ascii_digit()),
+                next => next.is_some_and(|b| b.is_ascii_digit()),
+            };
+            if exponent_follows {
+                self.advance(); // Consume 'e'
+                if matches!(self.peek(), Some(b'+' | b'-')) {
+                    self.advance();
+                }
+                self.scan_digits(10);
+                token_type = TokenType::Float;
+            }
+        }
+        // Un número pegado a letras (ej. `12abc`) no es un literal ni un identificador válido.
+        if self.peek_is_identifier_char() {
+            return self.malformed_number();
+        }
+        self.make_token(token_type, self.lexeme())
+    }
+
+    /// Consume dígitos de la base indicada y separadores `_`, que solo valen entre
+    /// dos dígitos; devuelve cuántos dígitos leyó. Un `_` que no cumple eso queda
+    /// sin consumir y el llamador reporta el literal como mal formado.
+    fn scan_digits(&mut self, radix: u32) -> usize {
+        let is_digit = |byte: Option<u8>| byte.is_some_and(|b| (b as char).is_digit(radix));
+        let mut digits = 0;
+        loop {
+            if is_digit(self.peek()) {
+                digits += 1;
+            } else if !(self.peek() == Some(b'_') && is_digit(self.previous_byte()) && is_digit(self.peek_nth(1))) {
+                break;
+            }
+            self.advance();
+        }
+        digits
+    }
+
+    /// Consume el resto de un literal mal formado y lo reporta como un único error.
+    fn malformed_number(&mut self) -> Token<'src> {
+        self.skip_identifier_chars();
+        self.error(LexErrorKind::MalformedNumber(self.lexeme().to_string()));
+        self.make_token(TokenType::Unknown, self.lexeme())
+    }
+
+    fn skip_identifier_chars(&mut self) {
+        while self.peek_is_identifier_char() {
+            self.advance();
+        }
+    }
+
+    /// Letras (incluidas las no ASCII), dígitos y `_` pueden continuar un identificador.
+    fn peek_is_identifier_char(&self) -> bool {
+        match self.peek() {
+            Some(byte) if byte.is_ascii() => byte.is_ascii_alphanumeric() || byte == b'_',
+            Some(_) => self.source[self.offset..].chars().next().is_some_and(char::is_alphanumeric),
+            None => false,
+        }
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<u8> {
+        self.bytes.get(self.offset + n).copied()
+    }
+
+    fn previous_byte(&self) -> Option<u8> {
+        self.offset.checked_sub(1).and_then(|i| self.bytes.get(i).copied())
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.peek_nth(0)
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.offset >= self.bytes.len()
+    }
+
+    fn match_next(&mut self, expected: u8) -> bool {
+        if self.peek() == Some(expected) {
             self.advance();
             true
         } else {
@@ -69,137 +533,175 @@ impl<'a> LexicalAnalyzer<'a> {
         }
     }
 
+    /// Escanea todo el fuente y devuelve los tokens con sus lexemas en propiedad.
     pub fn scan_tokens(&mut self) -> Vec<LexerToken> {
-        let mut tokens = Vec::new();
-        while !self.is_at_end() {
-            tokens.push(self.scan_token());
-        }
-        tokens.push(LexerToken::new(TokenType::EndOfFile, "".to_string(), self.line, self.column));
-        tokens
+        self.by_ref().map(Token::into_owned).collect()
     }
 
-    fn scan_token(&mut self) -> LexerToken {
-        let start_line = self.line;
-        let start_column = self.column;
+    fn scan_token(&mut self) -> Token<'src> {
+        self.token_start = self.current_position();
 
         let ch = match self.advance() {
             Some(c) => c,
-            None => return LexerToken::new(TokenType::EndOfFile, "".to_string(), start_line, start_column),
+            None => return self.make_token(TokenType::EndOfFile, ""),
         };
 
         match ch {
             ' ' | '\t' | '\r' => {
-                let mut lexeme = String::from(ch);
-                while let Some(' ' | '\t' | '\r') = self.peek() {
-                    lexeme.push(self.advance().unwrap());
+                while let Some(b' ' | b'\t' | b'\r') = self.peek() {
+                    self.advance();
                 }
-                LexerToken::new(TokenType::Whitespace, lexeme, start_line, start_column)
+                self.make_token(TokenType::Whitespace, self.lexeme())
             }
-            '\n' => LexerToken::new(TokenType::NewLine, "\n".to_string(), start_line, start_column),
+            '\n' => self.make_token(TokenType::NewLine, "\n"),
             '/' => {
-                if self.match_next('/') {
-                    let mut comment = String::new();
-                    while let Some(c) = self.peek() {
-                        if *c == '\n' { break; }
-                        comment.push(self.advance().unwrap());
+                if self.match_next(b'/') {
+                    // `///` documenta la declaración siguiente; `////` vuelve a ser un comentario normal.
+                    let token_type = if self.peek() == Some(b'/') && self.peek_nth(1) != Some(b'/') {
+                        self.advance();
+                        TokenType::DocComment
+                    } else {
+                        TokenType::CommentSingle
+                    };
+                    let content_start = self.offset;
+                    while !matches!(self.peek(), None | Some(b'\n')) {
+                        self.advance();
                     }
-                    LexerToken::new(TokenType::CommentSingle, comment, start_line, start_column)
-                } else if self.match_next('*') {
-                    let mut comment = String::new();
+                    self.make_token(token_type, &self.source[content_start..self.offset])
+                } else if self.match_next(b'*') {
+                    // Igual que en `///`: `/**` documenta, pero `/**/` y `/***` no.
+                    let token_type = if self.peek() == Some(b'*') && !matches!(self.peek_nth(1), Some(b'*' | b'/')) {
+                        self.advance();
+                        TokenType::DocComment
+                    } else {
+                        TokenType::CommentMultiLine
+                    };
+                    let content_start = self.offset;
                     while !self.is_at_end() {
-                        if self.match_next('*') && self.match_next('/') {
-                            return LexerToken::new(TokenType::CommentMultiLine, comment, start_line, start_column);
+                        let content_end = self.offset;
+                        if self.match_next(b'*') && self.match_next(b'/') {
+                            return self.make_token(token_type, &self.source[content_start..content_end]);
+                        }
+                        // Un '*' seguido de otro '*' ya fue consumido: no hay que saltar el segundo.
+                        if content_end == self.offset {
+                            self.advance();
                         }
-                        comment.push(self.advance().unwrap());
                     }
-                    LexerToken::new(TokenType::Unknown, comment, start_line, start_column) // Unterminated comment
+                    self.error(LexErrorKind::UnterminatedComment);
+                    self.make_token(token_type, &self.source[content_start..self.offset])
+                } else if self.match_next(b'=') {
+                    self.make_token(TokenType::SlashEqual, "/=")
                 } else {
-                    LexerToken::new(TokenType::Slash, "/".to_string(), start_line, start_column)
+                    self.make_token(TokenType::Slash, "/")
                 }
             }
             // --- Delimitadores y Operadores ---
-            '(' => LexerToken::new(TokenType::LeftParen, "(".to_string(), start_line, start_column),
-            ')' => LexerToken::new(TokenType::RightParen, ")".to_string(), start_line, start_column),
-            '{' => LexerToken::new(TokenType::LeftBrace, "{".to_string(), start_line, start_column),
-            '}' => LexerToken::new(TokenType::RightBrace, "}".to_string(), start_line, start_column),
-            '[' => LexerToken::new(TokenType::LeftBracket, "[".to_string(), start_line, start_column),
-            ']' => LexerToken::new(TokenType::RightBracket, "]".to_string(), start_line, start_column),
-            ',' => LexerToken::new(TokenType::Comma, ",".to_string(), start_line, start_column),
-            ';' => LexerToken::new(TokenType::Semicolon, ";".to_string(), start_line, start_column),
-            ':' => LexerToken::new(TokenType::Colon, ":".to_string(), start_line, start_column),
+            '(' => self.make_token(TokenType::LeftParen, "("),
+            ')' => self.make_token(TokenType::RightParen, ")"),
+            '{' => self.make_token(TokenType::LeftBrace, "{"),
+            '}' => self.make_token(TokenType::RightBrace, "}"),
+            '[' => self.make_token(TokenType::LeftBracket, "["),
+            ']' => self.make_token(TokenType::RightBracket, "]"),
+            ',' => self.make_token(TokenType::Comma, ","),
+            ';' => self.make_token(TokenType::Semicolon, ";"),
+            ':' => if self.match_next(b':') { self.make_token(TokenType::DoubleColon, "::") } else { self.make_token(TokenType::Colon, ":") },
+            '?' => self.make_token(TokenType::Question, "?"),
             '.' => {
-                if self.peek() == Some(&'.') {
-                    self.advance();
-                    if self.peek() == Some(&'.') {
+                if self.peek() == Some(b'.') && self.peek_nth(1) == Some(b'.') && self.peek_nth(2) == Some(b'+') {
+                    for _ in 0..3 {
                         self.advance();
-                        if self.peek() == Some(&'+') {
-                            self.advance();
-                            return LexerToken::new(TokenType::Spread, "...+".to_string(), start_line, start_column);
-                        }
                     }
+                    self.make_token(TokenType::Spread, "...+")
+                } else if self.match_next(b'.') {
+                    if self.match_next(b'=') { self.make_token(TokenType::DotDotEqual, "..=") } else { self.make_token(TokenType::DotDot, "..") }
+                } else {
+                    self.make_token(TokenType::Dot, ".")
                 }
-                LexerToken::new(TokenType::Dot, ".".to_string(), start_line, start_column)
             },
-            '+' => if self.match_next('+') { LexerToken::new(TokenType::Increment, "++".to_string(), start_line, start_column) } else { LexerToken::new(TokenType::Plus, "+".to_string(), start_line, start_column) },
-            '-' => if self.match_next('>') { LexerToken::new(TokenType::ArrowRight, "->".to_string(), start_line, start_column) } else if self.match_next('-') { LexerToken::new(TokenType::Decrement, "--".to_string(), start_line, start_column) } else { LexerToken::new(TokenType::Minus, "-".to_string(), start_line, start_column) },
-            '*' => LexerToken::new(TokenType::Asterisk, "*".to_string(), start_line, start_column),
-            '=' => if self.match_next('=') { LexerToken::new(TokenType::DoubleEqual, "==".to_string(), start_line, start_column) } else { LexerToken::new(TokenType::Equal, "=".to_string(), start_line, start_column) },
-            '>' => if self.match_next('=') { LexerToken::new(TokenType::GreaterEqual, ">=".to_string(), start_line, start_column) } else { LexerToken::new(TokenType::Greater, ">".to_string(), start_line, start_column) },
-            '<' => if self.match_next('=') {
-                if self.match_next('>') { LexerToken::new(TokenType::Swap, "<=>".to_string(), start_line, start_column) }
-                else { LexerToken::new(TokenType::LessEqual, "<=".to_string(), start_line, start_column) }
-            } else if self.match_next('>') { LexerToken::new(TokenType::NotEqual, "<>".to_string(), start_line, start_column) } else { LexerToken::new(TokenType::Less, "<".to_string(), start_line, start_column) },
-            '!' => if self.match_next('=') { LexerToken::new(TokenType::NotEqual, "!=".to_string(), start_line, start_column) } else { LexerToken::new(TokenType::Exclamation, "!".to_string(), start_line, start_column) },
-            '&' => if self.match_next('&') { LexerToken::new(TokenType::DoubleAmpersand, "&&".to_string(), start_line, start_column) } else { LexerToken::new(TokenType::Ampersand, "&".to_string(), start_line, start_column) },
-            '|' => if self.match_next('>') { LexerToken::new(TokenType::Pipe, "|>".to_string(), start_line, start_column) } else if self.match_next('|') { LexerToken::new(TokenType::DoubleBar, "||".to_string(), start_line, start_column) } else { LexerToken::new(TokenType::Bar, "|".to_string(), start_line, start_column) },
-            '@' => if self.match_next('*') { LexerToken::new(TokenType::Splat, "@*".to_string(), start_line, start_column) } else { LexerToken::new(TokenType::Unknown, "@".to_string(), start_line, start_column) },
+            '+' => if self.match_next(b'+') { self.make_token(TokenType::Increment, "++") } else if self.match_next(b'=') { self.make_token(TokenType::PlusEqual, "+=") } else { self.make_token(TokenType::Plus, "+") },
+            '-' => if self.match_next(b'>') { self.make_token(TokenType::ArrowRight, "->") } else if self.match_next(b'-') { self.make_token(TokenType::Decrement, "--") } else if self.match_next(b'=') { self.make_token(TokenType::MinusEqual, "-=") } else { self.make_token(TokenType::Minus, "-") },
+            '*' => if self.match_next(b'=') { self.make_token(TokenType::AsteriskEqual, "*=") } else { self.make_token(TokenType::Asterisk, "*") },
+            '%' => if self.match_next(b'=') { self.make_token(TokenType::PercentEqual, "%=") } else { self.make_token(TokenType::Percent, "%") },
+            '=' => if self.match_next(b'=') { self.make_token(TokenType::DoubleEqual, "==") } else if self.match_next(b'>') { self.make_token(TokenType::FatArrow, "=>") } else { self.make_token(TokenType::Equal, "=") },
+            '>' => if self.match_next(b'=') { self.make_token(TokenType::GreaterEqual, ">=") } else if self.match_next(b'>') { self.make_token(TokenType::ShiftRight, ">>") } else { self.make_token(TokenType::Greater, ">") },
+            '<' => if self.match_next(b'=') {
+                if self.match_next(b'>') { self.make_token(TokenType::Swap, "<=>") }
+                else { self.make_token(TokenType::LessEqual, "<=") }
+            } else if self.match_next(b'>') { self.make_token(TokenType::NotEqual, "<>") }
+            else if self.match_next(b'<') { self.make_token(TokenType::ShiftLeft, "<<") }
+            else { self.make_token(TokenType::Less, "<") },
+            '!' => if self.match_next(b'=') { self.make_token(TokenType::NotEqual, "!=") } else { self.make_token(TokenType::Exclamation, "!") },
+            '&' => if self.match_next(b'&') { self.make_token(TokenType::DoubleAmpersand, "&&") } else { self.make_token(TokenType::Ampersand, "&") },
+            '|' => if self.match_next(b'>') { self.make_token(TokenType::Pipe, "|>") } else if self.match_next(b'|') { self.make_token(TokenType::DoubleBar, "||") } else { self.make_token(TokenType::Bar, "|") },
+            '^' => self.make_token(TokenType::Caret, "^"),
+            '~' => self.make_token(TokenType::Tilde, "~"),
+            '@' => if self.match_next(b'*') { self.make_token(TokenType::Splat, "@*") } else {
+                self.error(LexErrorKind::InvalidCharacter('@'));
+                self.make_token(TokenType::Unknown, "@")
+            },
             // --- Literales ---
-            '\'' | '"' => {
-                let quote_char = ch;
-                let mut content = String::new();
-                while let Some(&next) = self.peek() {
-                    if next == quote_char { break; }
-                    if next == '\n' { return LexerToken::new(TokenType::Unknown, format!("{}{}", quote_char, content), start_line, start_column); } // Unterminated string
-                    content.push(self.advance().unwrap());
+            '\'' => match self.label_len() {
+                Some(len) => {
+                    let name_start = self.offset;
+                    while self.offset < name_start + len {
+                        self.advance();
+                    }
+                    self.make_token(TokenType::Label, &self.source[name_start..self.offset])
                 }
-                self.advance(); // Consume closing quote
-                LexerToken::new(TokenType::String, content, start_line, start_column)
+                None => self.scan_char(),
+            },
+            '"' => {
+                let kind = self.open_string(ch);
+                self.scan_string(kind)
+            }
+            'r' if self.peek() == Some(b'"') => {
+                let kind = self.open_string(ch);
+                self.scan_string(kind)
             }
             c if c.is_alphabetic() || c == '_' => {
-                let mut identifier = String::from(c);
-                while let Some(&next) = self.peek() {
-                    if next.is_alphanumeric() || next == '_' {
-                        identifier.push(self.advance().unwrap());
-                    } else { break; }
-                }
-                if let Some(token_type) = self.keywords.get(&identifier).cloned() {
-                    LexerToken::new(token_type, identifier, start_line, start_column)
-                } else {
-                    LexerToken::new(TokenType::Identifier, identifier, start_line, start_column)
-                }
+                self.skip_identifier_chars();
+                let identifier = self.lexeme();
+                self.make_token(TokenType::keyword(identifier).unwrap_or(TokenType::Identifier), identifier)
             }
-            c if c.is_digit(10) => {
-                let mut number_str = String::from(c);
-                while let Some(&next) = self.peek() {
-                    if next.is_digit(10) {
-                        number_str.push(self.advance().unwrap());
-                    } else { break; }
-                }
-                if self.peek() == Some(&'.') {
-                     if self.input.clone().nth(1).map_or(false, |c| c.is_digit(10)) {
-                        number_str.push(self.advance().unwrap()); // Consume '.'
-                        while let Some(&next) = self.peek() {
-                           if next.is_digit(10) {
-                               number_str.push(self.advance().unwrap());
-                           } else { break; }
-                        }
-                        return LexerToken::new(TokenType::Float, number_str, start_line, start_column);
-                     }
-                }
-                LexerToken::new(TokenType::Integer, number_str, start_line, start_column)
+            c if c.is_ascii_digit() => self.scan_number(c),
+            _ => {
+                self.error(LexErrorKind::InvalidCharacter(ch));
+                self.make_token(TokenType::Unknown, self.lexeme())
             }
-            _ => LexerToken::new(TokenType::Unknown, ch.to_string(), start_line, start_column),
         }
     }
 }
-    
\ No newline at end of file
+
+/// Separa un token `InterpolatedString` en su texto y los tokens de cada
+/// expresión `${...}`, con posiciones relativas al fuente original. Los errores
+/// ya se reportaron al escanear el token, así que aquí se descartan.
+pub fn interpolation_parts<'a>(token: &'a Token<'_>) -> Vec<StringPart<'a>> {
+    let start = Position { offset: 0, ..token.start_position() };
+    let mut lexer = LexicalAnalyzer::starting_at(&token.lexeme, start);
+    let Some(first) = lexer.advance() else { return Vec::new() };
+    let kind = lexer.open_string(first);
+    let mut parts = lexer.scan_string_parts(kind);
+    for part in &mut parts {
+        if let StringPart::Expression(tokens) = part {
+            for t in tokens {
+                t.start += token.start;
+                t.end += token.start;
+            }
+        }
+    }
+    parts
+}
+
+impl<'src> Iterator for LexicalAnalyzer<'src> {
+    type Item = Token<'src>;
+
+    /// Devuelve el siguiente token; el último es siempre `EndOfFile`.
+    fn next(&mut self) -> Option<Token<'src>> {
+        if self.finished {
+            return None;
+        }
+        let token = self.scan_token();
+        self.finished = token.token_type == TokenType::EndOfFile;
+        Some(token)
+    }
+}
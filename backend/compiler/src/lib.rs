@@ -2,10 +2,12 @@
 
 pub mod ast;
 pub mod lexer;
+pub mod incremental_lexer;
 pub mod parser;
 pub mod token;
 pub mod semantic_analyzer;
 pub mod symbol_table;
 pub mod llvm_compiler;
+pub mod modules;
 pub mod grpc_services;
 pub mod reflection;
\ No newline at end of file
@@ -1,14 +1,32 @@
+use inkwell::basic_block::BasicBlock;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
 use inkwell::module::Module;
 use inkwell::passes::PassManager;
-use inkwell::values::{BasicValueEnum, FunctionValue, PointerValue, BasicMetadataValueEnum, BasicValue};
-use inkwell::types::{BasicTypeEnum, BasicMetadataTypeEnum, BasicType};
+use inkwell::values::{BasicValueEnum, FunctionValue, PointerValue, BasicMetadataValueEnum, BasicValue, IntValue, StructValue};
+use inkwell::types::{BasicTypeEnum, BasicMetadataTypeEnum, BasicType, FunctionType, StructType};
 use inkwell::{AddressSpace, IntPredicate, FloatPredicate};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::ast::*;
 
+/// Lo que recorre un `for`. En ambos casos el bucle avanza un contador entero:
+/// en un rango es la propia variable del bucle y en un arreglo, el índice.
+enum ForIteration<'ctx> {
+    Range { end: IntValue<'ctx>, step: IntValue<'ctx>, inclusive: bool },
+    Array { length: IntValue<'ctx>, data: PointerValue<'ctx>, element_type: BasicTypeEnum<'ctx> },
+}
+
+// Un rango de un patrón se reparte en casos del `switch` si no tiene más valores que estos.
+const MAX_SWITCH_RANGE: i64 = 64;
+
+/// Bloques a los que saltan `break` y `continue` dentro de un bucle.
+struct LoopTarget<'ctx> {
+    label: Option<String>,
+    break_block: BasicBlock<'ctx>,
+    continue_block: BasicBlock<'ctx>,
+}
+
 pub struct Compiler<'ctx> {
     context: &'ctx Context,
     builder: Builder<'ctx>,
@@ -16,7 +34,12 @@ pub struct Compiler<'ctx> {
     fpm: PassManager<FunctionValue<'ctx>>,
     variables: HashMap<String, PointerValue<'ctx>>,
     variable_types: HashMap<String, BasicTypeEnum<'ctx>>,
+    array_elements: HashMap<String, BasicTypeEnum<'ctx>>, // Tipo de elemento de cada struct de arreglo, por nombre
+    function_types: HashMap<String, FunctionType<'ctx>>, // Firma de las variables que guardan una función
+    struct_fields: HashMap<String, Vec<String>>, // Nombres de los campos de cada struct, en orden
+    enum_variants: HashMap<String, Vec<(String, Vec<u32>)>>, // Variantes de cada enum por etiqueta, con las posiciones de su carga útil
     current_function: Option<FunctionValue<'ctx>>,
+    loop_targets: Vec<LoopTarget<'ctx>>, // Bucles abiertos, del más externo al más interno
 }
 
 impl<'ctx> Compiler<'ctx> {
@@ -35,7 +58,12 @@ impl<'ctx> Compiler<'ctx> {
             fpm,
             variables: HashMap::new(),
             variable_types: HashMap::new(),
+            array_elements: HashMap::new(),
+            function_types: HashMap::new(),
+            struct_fields: HashMap::new(),
+            enum_variants: HashMap::new(),
             current_function: None,
+            loop_targets: Vec::new(),
         };
 
         // Declare external C library functions
@@ -59,9 +87,27 @@ impl<'ctx> Compiler<'ctx> {
         // Declare scanf: i32 scanf(i8*, ...)
         let scanf_type = i32_type.fn_type(&[i8_ptr_type.into()], true); // true = variadic
         self.module.add_function("scanf", scanf_type, None);
+
+        // Declare snprintf: i32 snprintf(i8*, i64, i8*, ...)
+        let i64_type = self.context.i64_type();
+        let snprintf_type = i32_type.fn_type(&[i8_ptr_type.into(), i64_type.into(), i8_ptr_type.into()], true);
+        self.module.add_function("snprintf", snprintf_type, None);
+
+        // Declare malloc: i8* malloc(i64)
+        let malloc_type = i8_ptr_type.fn_type(&[i64_type.into()], false);
+        self.module.add_function("malloc", malloc_type, None);
+
+        // Declare strcmp: i32 strcmp(i8*, i8*)
+        let strcmp_type = i32_type.fn_type(&[i8_ptr_type.into(), i8_ptr_type.into()], false);
+        self.module.add_function("strcmp", strcmp_type, None);
+
+        // Declare exit: void exit(i32)
+        let exit_type = self.context.void_type().fn_type(&[i32_type.into()], false);
+        self.module.add_function("exit", exit_type, None);
     }
 
     pub fn compile(&mut self, program: &Program) -> Result<String, String> {
+        self.declare_structs(&program.declarations)?;
         for declaration in &program.declarations {
             self.compile_declaration(declaration)?;
         }
@@ -78,11 +124,79 @@ impl<'ctx> Compiler<'ctx> {
             Declaration::Function(func) => self.compile_function(func),
             Declaration::Variable(var) => self.compile_global_variable(var),
             Declaration::Constant(const_decl) => self.compile_global_constant(const_decl),
-            Declaration::Struct(_) => Ok(()), // Structs are handled separately
+            Declaration::Struct(_) | Declaration::Enum(_) => Ok(()), // Ya declarados en `declare_structs`
+            Declaration::Import(_) => Ok(()), // Los archivos importados ya vienen unidos al programa
             Declaration::Statement(_) => Err("Top-level statements not supported".to_string()),
         }
     }
 
+    /// Declara un struct LLVM con nombre por cada struct y cada enum del programa,
+    /// antes de compilar las funciones que los usan. Primero se crean todos opacos,
+    /// para que un campo pueda referirse a un tipo declarado más abajo.
+    fn declare_structs(&mut self, declarations: &[Declaration]) -> Result<(), String> {
+        let structs: Vec<&StructDeclaration> = declarations
+            .iter()
+            .filter_map(|declaration| match declaration {
+                Declaration::Struct(struct_decl) => Some(struct_decl),
+                _ => None,
+            })
+            .collect();
+        let enums: Vec<&EnumDeclaration> = declarations
+            .iter()
+            .filter_map(|declaration| match declaration {
+                Declaration::Enum(enum_decl) => Some(enum_decl),
+                _ => None,
+            })
+            .collect();
+        let struct_types: Vec<StructType<'ctx>> = structs
+            .iter()
+            .map(|struct_decl| self.context.opaque_struct_type(&struct_decl.name.name))
+            .collect();
+        let enum_types: Vec<StructType<'ctx>> = enums
+            .iter()
+            .map(|enum_decl| self.context.opaque_struct_type(&enum_decl.name.name))
+            .collect();
+
+        for (struct_decl, struct_type) in structs.into_iter().zip(struct_types) {
+            let field_types = struct_decl
+                .fields
+                .iter()
+                .map(|field| {
+                    self.ast_type_to_llvm(&field.field_type)?
+                        .ok_or_else(|| format!("Void field type: {}.{}", struct_decl.name.name, field.name.name))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            struct_type.set_body(&field_types, false);
+            let field_names = struct_decl.fields.iter().map(|field| field.name.name.clone()).collect();
+            self.struct_fields.insert(struct_decl.name.name.clone(), field_names);
+        }
+        for (enum_decl, enum_type) in enums.into_iter().zip(enum_types) {
+            self.define_enum(enum_decl, enum_type)?;
+        }
+        Ok(())
+    }
+
+    /// Un enum es un struct con la etiqueta de la variante (un `i32` con su posición
+    /// en la declaración) seguida de la carga útil de cada variante, una tras otra.
+    /// Los campos de las variantes que no están activas valen cero.
+    fn define_enum(&mut self, enum_decl: &EnumDeclaration, enum_type: StructType<'ctx>) -> Result<(), String> {
+        let mut field_types: Vec<BasicTypeEnum<'ctx>> = vec![self.context.i32_type().into()];
+        let mut variants = Vec::new();
+        for variant in &enum_decl.variants {
+            let mut slots = Vec::new();
+            for payload_type in &variant.payload {
+                let payload_type = self.ast_type_to_llvm(payload_type)?
+                    .ok_or_else(|| format!("Void payload type: {}::{}", enum_decl.name.name, variant.name.name))?;
+                slots.push(field_types.len() as u32);
+                field_types.push(payload_type);
+            }
+            variants.push((variant.name.name.clone(), slots));
+        }
+        enum_type.set_body(&field_types, false);
+        self.enum_variants.insert(enum_decl.name.name.clone(), variants);
+        Ok(())
+    }
+
     fn compile_function(&mut self, function: &Function) -> Result<(), String> {
         let param_types: Vec<BasicMetadataTypeEnum> = function
             .parameters
@@ -107,6 +221,7 @@ impl<'ctx> Compiler<'ctx> {
 
         self.variables.clear();
         self.variable_types.clear();
+        self.function_types.clear();
 
         for (i, param) in function.parameters.iter().enumerate() {
             let param_val = fn_val.get_nth_param(i as u32).unwrap();
@@ -115,6 +230,10 @@ impl<'ctx> Compiler<'ctx> {
             self.builder.build_store(alloca, param_val).unwrap();
             self.variables.insert(param.name.name.clone(), alloca);
             self.variable_types.insert(param.name.name.clone(), param_type);
+            if let Type::Function(parameters, return_type) = &param.param_type {
+                let signature = self.function_signature(parameters, return_type)?;
+                self.function_types.insert(param.name.name.clone(), signature);
+            }
         }
 
         self.compile_block(&function.body)?;
@@ -138,6 +257,10 @@ impl<'ctx> Compiler<'ctx> {
 
     fn compile_block(&mut self, block: &Block) -> Result<(), String> {
         for declaration in &block.statements {
+            // Lo que sigue a un `return`, `break` o `continue` es inalcanzable.
+            if self.builder.get_insert_block().is_some_and(|block| block.get_terminator().is_some()) {
+                break;
+            }
             self.compile_block_declaration(declaration)?;
         }
         Ok(())
@@ -145,28 +268,37 @@ impl<'ctx> Compiler<'ctx> {
 
     fn compile_block_declaration(&mut self, declaration: &Declaration) -> Result<(), String> {
         match declaration {
-            Declaration::Variable(var) => {
-                let value = self.compile_expression(&var.value)?;
-                let var_type = value.get_type();
-                let alloca = self.create_entry_block_alloca(&var.identifier.name, var_type);
-                self.builder.build_store(alloca, value).unwrap();
-                self.variables.insert(var.identifier.name.clone(), alloca);
-                self.variable_types.insert(var.identifier.name.clone(), var_type);
-                Ok(())
-            }
+            Declaration::Variable(var) => self.compile_local(&var.identifier.name, var.var_type.as_ref(), &var.value),
             Declaration::Constant(const_decl) => {
-                let value = self.compile_expression(&const_decl.value)?;
-                let var_type = value.get_type();
-                let alloca = self.create_entry_block_alloca(&const_decl.identifier.name, var_type);
-                self.builder.build_store(alloca, value).unwrap();
-                self.variables.insert(const_decl.identifier.name.clone(), alloca);
-                self.variable_types.insert(const_decl.identifier.name.clone(), var_type);
-                Ok(())
+                self.compile_local(&const_decl.identifier.name, const_decl.const_type.as_ref(), &const_decl.value)
             }
             Declaration::Statement(stmt) => self.compile_statement(stmt),
             Declaration::Function(_) => Err("Nested functions not supported".to_string()),
-            Declaration::Struct(_) => Ok(()),
+            Declaration::Struct(_) | Declaration::Enum(_) | Declaration::Import(_) => Ok(()),
+        }
+    }
+
+    /// Reserva y guarda una variable local. Con un tipo escrito, la variable tiene ese
+    /// tipo y el valor se convierte a él (p. ej. de `Int` a `Int?`).
+    fn compile_local(&mut self, name: &str, declared_type: Option<&Type>, value: &Expression) -> Result<(), String> {
+        let signature = match declared_type {
+            Some(Type::Function(parameters, return_type)) => Some(self.function_signature(parameters, return_type)?),
+            _ => self.callee_signature(value),
+        };
+        let mut value = self.compile_expression(value)?;
+        if let Some(declared_type) = declared_type.map(|t| self.ast_type_to_llvm(t)).transpose()?.flatten() {
+            value = self.coerce(value, declared_type);
         }
+        let var_type = value.get_type();
+        let alloca = self.create_entry_block_alloca(name, var_type);
+        self.builder.build_store(alloca, value).unwrap();
+        self.variables.insert(name.to_string(), alloca);
+        self.variable_types.insert(name.to_string(), var_type);
+        match signature {
+            Some(signature) => self.function_types.insert(name.to_string(), signature),
+            None => self.function_types.remove(name),
+        };
+        Ok(())
     }
 
     fn compile_statement(&mut self, statement: &Statement) -> Result<(), String> {
@@ -176,7 +308,10 @@ impl<'ctx> Compiler<'ctx> {
                 Ok(())
             }
             Statement::Return(ret) => {
-                let value = self.compile_expression(&ret.value)?;
+                let mut value = self.compile_expression(&ret.value)?;
+                if let Some(return_type) = self.current_function.and_then(|f| f.get_type().get_return_type()) {
+                    value = self.coerce(value, return_type);
+                }
                 self.builder.build_return(Some(&value)).unwrap();
                 Ok(())
             }
@@ -184,31 +319,95 @@ impl<'ctx> Compiler<'ctx> {
             Statement::While(while_stmt) => self.compile_while(while_stmt),
             Statement::For(for_stmt) => self.compile_for(for_stmt),
             Statement::DoUntil(do_until) => self.compile_do_until(do_until),
+            Statement::Match(match_stmt) => self.compile_match(match_stmt),
+            Statement::Break(jump) => self.compile_jump(jump, true),
+            Statement::Continue(jump) => self.compile_jump(jump, false),
             Statement::Block(block) => self.compile_block(block),
+            Statement::Error(_) => Err("Cannot compile a statement with syntax errors".to_string()),
         }
     }
 
     fn compile_expression(&mut self, expression: &Expression) -> Result<BasicValueEnum<'ctx>, String> {
         match expression {
-            Expression::Literal(lit) => self.compile_literal(lit),
+            Expression::Literal(lit, _) => self.compile_literal(lit),
             Expression::Identifier(ident) => {
+                // El nombre de una función, usado como valor, es un puntero a ella.
+                if let (None, Some(function)) = (self.variables.get(&ident.name), self.module.get_function(&ident.name)) {
+                    return Ok(function.as_global_value().as_pointer_value().into());
+                }
                 let ptr = self.variables.get(&ident.name)
                     .ok_or_else(|| format!("Undefined variable: {}", ident.name))?;
                 let var_type = self.variable_types.get(&ident.name)
                     .ok_or_else(|| format!("Variable type not found: {}", ident.name))?;
                 Ok(self.builder.build_load(*var_type, *ptr, &ident.name).unwrap())
             }
-            Expression::Binary { left, op, right } => self.compile_binary(left, op, right),
-            Expression::Unary { op, expr } => self.compile_unary(op, expr),
-            Expression::Assignment { target, value } => {
+            Expression::Binary { left, op, right, .. } => self.compile_binary(left, op, right),
+            Expression::Unary { op, expr, .. } => self.compile_unary(op, expr),
+            Expression::Assignment { target, value, .. } => {
+                let (ptr, target_type) = self.compile_place(target)?;
                 let val = self.compile_expression(value)?;
-                let ptr = self.variables.get(&target.name)
-                    .ok_or_else(|| format!("Undefined variable: {}", target.name))?;
-                self.builder.build_store(*ptr, val).unwrap();
+                let val = self.coerce(val, target_type);
+                self.builder.build_store(ptr, val).unwrap();
                 Ok(val)
             }
-            Expression::FunctionCall { function, arguments } => self.compile_function_call(function, arguments),
-            Expression::Grouped(expr) => self.compile_expression(expr),
+            Expression::CompoundAssignment { target, op, value, .. } => {
+                // La dirección se calcula una vez y sirve tanto para leer como para escribir.
+                let (ptr, target_type) = self.compile_place(target)?;
+                let current = self.builder.build_load(target_type, ptr, "current").unwrap();
+                let rhs = self.compile_expression(value)?;
+                let val = self.build_binary(current, op, rhs)?;
+                self.builder.build_store(ptr, val).unwrap();
+                Ok(val)
+            }
+            Expression::FunctionCall { function, arguments, .. } => self.compile_function_call(function, arguments),
+            Expression::Grouped(expr, _) => self.compile_expression(expr),
+            Expression::InterpolatedString(parts, _) => self.compile_interpolated_string(parts),
+            Expression::Array(elements, _) => self.compile_array(elements),
+            Expression::Index { object, index, .. } => {
+                let (pointer, element_type) = self.compile_element_pointer(object, index)?;
+                Ok(self.builder.build_load(element_type, pointer, "elem").unwrap())
+            }
+            Expression::StructInstantiation { name, fields, .. } => {
+                let struct_type = self.module.get_struct_type(&name.name)
+                    .ok_or_else(|| format!("Undefined struct: {}", name.name))?;
+                let mut value = struct_type.get_undef();
+                for (field, field_value) in fields {
+                    let index = self.field_index(struct_type, &field.name)?;
+                    let field_value = self.compile_expression(field_value)?;
+                    let field_value = self.coerce(field_value, struct_type.get_field_type_at_index(index).unwrap());
+                    value = self.builder.build_insert_value(value, field_value, index, &field.name).unwrap().into_struct_value();
+                }
+                Ok(value.into())
+            }
+            Expression::MemberAccess { object, property, .. } => {
+                let value = match self.compile_expression(object)? {
+                    BasicValueEnum::StructValue(value) => value,
+                    _ => return Err(format!("Cannot access field '{}' of a non-struct value", property.name)),
+                };
+                let index = self.field_index(value.get_type(), &property.name)?;
+                Ok(self.builder.build_extract_value(value, index, &property.name).unwrap())
+            }
+            Expression::If { condition, then_branch, else_branch, .. } => {
+                self.compile_if_expression(condition, then_branch, else_branch)
+            }
+            Expression::Block { block, value } => {
+                self.compile_block(block)?;
+                // Si el bloque termina con un salto, su valor nunca se calcula; se
+                // compila en un bloque sin predecesores para que la IR siga siendo válida.
+                if self.builder.get_insert_block().is_some_and(|block| block.get_terminator().is_some()) {
+                    let func = self.current_function.ok_or("No current function")?;
+                    let dead_bb = self.context.append_basic_block(func, "afterjump");
+                    self.builder.position_at_end(dead_bb);
+                }
+                self.compile_expression(value)
+            }
+            Expression::EnumVariant { enum_name, variant, arguments, .. } => self.compile_enum_variant(enum_name, variant, arguments),
+            Expression::Is { value, enum_name, variant, .. } => {
+                let BasicValueEnum::StructValue(value) = self.compile_expression(value)? else {
+                    return Err(format!("Cannot check a non-enum value against '{}::{}'", enum_name.name, variant.name));
+                };
+                Ok(self.build_variant_test(value, &enum_name.name, &variant.name)?.into())
+            }
             _ => Err(format!("Unsupported expression type: {:?}", expression)),
         }
     }
@@ -218,25 +417,267 @@ impl<'ctx> Compiler<'ctx> {
             Literal::Int(val) => Ok(self.context.i64_type().const_int(*val as u64, true).into()),
             Literal::Float(val) => Ok(self.context.f64_type().const_float(*val).into()),
             Literal::Bool(val) => Ok(self.context.bool_type().const_int(*val as u64, false).into()),
+            // El lexer solo acepta caracteres ASCII, que caben en un byte.
+            Literal::Char(val) => Ok(self.context.i8_type().const_int(*val as u64, false).into()),
             Literal::String(val) => {
-                // Process escape sequences
-                let processed = val
-                    .replace("\\n", "\n")
-                    .replace("\\t", "\t")
-                    .replace("\\r", "\r")
-                    .replace("\\\\", "\\")
-                    .replace("\\\"", "\"")
-                    .replace("\\'", "'");
-                let global_str = self.builder.build_global_string_ptr(&processed, "str").unwrap();
+                // El lexer ya decodificó las secuencias de escape.
+                let global_str = self.builder.build_global_string_ptr(val, "str").unwrap();
                 Ok(global_str.as_basic_value_enum())
             }
         }
     }
 
+    /// Construye la cadena con `snprintf`: una primera llamada sin búfer calcula
+    /// la longitud, se reserva memoria con `malloc` y la segunda la escribe.
+    fn compile_interpolated_string(&mut self, parts: &[InterpolationPart]) -> Result<BasicValueEnum<'ctx>, String> {
+        let i64_type = self.context.i64_type();
+        let mut format = String::new();
+        let mut args: Vec<BasicMetadataValueEnum> = Vec::new();
+        for part in parts {
+            match part {
+                InterpolationPart::Text(text) => format.push_str(&text.replace('%', "%%")),
+                InterpolationPart::Expression(expr) => match self.compile_expression(expr)? {
+                    BasicValueEnum::IntValue(i) if i.get_type().get_bit_width() == 1 => {
+                        let true_str = self.builder.build_global_string_ptr("true", "true_str").unwrap();
+                        let false_str = self.builder.build_global_string_ptr("false", "false_str").unwrap();
+                        let text = self.builder.build_select(i, true_str.as_pointer_value(), false_str.as_pointer_value(), "boolstr").unwrap();
+                        format.push_str("%s");
+                        args.push(text.into());
+                    }
+                    BasicValueEnum::IntValue(i) if i.get_type().get_bit_width() == 8 => {
+                        // Los argumentos variádicos de tipo char se promueven a int.
+                        let value = self.builder.build_int_s_extend(i, self.context.i32_type(), "tmpext").unwrap();
+                        format.push_str("%c");
+                        args.push(value.into());
+                    }
+                    BasicValueEnum::IntValue(i) => {
+                        let value = self.builder.build_int_s_extend_or_bit_cast(i, i64_type, "tmpext").unwrap();
+                        format.push_str("%lld");
+                        args.push(value.into());
+                    }
+                    BasicValueEnum::FloatValue(f) => {
+                        format.push_str("%g");
+                        args.push(f.into());
+                    }
+                    BasicValueEnum::PointerValue(p) => {
+                        format.push_str("%s");
+                        args.push(p.into());
+                    }
+                    _ => return Err("Unsupported value in interpolated string".to_string()),
+                },
+            }
+        }
+
+        let snprintf = self.module.get_function("snprintf").ok_or("snprintf not declared")?;
+        let malloc = self.module.get_function("malloc").ok_or("malloc not declared")?;
+        let format_str = self.builder.build_global_string_ptr(&format, "fmt").unwrap();
+        let null = self.context.ptr_type(AddressSpace::default()).const_null();
+
+        let mut measure_args: Vec<BasicMetadataValueEnum> = vec![null.into(), i64_type.const_zero().into(), format_str.as_pointer_value().into()];
+        measure_args.extend(args.iter().cloned());
+        let length = self.builder.build_call(snprintf, &measure_args, "len").unwrap()
+            .try_as_basic_value().left().ok_or("snprintf returned void")?.into_int_value();
+        let length = self.builder.build_int_s_extend(length, i64_type, "len64").unwrap();
+        let size = self.builder.build_int_add(length, i64_type.const_int(1, false), "size").unwrap();
+        let buffer = self.builder.build_call(malloc, &[size.into()], "buf").unwrap()
+            .try_as_basic_value().left().ok_or("malloc returned void")?.into_pointer_value();
+
+        let mut write_args: Vec<BasicMetadataValueEnum> = vec![buffer.into(), size.into(), format_str.as_pointer_value().into()];
+        write_args.extend(args);
+        self.builder.build_call(snprintf, &write_args, "tmp").unwrap();
+        Ok(buffer.into())
+    }
+
+    /// Dirección de un destino de asignación (variable, elemento o campo) y el
+    /// tipo del valor que guarda. Los campos se alcanzan con un GEP sobre la
+    /// dirección del struct que los contiene, sin copiarlo.
+    fn compile_place(&mut self, target: &Expression) -> Result<(PointerValue<'ctx>, BasicTypeEnum<'ctx>), String> {
+        match target {
+            Expression::Identifier(ident) => {
+                let ptr = self.variables.get(&ident.name)
+                    .ok_or_else(|| format!("Undefined variable: {}", ident.name))?;
+                let var_type = self.variable_types.get(&ident.name)
+                    .ok_or_else(|| format!("Variable type not found: {}", ident.name))?;
+                Ok((*ptr, *var_type))
+            }
+            Expression::Index { object, index, .. } => self.compile_element_pointer(object, index),
+            Expression::MemberAccess { object, property, .. } => {
+                let (ptr, object_type) = self.compile_place(object)?;
+                let BasicTypeEnum::StructType(struct_type) = object_type else {
+                    return Err(format!("Cannot access field '{}' of a non-struct value", property.name));
+                };
+                let index = self.field_index(struct_type, &property.name)?;
+                let field_ptr = self.builder.build_struct_gep(struct_type, ptr, index, &property.name).unwrap();
+                let field_type = struct_type.get_field_type_at_index(index).ok_or("Invalid field index")?;
+                Ok((field_ptr, field_type))
+            }
+            _ => Err("Invalid assignment target".to_string()),
+        }
+    }
+
+    /// Posición del campo `field` en el struct LLVM `struct_type`.
+    fn field_index(&self, struct_type: StructType<'ctx>, field: &str) -> Result<u32, String> {
+        let struct_name = struct_type.get_name().and_then(|name| name.to_str().ok()).unwrap_or_default();
+        self.struct_fields
+            .get(struct_name)
+            .and_then(|fields| fields.iter().position(|name| name == field))
+            .map(|index| index as u32)
+            .ok_or_else(|| format!("Unknown field '{}' in struct '{}'", field, struct_name))
+    }
+
+    /// Construye `Enum::Variante(...)`: la etiqueta y la carga útil sobre un valor a cero.
+    fn compile_enum_variant(&mut self, enum_name: &Identifier, variant: &Identifier, arguments: &[Expression]) -> Result<BasicValueEnum<'ctx>, String> {
+        let enum_type = self.module.get_struct_type(&enum_name.name)
+            .ok_or_else(|| format!("Undefined enum: {}", enum_name.name))?;
+        let (tag, slots) = self.variant_layout(&enum_name.name, &variant.name)?;
+        let tag = self.context.i32_type().const_int(tag, false);
+        let mut value = self.builder.build_insert_value(enum_type.const_zero(), tag, 0, "tag").unwrap().into_struct_value();
+        for (slot, argument) in slots.into_iter().zip(arguments) {
+            let argument = self.compile_expression(argument)?;
+            let argument = self.coerce(argument, enum_type.get_field_type_at_index(slot).unwrap());
+            value = self.builder.build_insert_value(value, argument, slot, &variant.name).unwrap().into_struct_value();
+        }
+        Ok(value.into())
+    }
+
+    /// Etiqueta de `Enum::Variante` y posiciones de su carga útil en el struct del enum.
+    fn variant_layout(&self, enum_name: &str, variant: &str) -> Result<(u64, Vec<u32>), String> {
+        self.enum_variants
+            .get(enum_name)
+            .and_then(|variants| variants.iter().position(|(name, _)| name == variant).map(|tag| (tag, &variants[tag].1)))
+            .map(|(tag, slots)| (tag as u64, slots.clone()))
+            .ok_or_else(|| format!("Unknown variant '{}::{}'", enum_name, variant))
+    }
+
+    /// Si `value` es la variante `variant`: compara su etiqueta.
+    fn build_variant_test(&mut self, value: StructValue<'ctx>, enum_name: &str, variant: &str) -> Result<IntValue<'ctx>, String> {
+        let (tag, _) = self.variant_layout(enum_name, variant)?;
+        let actual = self.builder.build_extract_value(value, 0, "tag").unwrap().into_int_value();
+        let expected = actual.get_type().const_int(tag, false);
+        Ok(self.builder.build_int_compare(IntPredicate::EQ, actual, expected, "isvariant").unwrap())
+    }
+
+    /// `==` entre valores de un enum. Como los campos de las variantes inactivas
+    /// valen cero en ambos, basta con comparar la etiqueta y todos los campos.
+    fn build_enum_equality(&mut self, lhs: StructValue<'ctx>, rhs: StructValue<'ctx>) -> Result<IntValue<'ctx>, String> {
+        let mut equal: Option<IntValue<'ctx>> = None;
+        for index in 0..lhs.get_type().count_fields() {
+            let l = self.builder.build_extract_value(lhs, index, "lhsfield").unwrap();
+            let r = self.builder.build_extract_value(rhs, index, "rhsfield").unwrap();
+            let BasicValueEnum::IntValue(field_equal) = self.build_binary(l, &BinaryOp::DoubleEqual, r)? else {
+                return Err("Enum payload cannot be compared".to_string());
+            };
+            equal = Some(match equal {
+                Some(all) => self.builder.build_and(all, field_equal, "enumeq").unwrap(),
+                None => field_equal,
+            });
+        }
+        equal.ok_or_else(|| "Enum without tag".to_string())
+    }
+
+    /// Reserva los elementos con `malloc` y devuelve el arreglo como `{ longitud, datos }`.
+    fn compile_array(&mut self, elements: &[Expression]) -> Result<BasicValueEnum<'ctx>, String> {
+        let i64_type = self.context.i64_type();
+        let values = elements
+            .iter()
+            .map(|element| self.compile_expression(element))
+            .collect::<Result<Vec<_>, String>>()?;
+        // Un arreglo vacío no tiene de dónde sacar el tipo de elemento; se usa Int.
+        let element_type = values.first().map_or(i64_type.into(), |value| value.get_type());
+        let array_type = self.array_type(element_type);
+
+        let malloc = self.module.get_function("malloc").ok_or("malloc not declared")?;
+        let length = i64_type.const_int(values.len() as u64, false);
+        let element_size = element_type.size_of().ok_or("Array element type has no size")?;
+        let size = self.builder.build_int_mul(element_size, length, "arrsize").unwrap();
+        let data = self.builder.build_call(malloc, &[size.into()], "arrdata").unwrap()
+            .try_as_basic_value().left().ok_or("malloc returned void")?.into_pointer_value();
+        for (i, value) in values.into_iter().enumerate() {
+            let slot = unsafe {
+                self.builder.build_in_bounds_gep(element_type, data, &[i64_type.const_int(i as u64, false)], "arrslot").unwrap()
+            };
+            self.builder.build_store(slot, value).unwrap();
+        }
+
+        let array = self.builder.build_insert_value(array_type.get_undef(), length, 0, "arrlen").unwrap();
+        let array = self.builder.build_insert_value(array, data, 1, "arr").unwrap();
+        Ok(array.into_struct_value().into())
+    }
+
+    /// Calcula la dirección de `object[index]`. Antes comprueba que el índice esté
+    /// dentro del arreglo; si no lo está, el programa termina con un mensaje de error.
+    fn compile_element_pointer(&mut self, object: &Expression, index: &Expression) -> Result<(PointerValue<'ctx>, BasicTypeEnum<'ctx>), String> {
+        let array = match self.compile_expression(object)? {
+            BasicValueEnum::StructValue(array) => array,
+            _ => return Err("Only arrays can be indexed".to_string()),
+        };
+        let element_type = self.array_element_type(array).ok_or("Only arrays can be indexed")?;
+        let index = match self.compile_expression(index)? {
+            BasicValueEnum::IntValue(i) => i,
+            _ => return Err("Array index must be an integer".to_string()),
+        };
+        let length = self.builder.build_extract_value(array, 0, "len").unwrap().into_int_value();
+        let data = self.builder.build_extract_value(array, 1, "data").unwrap().into_pointer_value();
+
+        self.build_bounds_check(index, length)?;
+        let pointer = unsafe { self.builder.build_in_bounds_gep(element_type, data, &[index], "elemptr").unwrap() };
+        Ok((pointer, element_type))
+    }
+
+    /// Salta a un bloque que aborta el programa si `index` no está en `[0, length)`.
+    /// La comparación sin signo descarta también los índices negativos.
+    fn build_bounds_check(&mut self, index: IntValue<'ctx>, length: IntValue<'ctx>) -> Result<(), String> {
+        let func = self.current_function.ok_or("No current function")?;
+        let in_bounds = self.builder.build_int_compare(IntPredicate::ULT, index, length, "inbounds").unwrap();
+        let ok_bb = self.context.append_basic_block(func, "inbounds");
+        let fail_bb = self.context.append_basic_block(func, "outofbounds");
+        self.builder.build_conditional_branch(in_bounds, ok_bb, fail_bb).unwrap();
+
+        self.builder.position_at_end(fail_bb);
+        let printf = self.module.get_function("printf").ok_or("printf not declared")?;
+        let exit = self.module.get_function("exit").ok_or("exit not declared")?;
+        let message = self.builder
+            .build_global_string_ptr("Runtime error: index %lld out of bounds for array of length %lld\n", "oobmsg")
+            .unwrap();
+        self.builder.build_call(printf, &[message.as_pointer_value().into(), index.into(), length.into()], "tmp").unwrap();
+        self.builder.build_call(exit, &[self.context.i32_type().const_int(1, false).into()], "").unwrap();
+        self.builder.build_unreachable().unwrap();
+
+        self.builder.position_at_end(ok_bb);
+        Ok(())
+    }
+
+    /// Tipo LLVM de un arreglo: `{ i64, ptr }` con la longitud y los elementos.
+    /// Cada tipo de elemento tiene su propio struct con nombre (p. ej. `array.i64`),
+    /// para poder saber a partir del valor qué tipo tienen sus elementos.
+    fn array_type(&mut self, element_type: BasicTypeEnum<'ctx>) -> StructType<'ctx> {
+        let element_name = match element_type {
+            BasicTypeEnum::StructType(inner) => inner.get_name().map(|name| name.to_string_lossy().into_owned()),
+            _ => None,
+        };
+        let name = format!("array.{}", element_name.unwrap_or_else(|| element_type.print_to_string().to_string()));
+        if let Some(array_type) = self.module.get_struct_type(&name) {
+            return array_type;
+        }
+        let array_type = self.context.opaque_struct_type(&name);
+        array_type.set_body(&[self.context.i64_type().into(), self.context.ptr_type(AddressSpace::default()).into()], false);
+        self.array_elements.insert(name, element_type);
+        array_type
+    }
+
+    fn array_element_type(&self, array: StructValue<'ctx>) -> Option<BasicTypeEnum<'ctx>> {
+        let array_type = array.get_type();
+        let name = array_type.get_name()?.to_str().ok()?;
+        self.array_elements.get(name).copied()
+    }
+
     fn compile_binary(&mut self, left: &Expression, op: &BinaryOp, right: &Expression) -> Result<BasicValueEnum<'ctx>, String> {
         let lhs = self.compile_expression(left)?;
         let rhs = self.compile_expression(right)?;
+        self.build_binary(lhs, op, rhs)
+    }
 
+    fn build_binary(&mut self, lhs: BasicValueEnum<'ctx>, op: &BinaryOp, rhs: BasicValueEnum<'ctx>) -> Result<BasicValueEnum<'ctx>, String> {
         match (lhs, rhs) {
             (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
                 let result = match op {
@@ -244,6 +685,7 @@ impl<'ctx> Compiler<'ctx> {
                     BinaryOp::Minus => self.builder.build_int_sub(l, r, "tmpsub").unwrap(),
                     BinaryOp::Asterisk => self.builder.build_int_mul(l, r, "tmpmul").unwrap(),
                     BinaryOp::Slash => self.builder.build_int_signed_div(l, r, "tmpdiv").unwrap(),
+                    BinaryOp::Percent => self.builder.build_int_signed_rem(l, r, "tmprem").unwrap(),
                     BinaryOp::Greater => return Ok(self.builder.build_int_compare(IntPredicate::SGT, l, r, "tmpcmp").unwrap().into()),
                     BinaryOp::Less => return Ok(self.builder.build_int_compare(IntPredicate::SLT, l, r, "tmpcmp").unwrap().into()),
                     BinaryOp::GreaterEqual => return Ok(self.builder.build_int_compare(IntPredicate::SGE, l, r, "tmpcmp").unwrap().into()),
@@ -252,6 +694,11 @@ impl<'ctx> Compiler<'ctx> {
                     BinaryOp::NotEqual => return Ok(self.builder.build_int_compare(IntPredicate::NE, l, r, "tmpcmp").unwrap().into()),
                     BinaryOp::DoubleAmpersand => self.builder.build_and(l, r, "tmpand").unwrap(),
                     BinaryOp::DoubleBar => self.builder.build_or(l, r, "tmpor").unwrap(),
+                    BinaryOp::Ampersand => self.builder.build_and(l, r, "tmpbitand").unwrap(),
+                    BinaryOp::Bar => self.builder.build_or(l, r, "tmpbitor").unwrap(),
+                    BinaryOp::Caret => self.builder.build_xor(l, r, "tmpxor").unwrap(),
+                    BinaryOp::ShiftLeft => self.builder.build_left_shift(l, r, "tmpshl").unwrap(),
+                    BinaryOp::ShiftRight => self.builder.build_right_shift(l, r, true, "tmpshr").unwrap(),
                     _ => return Err(format!("Unsupported binary operation: {:?}", op)),
                 };
                 Ok(result.into())
@@ -262,6 +709,7 @@ impl<'ctx> Compiler<'ctx> {
                     BinaryOp::Minus => self.builder.build_float_sub(l, r, "tmpsub").unwrap(),
                     BinaryOp::Asterisk => self.builder.build_float_mul(l, r, "tmpmul").unwrap(),
                     BinaryOp::Slash => self.builder.build_float_div(l, r, "tmpdiv").unwrap(),
+                    BinaryOp::Percent => self.builder.build_float_rem(l, r, "tmprem").unwrap(),
                     BinaryOp::Greater => return Ok(self.builder.build_float_compare(FloatPredicate::OGT, l, r, "tmpcmp").unwrap().into()),
                     BinaryOp::Less => return Ok(self.builder.build_float_compare(FloatPredicate::OLT, l, r, "tmpcmp").unwrap().into()),
                     BinaryOp::GreaterEqual => return Ok(self.builder.build_float_compare(FloatPredicate::OGE, l, r, "tmpcmp").unwrap().into()),
@@ -272,10 +720,25 @@ impl<'ctx> Compiler<'ctx> {
                 };
                 Ok(result.into())
             }
+            (BasicValueEnum::StructValue(l), BasicValueEnum::StructValue(r))
+                if matches!(op, BinaryOp::DoubleEqual | BinaryOp::NotEqual) && self.is_enum(l.get_type()) =>
+            {
+                let equal = self.build_enum_equality(l, r)?;
+                match op {
+                    BinaryOp::NotEqual => Ok(self.builder.build_not(equal, "enumne").unwrap().into()),
+                    _ => Ok(equal.into()),
+                }
+            }
             _ => Err("Type mismatch in binary operation".to_string()),
         }
     }
 
+    fn is_enum(&self, struct_type: StructType<'ctx>) -> bool {
+        struct_type.get_name()
+            .and_then(|name| name.to_str().ok())
+            .is_some_and(|name| self.enum_variants.contains_key(name))
+    }
+
     fn compile_unary(&mut self, op: &UnaryOp, expr: &Expression) -> Result<BasicValueEnum<'ctx>, String> {
         let val = self.compile_expression(expr)?;
         match op {
@@ -288,6 +751,10 @@ impl<'ctx> Compiler<'ctx> {
                 BasicValueEnum::IntValue(i) => Ok(self.builder.build_not(i, "tmpnot").unwrap().into()),
                 _ => Err("Cannot negate non-boolean value".to_string()),
             },
+            UnaryOp::Tilde => match val {
+                BasicValueEnum::IntValue(i) => Ok(self.builder.build_not(i, "tmpbitnot").unwrap().into()),
+                _ => Err("Cannot apply '~' to a non-integer value".to_string()),
+            },
         }
     }
 
@@ -347,6 +814,41 @@ impl<'ctx> Compiler<'ctx> {
         Ok(())
     }
 
+    /// `if` como expresión (y `cond ? a : b`): cada rama calcula su valor y un
+    /// nodo phi en `ifexprcont` toma el de la rama por la que se llegó.
+    fn compile_if_expression(&mut self, condition: &Expression, then_branch: &Expression, else_branch: &Expression) -> Result<BasicValueEnum<'ctx>, String> {
+        let condition = match self.compile_expression(condition)? {
+            BasicValueEnum::IntValue(i) => i,
+            _ => return Err("Condition must be boolean".to_string()),
+        };
+
+        let func = self.current_function.ok_or("No current function")?;
+        let then_bb = self.context.append_basic_block(func, "ifexprthen");
+        let else_bb = self.context.append_basic_block(func, "ifexprelse");
+        let merge_bb = self.context.append_basic_block(func, "ifexprcont");
+        self.builder.build_conditional_branch(condition, then_bb, else_bb).unwrap();
+
+        // Una rama puede acabar en otro bloque que en el que empezó (p. ej. si
+        // contiene otro `if`); el phi necesita el bloque desde el que se salta.
+        self.builder.position_at_end(then_bb);
+        let then_value = self.compile_expression(then_branch)?;
+        let then_end = self.builder.get_insert_block().unwrap();
+        self.builder.build_unconditional_branch(merge_bb).unwrap();
+
+        self.builder.position_at_end(else_bb);
+        let else_value = self.compile_expression(else_branch)?;
+        let else_end = self.builder.get_insert_block().unwrap();
+        self.builder.build_unconditional_branch(merge_bb).unwrap();
+
+        if then_value.get_type() != else_value.get_type() {
+            return Err("Type mismatch between the branches of an if expression".to_string());
+        }
+        self.builder.position_at_end(merge_bb);
+        let phi = self.builder.build_phi(then_value.get_type(), "iftmp").unwrap();
+        phi.add_incoming(&[(&then_value, then_end), (&else_value, else_end)]);
+        Ok(phi.as_basic_value())
+    }
+
     fn compile_while(&mut self, while_stmt: &WhileStatement) -> Result<(), String> {
         let func = self.current_function.ok_or("No current function")?;
         let cond_bb = self.context.append_basic_block(func, "whilecond");
@@ -373,7 +875,7 @@ impl<'ctx> Compiler<'ctx> {
         self.builder.build_conditional_branch(condition, body_bb, after_bb).unwrap();
 
         self.builder.position_at_end(body_bb);
-        self.compile_block(&while_stmt.body)?;
+        self.compile_loop_body(&while_stmt.label, &while_stmt.body, after_bb, cond_bb)?;
         if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
             self.builder.build_unconditional_branch(cond_bb).unwrap();
         }
@@ -382,8 +884,151 @@ impl<'ctx> Compiler<'ctx> {
         Ok(())
     }
 
-    fn compile_for(&mut self, _for_stmt: &ForStatement) -> Result<(), String> {
-        Err("For loops not yet implemented".to_string())
+    /// Compila el cuerpo de un bucle con `break` saltando a `break_block` y
+    /// `continue` a `continue_block`.
+    fn compile_loop_body(&mut self, label: &Option<Identifier>, body: &Block, break_block: BasicBlock<'ctx>, continue_block: BasicBlock<'ctx>) -> Result<(), String> {
+        self.loop_targets.push(LoopTarget {
+            label: label.as_ref().map(|label| label.name.clone()),
+            break_block,
+            continue_block,
+        });
+        let result = self.compile_block(body);
+        self.loop_targets.pop();
+        result
+    }
+
+    fn compile_jump(&mut self, jump: &JumpStatement, is_break: bool) -> Result<(), String> {
+        let target = match &jump.label {
+            Some(label) => self.loop_targets.iter().rev()
+                .find(|target| target.label.as_ref() == Some(&label.name))
+                .ok_or_else(|| format!("Undefined loop label: {}", label.name))?,
+            None => self.loop_targets.last().ok_or("'break' or 'continue' outside of a loop")?,
+        };
+        let block = if is_break { target.break_block } else { target.continue_block };
+        self.builder.build_unconditional_branch(block).unwrap();
+        Ok(())
+    }
+
+    fn compile_for(&mut self, for_stmt: &ForStatement) -> Result<(), String> {
+        let i64_type = self.context.i64_type();
+        match &for_stmt.iterable {
+            Expression::Range { start, end, inclusive, step, .. } => {
+                let start = self.compile_int(start, "Range bounds must be integers")?;
+                let end = self.compile_int(end, "Range bounds must be integers")?;
+                let step = match step {
+                    Some(step) => self.compile_int(step, "Range step must be an integer")?,
+                    None => i64_type.const_int(1, false),
+                };
+                let counter = self.create_entry_block_alloca(&for_stmt.variable.name, i64_type);
+                self.builder.build_store(counter, start).unwrap();
+                self.compile_for_loop(for_stmt, counter, ForIteration::Range { end, step, inclusive: *inclusive })
+            }
+            iterable => {
+                let array = match self.compile_expression(iterable)? {
+                    BasicValueEnum::StructValue(array) => array,
+                    _ => return Err("Only ranges and arrays can be iterated".to_string()),
+                };
+                let element_type = self.array_element_type(array).ok_or("Only ranges and arrays can be iterated")?;
+                let length = self.builder.build_extract_value(array, 0, "len").unwrap().into_int_value();
+                let data = self.builder.build_extract_value(array, 1, "data").unwrap().into_pointer_value();
+                let counter = self.create_entry_block_alloca("index", i64_type);
+                self.builder.build_store(counter, i64_type.const_zero()).unwrap();
+                self.compile_for_loop(for_stmt, counter, ForIteration::Array { length, data, element_type })
+            }
+        }
+    }
+
+    /// Bucle común a los `for`: comprueba el contador en `forcond`, enlaza la
+    /// variable y ejecuta el cuerpo en `forbody`, y avanza el contador en `forstep`,
+    /// que es también adonde salta `continue`.
+    fn compile_for_loop(&mut self, for_stmt: &ForStatement, counter: PointerValue<'ctx>, iteration: ForIteration<'ctx>) -> Result<(), String> {
+        let func = self.current_function.ok_or("No current function")?;
+        let i64_type = self.context.i64_type();
+        let cond_bb = self.context.append_basic_block(func, "forcond");
+        let body_bb = self.context.append_basic_block(func, "forbody");
+        let step_bb = self.context.append_basic_block(func, "forstep");
+        let after_bb = self.context.append_basic_block(func, "afterfor");
+
+        // La variable de un arreglo se reserva una sola vez y se rellena en cada vuelta.
+        let variable = match &iteration {
+            ForIteration::Range { .. } => (counter, i64_type.as_basic_type_enum()),
+            ForIteration::Array { element_type, .. } => {
+                (self.create_entry_block_alloca(&for_stmt.variable.name, *element_type), *element_type)
+            }
+        };
+
+        self.builder.build_unconditional_branch(cond_bb).unwrap();
+        self.builder.position_at_end(cond_bb);
+        let current = self.builder.build_load(i64_type, counter, "counter").unwrap().into_int_value();
+        let condition = match &iteration {
+            ForIteration::Range { end, step, inclusive } => self.range_condition(current, *end, *step, *inclusive),
+            ForIteration::Array { length, .. } => {
+                self.builder.build_int_compare(IntPredicate::SLT, current, *length, "forcmp").unwrap()
+            }
+        };
+        self.builder.build_conditional_branch(condition, body_bb, after_bb).unwrap();
+
+        self.builder.position_at_end(body_bb);
+        if let ForIteration::Array { data, element_type, .. } = &iteration {
+            let pointer = unsafe { self.builder.build_in_bounds_gep(*element_type, *data, &[current], "elemptr").unwrap() };
+            let element = self.builder.build_load(*element_type, pointer, "element").unwrap();
+            self.builder.build_store(variable.0, element).unwrap();
+        }
+        let name = for_stmt.variable.name.clone();
+        let shadowed = (
+            self.variables.insert(name.clone(), variable.0),
+            self.variable_types.insert(name.clone(), variable.1),
+        );
+        let result = self.compile_loop_body(&for_stmt.label, &for_stmt.body, after_bb, step_bb);
+        match shadowed {
+            (Some(pointer), Some(var_type)) => {
+                self.variables.insert(name.clone(), pointer);
+                self.variable_types.insert(name, var_type);
+            }
+            _ => {
+                self.variables.remove(&name);
+                self.variable_types.remove(&name);
+            }
+        }
+        result?;
+        if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+            self.builder.build_unconditional_branch(step_bb).unwrap();
+        }
+
+        self.builder.position_at_end(step_bb);
+        let current = self.builder.build_load(i64_type, counter, "counter").unwrap().into_int_value();
+        let step = match iteration {
+            ForIteration::Range { step, .. } => step,
+            ForIteration::Array { .. } => i64_type.const_int(1, false),
+        };
+        let next = self.builder.build_int_add(current, step, "next").unwrap();
+        self.builder.build_store(counter, next).unwrap();
+        self.builder.build_unconditional_branch(cond_bb).unwrap();
+
+        self.builder.position_at_end(after_bb);
+        Ok(())
+    }
+
+    /// Condición para seguir en un rango: `current < end` (o `<=`) si el paso es
+    /// positivo y `current > end` (o `>=`) si es negativo. Con un paso constante,
+    /// que es lo habitual, solo se emite la comparación que corresponde.
+    fn range_condition(&self, current: IntValue<'ctx>, end: IntValue<'ctx>, step: IntValue<'ctx>, inclusive: bool) -> IntValue<'ctx> {
+        let (up, down) = if inclusive { (IntPredicate::SLE, IntPredicate::SGE) } else { (IntPredicate::SLT, IntPredicate::SGT) };
+        if let Some(step) = step.get_sign_extended_constant() {
+            let predicate = if step >= 0 { up } else { down };
+            return self.builder.build_int_compare(predicate, current, end, "forcmp").unwrap();
+        }
+        let ascending = self.builder.build_int_compare(IntPredicate::SGT, step, step.get_type().const_zero(), "ascending").unwrap();
+        let below = self.builder.build_int_compare(up, current, end, "below").unwrap();
+        let above = self.builder.build_int_compare(down, current, end, "above").unwrap();
+        self.builder.build_select(ascending, below, above, "forcmp").unwrap().into_int_value()
+    }
+
+    fn compile_int(&mut self, expression: &Expression, error: &str) -> Result<IntValue<'ctx>, String> {
+        match self.compile_expression(expression)? {
+            BasicValueEnum::IntValue(value) => Ok(value),
+            _ => Err(error.to_string()),
+        }
     }
 
     fn compile_do_until(&mut self, do_until: &DoUntilStatement) -> Result<(), String> {
@@ -395,7 +1040,7 @@ impl<'ctx> Compiler<'ctx> {
         self.builder.build_unconditional_branch(body_bb).unwrap();
         self.builder.position_at_end(body_bb);
 
-        self.compile_block(&do_until.body)?;
+        self.compile_loop_body(&do_until.label, &do_until.body, after_bb, cond_bb)?;
         if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
             self.builder.build_unconditional_branch(cond_bb).unwrap();
         }
@@ -420,12 +1065,230 @@ impl<'ctx> Compiler<'ctx> {
         Ok(())
     }
 
+    /// Compila un `match`. Si el valor es entero (o carácter o booleano), los
+    /// primeros brazos sin guarda cuyos patrones son literales o rangos pequeños se
+    /// reparten con un `switch`; los demás se prueban en orden, cada uno en su
+    /// bloque `matchtest`, y el primero que encaja ejecuta su cuerpo.
+    fn compile_match(&mut self, match_stmt: &MatchStatement) -> Result<(), String> {
+        let func = self.current_function.ok_or("No current function")?;
+        let subject = self.compile_expression(&match_stmt.subject)?;
+        let switch_arms: Vec<Vec<i64>> = match subject {
+            BasicValueEnum::IntValue(_) => match_stmt.arms.iter()
+                .map_while(|arm| arm.guard.is_none().then(|| switch_values(&arm.pattern)).flatten())
+                .collect(),
+            _ => Vec::new(),
+        };
+        let after_bb = self.context.append_basic_block(func, "aftermatch");
+        let mut next_bb = self.context.append_basic_block(func, "matchtest");
+        let mut falls_through = false;
+
+        match subject {
+            BasicValueEnum::IntValue(value) if !switch_arms.is_empty() => {
+                let arm_blocks: Vec<BasicBlock<'ctx>> = switch_arms.iter()
+                    .map(|_| self.context.append_basic_block(func, "matcharm"))
+                    .collect();
+                // Si un valor aparece en varios brazos, se queda con el primero.
+                let mut seen = HashSet::new();
+                let cases: Vec<(IntValue<'ctx>, BasicBlock<'ctx>)> = switch_arms.iter()
+                    .zip(&arm_blocks)
+                    .flat_map(|(values, block)| values.iter().map(move |value| (*value, *block)))
+                    .filter(|(value, _)| seen.insert(*value))
+                    .map(|(case, block)| (value.get_type().const_int(case as u64, true), block))
+                    .collect();
+                self.builder.build_switch(value, next_bb, &cases).unwrap();
+
+                for (arm, arm_bb) in match_stmt.arms.iter().zip(arm_blocks) {
+                    self.builder.position_at_end(arm_bb);
+                    self.compile_block(&arm.body)?;
+                    if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                        self.builder.build_unconditional_branch(after_bb).unwrap();
+                        falls_through = true;
+                    }
+                }
+            }
+            _ => {
+                self.builder.build_unconditional_branch(next_bb).unwrap();
+            }
+        }
+
+        for arm in &match_stmt.arms[switch_arms.len()..] {
+            self.builder.position_at_end(next_bb);
+            let arm_bb = self.context.append_basic_block(func, "matcharm");
+            next_bb = self.context.append_basic_block(func, "matchtest");
+
+            // Las variables del patrón solo existen dentro de su brazo.
+            let saved = (self.variables.clone(), self.variable_types.clone());
+            let matches = self.compile_pattern_test(&arm.pattern, subject)?;
+            if let Some(guard) = &arm.guard {
+                // La guarda solo se evalúa si el patrón encaja.
+                if let Some(matches) = matches {
+                    let guard_bb = self.context.append_basic_block(func, "matchguard");
+                    self.builder.build_conditional_branch(matches, guard_bb, next_bb).unwrap();
+                    self.builder.position_at_end(guard_bb);
+                }
+                let guard = match self.compile_expression(guard)? {
+                    BasicValueEnum::IntValue(guard) => guard,
+                    _ => return Err("Match guard must be boolean".to_string()),
+                };
+                self.builder.build_conditional_branch(guard, arm_bb, next_bb).unwrap();
+            } else if let Some(matches) = matches {
+                self.builder.build_conditional_branch(matches, arm_bb, next_bb).unwrap();
+            } else {
+                self.builder.build_unconditional_branch(arm_bb).unwrap();
+            }
+
+            self.builder.position_at_end(arm_bb);
+            let result = self.compile_block(&arm.body);
+            (self.variables, self.variable_types) = saved;
+            result?;
+            if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                self.builder.build_unconditional_branch(after_bb).unwrap();
+                falls_through = true;
+            }
+        }
+
+        // Ningún brazo encajó; el análisis semántico ya comprobó que no puede pasar.
+        self.builder.position_at_end(next_bb);
+        self.builder.build_unreachable().unwrap();
+
+        if falls_through {
+            after_bb.move_after(next_bb).unwrap();
+            self.builder.position_at_end(after_bb);
+        } else {
+            // Todos los brazos terminan en un salto, así que no hay nada después del `match`.
+            unsafe {
+                after_bb.delete().ok();
+            }
+        }
+        Ok(())
+    }
+
+    /// Comprueba si `value` encaja con `pattern` y enlaza las variables que el
+    /// patrón introduce. Devuelve `None` si encaja siempre.
+    fn compile_pattern_test(&mut self, pattern: &Pattern, value: BasicValueEnum<'ctx>) -> Result<Option<IntValue<'ctx>>, String> {
+        match pattern {
+            Pattern::Wildcard(_) => Ok(None),
+            Pattern::Binding(ident) => {
+                let var_type = value.get_type();
+                let alloca = self.create_entry_block_alloca(&ident.name, var_type);
+                self.builder.build_store(alloca, value).unwrap();
+                self.variables.insert(ident.name.clone(), alloca);
+                self.variable_types.insert(ident.name.clone(), var_type);
+                Ok(None)
+            }
+            Pattern::Literal(literal, _) => {
+                let expected = self.compile_literal(literal)?;
+                let matches = match (value, expected) {
+                    (BasicValueEnum::IntValue(v), BasicValueEnum::IntValue(e)) => {
+                        self.builder.build_int_compare(IntPredicate::EQ, v, e, "patcmp").unwrap()
+                    }
+                    (BasicValueEnum::FloatValue(v), BasicValueEnum::FloatValue(e)) => {
+                        self.builder.build_float_compare(FloatPredicate::OEQ, v, e, "patcmp").unwrap()
+                    }
+                    (BasicValueEnum::PointerValue(v), BasicValueEnum::PointerValue(e)) => {
+                        let strcmp = self.module.get_function("strcmp").ok_or("strcmp not declared")?;
+                        let order = self.builder.build_call(strcmp, &[v.into(), e.into()], "strcmp").unwrap()
+                            .try_as_basic_value().left().ok_or("strcmp returned void")?.into_int_value();
+                        self.builder.build_int_compare(IntPredicate::EQ, order, order.get_type().const_zero(), "patcmp").unwrap()
+                    }
+                    _ => return Err("Type mismatch in match pattern".to_string()),
+                };
+                Ok(Some(matches))
+            }
+            Pattern::Range { start, end, inclusive, .. } => {
+                let (BasicValueEnum::IntValue(value), BasicValueEnum::IntValue(low), BasicValueEnum::IntValue(high)) =
+                    (value, self.compile_literal(start)?, self.compile_literal(end)?)
+                else {
+                    return Err("Range patterns must be integers or characters".to_string());
+                };
+                // Int tiene signo; los caracteres (ASCII) y booleanos se comparan sin él.
+                let signed = value.get_type().get_bit_width() == 64;
+                let (lower, upper) = match (signed, *inclusive) {
+                    (true, true) => (IntPredicate::SGE, IntPredicate::SLE),
+                    (true, false) => (IntPredicate::SGE, IntPredicate::SLT),
+                    (false, true) => (IntPredicate::UGE, IntPredicate::ULE),
+                    (false, false) => (IntPredicate::UGE, IntPredicate::ULT),
+                };
+                let above = self.builder.build_int_compare(lower, value, low, "patlow").unwrap();
+                let below = self.builder.build_int_compare(upper, value, high, "pathigh").unwrap();
+                Ok(Some(self.builder.build_and(above, below, "patrange").unwrap()))
+            }
+            Pattern::Struct { name, fields, .. } => {
+                let BasicValueEnum::StructValue(value) = value else {
+                    return Err(format!("Cannot match a non-struct value against '{}'", name.name));
+                };
+                let mut matches: Option<IntValue<'ctx>> = None;
+                for field in fields {
+                    let index = self.field_index(value.get_type(), &field.name.name)?;
+                    let field_value = self.builder.build_extract_value(value, index, &field.name.name).unwrap();
+                    let field_matches = self.compile_pattern_test(&field.pattern, field_value)?;
+                    matches = match (matches, field_matches) {
+                        (Some(all), Some(this)) => Some(self.builder.build_and(all, this, "patfields").unwrap()),
+                        (all, this) => all.or(this),
+                    };
+                }
+                Ok(matches)
+            }
+            Pattern::Variant { enum_name, variant, payload, .. } => {
+                let BasicValueEnum::StructValue(value) = value else {
+                    return Err(format!("Cannot match a non-enum value against '{}::{}'", enum_name.name, variant.name));
+                };
+                let is_variant = self.build_variant_test(value, &enum_name.name, &variant.name)?;
+                let (_, slots) = self.variant_layout(&enum_name.name, &variant.name)?;
+                // Enlazar la carga útil no cuesta nada aunque la variante sea otra: solo se
+                // lee del valor. Lo que haya que comprobar en ella se prueba aparte.
+                if payload.iter().all(|pattern| matches!(pattern, Pattern::Wildcard(_) | Pattern::Binding(_))) {
+                    for (slot, pattern) in slots.into_iter().zip(payload) {
+                        let field_value = self.builder.build_extract_value(value, slot, "payload").unwrap();
+                        self.compile_pattern_test(pattern, field_value)?;
+                    }
+                    return Ok(Some(is_variant));
+                }
+
+                // La carga útil solo se prueba si la etiqueta coincide; si no, sus campos valen cero.
+                let func = self.current_function.ok_or("No current function")?;
+                let tag_bb = self.builder.get_insert_block().unwrap();
+                let payload_bb = self.context.append_basic_block(func, "matchpayload");
+                let join_bb = self.context.append_basic_block(func, "matchvariant");
+                self.builder.build_conditional_branch(is_variant, payload_bb, join_bb).unwrap();
+                self.builder.position_at_end(payload_bb);
+                let mut matches: Option<IntValue<'ctx>> = None;
+                for (slot, pattern) in slots.into_iter().zip(payload) {
+                    let field_value = self.builder.build_extract_value(value, slot, "payload").unwrap();
+                    let field_matches = self.compile_pattern_test(pattern, field_value)?;
+                    matches = match (matches, field_matches) {
+                        (Some(all), Some(this)) => Some(self.builder.build_and(all, this, "patpayload").unwrap()),
+                        (all, this) => all.or(this),
+                    };
+                }
+                let payload_end = self.builder.get_insert_block().unwrap();
+                self.builder.build_unconditional_branch(join_bb).unwrap();
+
+                self.builder.position_at_end(join_bb);
+                let bool_type = self.context.bool_type();
+                let payload_matches = matches.unwrap_or_else(|| bool_type.const_int(1, false));
+                let phi = self.builder.build_phi(bool_type, "patvariant").unwrap();
+                phi.add_incoming(&[(&bool_type.const_zero(), tag_bb), (&payload_matches, payload_end)]);
+                Ok(Some(phi.as_basic_value().into_int_value()))
+            }
+            Pattern::Error(_) => Err("Cannot compile a pattern with syntax errors".to_string()),
+        }
+    }
+
     fn compile_function_call(&mut self, function: &Expression, arguments: &[Expression]) -> Result<BasicValueEnum<'ctx>, String> {
         let func_name = match function {
             Expression::Identifier(ident) => &ident.name,
             _ => return Err("Function call target must be an identifier".to_string()),
         };
 
+        // Una variable de tipo función se llama a través del puntero que guarda.
+        if let Some(signature) = self.function_types.get(func_name).copied() {
+            let pointer = self.compile_expression(function)?.into_pointer_value();
+            let args = self.compile_arguments(arguments, &signature.get_param_types())?;
+            let call_site = self.builder.build_indirect_call(signature, pointer, &args, "tmp").unwrap();
+            return call_site.try_as_basic_value().left().ok_or("Function call returned void".to_string());
+        }
+
         let func = self.module.get_function(func_name)
             .ok_or_else(|| format!("Undefined function: {}", func_name))?;
 
@@ -452,16 +1315,85 @@ impl<'ctx> Compiler<'ctx> {
                 })
                 .collect::<Result<Vec<_>, _>>()?
         } else {
-            arguments
-                .iter()
-                .map(|arg| self.compile_expression(arg).map(|v| v.into()))
-                .collect::<Result<Vec<_>, _>>()?
+            self.compile_arguments(arguments, &func.get_type().get_param_types())?
         };
 
         let call_site = self.builder.build_call(func, &args, "tmp").unwrap();
         call_site.try_as_basic_value().left().ok_or("Function call returned void".to_string())
     }
 
+    /// Compila los argumentos de una llamada y los convierte al tipo de su parámetro.
+    /// Como en C, los char que van a la parte variádica (ej. printf("%c")) se promueven a int.
+    fn compile_arguments(&mut self, arguments: &[Expression], param_types: &[BasicMetadataTypeEnum<'ctx>]) -> Result<Vec<BasicMetadataValueEnum<'ctx>>, String> {
+        arguments
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| {
+                let value = self.compile_expression(arg)?;
+                match (param_types.get(i), value) {
+                    (Some(param_type), value) => Ok(match BasicTypeEnum::try_from(*param_type) {
+                        Ok(param_type) => self.coerce(value, param_type).into(),
+                        Err(_) => value.into(),
+                    }),
+                    (None, BasicValueEnum::IntValue(v)) if v.get_type().get_bit_width() == 8 => {
+                        Ok(self.builder.build_int_s_extend(v, self.context.i32_type(), "charext").unwrap().into())
+                    }
+                    (None, value) => Ok(value.into()),
+                }
+            })
+            .collect()
+    }
+
+    /// Firma de la función a la que se refiere `expression`, si es el nombre de una
+    /// función o de una variable que guarda una.
+    fn callee_signature(&self, expression: &Expression) -> Option<FunctionType<'ctx>> {
+        let Expression::Identifier(ident) = expression else { return None };
+        match self.function_types.get(&ident.name) {
+            Some(signature) => Some(*signature),
+            None if !self.variables.contains_key(&ident.name) => self.module.get_function(&ident.name).map(|f| f.get_type()),
+            None => None,
+        }
+    }
+
+    fn function_signature(&mut self, parameters: &[Type], return_type: &Type) -> Result<FunctionType<'ctx>, String> {
+        let param_types = parameters
+            .iter()
+            .map(|p| self.ast_type_to_llvm(p)?.map(BasicMetadataTypeEnum::from).ok_or_else(|| "Void parameter type".to_string()))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(match self.ast_type_to_llvm(return_type)? {
+            Some(t) => t.fn_type(&param_types, false),
+            None => self.context.void_type().fn_type(&param_types, false),
+        })
+    }
+
+    /// Tipo LLVM de `T?`: `{ i1, T }`, con un indicador de si hay valor y el valor.
+    fn optional_type(&self, inner: BasicTypeEnum<'ctx>) -> StructType<'ctx> {
+        let inner_name = match inner {
+            BasicTypeEnum::StructType(inner) => inner.get_name().map(|name| name.to_string_lossy().into_owned()),
+            _ => None,
+        };
+        let name = format!("optional.{}", inner_name.unwrap_or_else(|| inner.print_to_string().to_string()));
+        if let Some(optional_type) = self.module.get_struct_type(&name) {
+            return optional_type;
+        }
+        let optional_type = self.context.opaque_struct_type(&name);
+        optional_type.set_body(&[self.context.bool_type().into(), inner], false);
+        optional_type
+    }
+
+    /// Convierte `value` al tipo `target` donde el lenguaje lo hace implícitamente:
+    /// un valor de `T` guardado como `T?` queda envuelto con el indicador encendido.
+    fn coerce(&mut self, value: BasicValueEnum<'ctx>, target: BasicTypeEnum<'ctx>) -> BasicValueEnum<'ctx> {
+        let BasicTypeEnum::StructType(optional_type) = target else { return value };
+        let is_optional = optional_type.get_name().is_some_and(|name| name.to_bytes().starts_with(b"optional."));
+        if value.get_type() == target || !is_optional || optional_type.get_field_type_at_index(1) != Some(value.get_type()) {
+            return value;
+        }
+        let present = self.context.bool_type().const_int(1, false);
+        let wrapped = self.builder.build_insert_value(optional_type.const_zero(), present, 0, "some").unwrap();
+        self.builder.build_insert_value(wrapped, value, 1, "optional").unwrap().into_struct_value().into()
+    }
+
     fn compile_global_variable(&mut self, var: &VariableDeclaration) -> Result<(), String> {
         let value = self.compile_expression(&var.value)?;
         let global = self.module.add_global(value.get_type(), Some(AddressSpace::default()), &var.identifier.name);
@@ -501,14 +1433,51 @@ impl<'ctx> Compiler<'ctx> {
         builder.build_alloca(ty, name).unwrap()
     }
 
-    fn ast_type_to_llvm(&self, ast_type: &Type) -> Result<Option<BasicTypeEnum<'ctx>>, String> {
+    fn ast_type_to_llvm(&mut self, ast_type: &Type) -> Result<Option<BasicTypeEnum<'ctx>>, String> {
         match ast_type {
             Type::Int => Ok(Some(self.context.i64_type().into())),
             Type::Float => Ok(Some(self.context.f64_type().into())),
             Type::Bool => Ok(Some(self.context.bool_type().into())),
+            Type::Char => Ok(Some(self.context.i8_type().into())),
             Type::String => Ok(Some(self.context.ptr_type(AddressSpace::default()).into())),
             Type::Void => Ok(None),
+            // Un arreglo de longitud fija tiene la misma forma; la longitud solo la comprueba el análisis semántico.
+            Type::Array(element) | Type::FixedArray(element, _) => {
+                let element_type = self.ast_type_to_llvm(element)?.ok_or("Void array element type")?;
+                Ok(Some(self.array_type(element_type).into()))
+            }
+            Type::Function(..) => Ok(Some(self.context.ptr_type(AddressSpace::default()).into())),
+            Type::Optional(inner) => {
+                let inner_type = self.ast_type_to_llvm(inner)?.ok_or("Void optional type")?;
+                Ok(Some(self.optional_type(inner_type).into()))
+            }
+            Type::Struct(name) => self.module.get_struct_type(name)
+                .map(|struct_type| Some(struct_type.into()))
+                .ok_or_else(|| format!("Undefined struct: {}", name)),
+            Type::Enum(name) => self.module.get_struct_type(name)
+                .map(|enum_type| Some(enum_type.into()))
+                .ok_or_else(|| format!("Undefined enum: {}", name)),
+        }
+    }
+}
+
+/// Valores con los que encaja un patrón literal o un rango pequeño, como casos de
+/// un `switch`. `None` si el patrón no se puede repartir así.
+fn switch_values(pattern: &Pattern) -> Option<Vec<i64>> {
+    let ordinal = |literal: &Literal| match literal {
+        Literal::Int(value) => Some(*value),
+        Literal::Char(value) => Some(*value as i64),
+        Literal::Bool(value) => Some(*value as i64),
+        Literal::Float(_) | Literal::String(_) => None,
+    };
+    match pattern {
+        Pattern::Literal(literal, _) => ordinal(literal).map(|value| vec![value]),
+        Pattern::Range { start, end, inclusive, .. } => {
+            let (low, high) = (ordinal(start)?, ordinal(end)?);
+            let high = if *inclusive { high } else { high.checked_sub(1)? };
+            (high.checked_sub(low)? < MAX_SWITCH_RANGE).then(|| (low..=high).collect())
         }
+        _ => None,
     }
 }
 
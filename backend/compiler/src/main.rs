@@ -7,6 +7,7 @@ mod reflection; // <-- Add this
 mod semantic_analyzer;
 mod symbol_table;
 mod llvm_compiler;
+mod modules;
 
 use crate::grpc_services::{CompilerService, LexerService, ParserService, compiler::compiler_server::CompilerServer, compiler::lexer_server::LexerServer, compiler::parser_server::ParserServer};
 use tonic::transport::Server;
@@ -0,0 +1,166 @@
+//! Programas de varios archivos. A partir del archivo principal se siguen sus
+//! `import`, resolviendo cada ruta respecto de la carpeta del archivo que la
+//! contiene, y se detectan las importaciones circulares.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use crate::ast::{Declaration, ParseResult, Program, Span, SyntaxError};
+use crate::lexer::LexError;
+use crate::parser::parse_source;
+
+/// De dónde se leen los archivos del programa.
+pub trait SourceLoader {
+    fn load(&self, path: &Path) -> Option<String>;
+}
+
+/// Una función que lee el archivo, p. ej. del disco.
+impl<F: Fn(&Path) -> Option<String>> SourceLoader for F {
+    fn load(&self, path: &Path) -> Option<String> {
+        self(path)
+    }
+}
+
+/// Archivos en memoria, p. ej. los que llegan en una petición gRPC.
+impl SourceLoader for HashMap<PathBuf, String> {
+    fn load(&self, path: &Path) -> Option<String> {
+        self.get(path).cloned()
+    }
+}
+
+/// Un archivo del programa, ya analizado sintácticamente.
+#[derive(Debug)]
+pub struct Module {
+    pub path: PathBuf,
+    pub source: String,
+    pub ast: Program,
+    pub imports: Vec<PathBuf>, // Rutas resueltas de sus `import`, en orden
+    pub lex_errors: Vec<LexError>,
+    pub syntax_errors: Vec<SyntaxError>,
+}
+
+impl Module {
+    /// Nombre del módulo en la tabla de símbolos.
+    pub fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModuleError {
+    /// Archivo que no se pudo leer y, si no es el principal, el archivo y el `import` que lo pedían.
+    FileNotFound(PathBuf, Option<(PathBuf, Span)>),
+    /// Archivos que se importan en círculo (el primero se repite al final) y el `import` que cierra el ciclo.
+    ImportCycle(Vec<PathBuf>, Span),
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModuleError::FileNotFound(path, None) => write!(f, "No se encontró el archivo '{}'", path.display()),
+            ModuleError::FileNotFound(path, Some((importer, span))) => write!(
+                f,
+                "No se encontró el archivo '{}', importado en '{}', línea {}, columna {}",
+                path.display(),
+                importer.display(),
+                span.start.line,
+                span.start.column
+            ),
+            ModuleError::ImportCycle(cycle, _) => {
+                let cycle: Vec<String> = cycle.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "Importación circular: {}", cycle.join(" -> "))
+            }
+        }
+    }
+}
+
+/// Carga el archivo `entry` y todos los que importa, directa o indirectamente.
+/// Cada archivo aparece una sola vez y después de los que importa, así que el
+/// principal es el último. Un `import` que no se puede cargar o que cierra un
+/// ciclo se reporta y se omite; los errores léxicos y de sintaxis quedan en cada módulo.
+pub fn load_program(entry: &Path, loader: &impl SourceLoader) -> (Vec<Module>, Vec<ModuleError>) {
+    let mut loading = Loading { loader, importers: Vec::new(), modules: Vec::new(), errors: Vec::new() };
+    loading.load(normalize(entry), None);
+    (loading.modules, loading.errors)
+}
+
+struct Loading<'a, L: SourceLoader> {
+    loader: &'a L,
+    importers: Vec<PathBuf>, // Archivos que se están cargando, del principal al actual
+    modules: Vec<Module>,
+    errors: Vec<ModuleError>,
+}
+
+impl<L: SourceLoader> Loading<'_, L> {
+    fn load(&mut self, path: PathBuf, imported_from: Option<(PathBuf, Span)>) {
+        if let Some(start) = self.importers.iter().position(|p| *p == path) {
+            let mut cycle = self.importers[start..].to_vec();
+            cycle.push(path);
+            let span = imported_from.map(|(_, span)| span).unwrap_or_default();
+            self.errors.push(ModuleError::ImportCycle(cycle, span));
+            return;
+        }
+        if self.modules.iter().any(|m| m.path == path) {
+            return;
+        }
+
+        let Some(source) = self.loader.load(&path) else {
+            self.errors.push(ModuleError::FileNotFound(path, imported_from));
+            return;
+        };
+        let (ParseResult { ast, errors }, lex_errors) = parse_source(&source);
+        let imports: Vec<(PathBuf, Span)> = ast
+            .declarations
+            .iter()
+            .filter_map(|d| match d {
+                Declaration::Import(import) => Some((resolve(&path, &import.path), import.span)),
+                _ => None,
+            })
+            .collect();
+
+        self.importers.push(path.clone());
+        for (import, span) in &imports {
+            self.load(import.clone(), Some((path.clone(), *span)));
+        }
+        self.importers.pop();
+
+        self.modules.push(Module {
+            path,
+            source,
+            ast,
+            imports: imports.into_iter().map(|(import, _)| import).collect(),
+            lex_errors,
+            syntax_errors: errors,
+        });
+    }
+}
+
+/// Une las declaraciones de todos los módulos en un solo programa, en el orden
+/// de carga, para compilarlo como un único módulo LLVM.
+pub fn link(modules: &[Module]) -> Program {
+    Program {
+        declarations: modules.iter().flat_map(|m| m.ast.declarations.iter().cloned()).collect(),
+        span: modules.last().map_or_else(Span::default, |entry| entry.ast.span),
+    }
+}
+
+/// Ruta de un `import` respecto de la carpeta del archivo que lo contiene.
+fn resolve(importer: &Path, import: &str) -> PathBuf {
+    normalize(&importer.parent().unwrap_or(Path::new("")).join(import))
+}
+
+/// Quita los `.` y `..` intermedios, para que cada archivo tenga una sola ruta.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(_))) => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
@@ -1,34 +1,157 @@
+use std::iter::{self, Map};
+use std::slice::Iter;
+
 use crate::ast::*;
-use crate::token::{LexerToken, TokenType};
+use crate::lexer::{interpolation_parts, LexError, LexicalAnalyzer, StringPart};
+use crate::token::{LexerToken, Position, Token, TokenType};
+
+/// Tokens que el parser descarta: espacios, comentarios y tokens inválidos
+/// (estos últimos ya fueron reportados por el lexer).
+fn is_trivia(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Whitespace | TokenType::NewLine | TokenType::CommentSingle
+            | TokenType::CommentMultiLine | TokenType::Unknown
+    )
+}
+
+/// Palabras clave que inician una declaración o sentencia: puntos seguros
+/// donde retomar el análisis después de un error.
+fn starts_statement(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Fn | TokenType::Let | TokenType::Const | TokenType::Return | TokenType::If
+            | TokenType::While | TokenType::For | TokenType::Struct | TokenType::Enum | TokenType::Import | TokenType::Do | TokenType::Until
+            | TokenType::Break | TokenType::Continue | TokenType::Label | TokenType::Match
+    )
+}
 
-pub struct Parser<'a> {
-    tokens: &'a [LexerToken],
-    current: usize,
+/// Tokens que cierran una construcción o empiezan otra. Cuando falta una
+/// expresión delante de ellos no se consumen, para que el llamador se recupere.
+fn is_recovery_point(token_type: TokenType) -> bool {
+    starts_statement(token_type)
+        || matches!(
+            token_type,
+            TokenType::Semicolon | TokenType::RightParen | TokenType::RightBracket
+                | TokenType::RightBrace | TokenType::Comma | TokenType::EndOfFile
+        )
+}
+
+/// Parser descendente recursivo. Extrae los tokens de `I` a medida que los
+/// necesita, con como mucho dos tokens de anticipación.
+///
+/// Tolera errores: lo que no se puede analizar queda como `Expression::Error`
+/// o `Statement::Error`, de modo que el AST siempre cubre todo el documento.
+pub struct Parser<'src, I: Iterator<Item = Token<'src>>> {
+    tokens: I,
+    current: Option<Token<'src>>,
+    current_doc: Option<String>, // Documentación que precede al token actual
+    lookahead: Option<(Token<'src>, Option<String>)>, // Token siguiente al actual, si ya se pidió
+    previous: Option<Token<'src>>,
+    last_error_at: Option<usize>, // Offset del token donde se reportó el último error
+    struct_literals_allowed: bool, // Falso en la cabecera de un `for`, donde `{` abre el cuerpo
+    trailing_value_allowed: bool, // Verdadero en un bloque que da un valor, cuya última expresión va sin ';'
     pub errors: Vec<SyntaxError>,
 }
 
-impl<'a> Parser<'a> {
+/// Tokens prestados de una lista ya escaneada.
+pub type SliceTokens<'a> = Map<Iter<'a, LexerToken>, fn(&'a LexerToken) -> Token<'a>>;
+
+impl<'a> Parser<'a, SliceTokens<'a>> {
     pub fn new(tokens: &'a [LexerToken]) -> Self {
-        Parser {
+        Parser::from_tokens(tokens.iter().map(Token::borrowed as fn(&'a LexerToken) -> Token<'a>))
+    }
+}
+
+impl<'src, I: Iterator<Item = Token<'src>>> Parser<'src, I> {
+    /// Crea un parser que pide los tokens bajo demanda, p. ej. a un `LexicalAnalyzer`.
+    pub fn from_tokens(tokens: I) -> Self {
+        let mut parser = Parser {
             tokens,
-            current: 0,
+            current: None,
+            current_doc: None,
+            lookahead: None,
+            previous: None,
+            last_error_at: None,
+            struct_literals_allowed: true,
+            trailing_value_allowed: false,
             errors: Vec::new(),
-        }
+        };
+        (parser.current, parser.current_doc) = parser.pull();
+        parser
     }
 
     // --- Métodos de Ayuda ---
 
-    fn peek(&self) -> Option<&LexerToken> {
-        self.tokens.get(self.current)
+    fn pull(&mut self) -> (Option<Token<'src>>, Option<String>) {
+        match self.lookahead.take() {
+            Some((token, doc)) => (Some(token), doc),
+            None => self.next_significant(),
+        }
+    }
+
+    /// Extrae el siguiente token significativo junto con los comentarios de
+    /// documentación que lo preceden, unidos por saltos de línea.
+    fn next_significant(&mut self) -> (Option<Token<'src>>, Option<String>) {
+        let mut doc: Option<String> = None;
+        for token in self.tokens.by_ref() {
+            if token.token_type == TokenType::DocComment {
+                let text = doc_text(&token.lexeme);
+                match doc.as_mut() {
+                    Some(doc) => { doc.push('\n'); doc.push_str(&text); }
+                    None => doc = Some(text),
+                }
+            } else if !is_trivia(token.token_type) {
+                return (Some(token), doc);
+            }
+        }
+        (None, doc)
+    }
+
+    /// Toma la documentación que precede al token actual, si la hay.
+    fn take_doc(&mut self) -> Option<String> {
+        self.current_doc.take()
+    }
+
+    fn peek(&self) -> Option<&Token<'src>> {
+        self.current.as_ref()
+    }
+
+    fn peek_next(&mut self) -> Option<&Token<'src>> {
+        if self.lookahead.is_none() {
+            let (token, doc) = self.next_significant();
+            self.lookahead = token.map(|token| (token, doc));
+        }
+        self.lookahead.as_ref().map(|(token, _)| token)
+    }
+
+    fn previous(&self) -> Option<&Token<'src>> {
+        self.previous.as_ref()
     }
 
-    fn previous(&self) -> Option<&LexerToken> {
-        self.current.checked_sub(1).map(|i| &self.tokens[i])
+    /// Inicio del token actual; al final de los tokens, el final del último consumido.
+    fn current_start(&self) -> Position {
+        match self.peek() {
+            Some(token) => token.start_position(),
+            None => self.previous().map_or(Position::START, Token::end_position),
+        }
+    }
+
+    /// Inicio del último token consumido (p. ej. la palabra clave que abre una sentencia).
+    fn previous_start(&self) -> Position {
+        self.previous().map_or(Position::START, Token::start_position)
+    }
+
+    /// Tramo desde `start` hasta el final del último token consumido.
+    fn span_from(&self, start: Position) -> Span {
+        Span::new(start, self.previous().map_or(start, Token::end_position))
     }
 
-    fn advance(&mut self) -> Option<&LexerToken> {
+    fn advance(&mut self) -> Option<&Token<'src>> {
         if !self.is_at_end() {
-            self.current += 1;
+            let (next, doc) = self.pull();
+            self.previous = std::mem::replace(&mut self.current, next);
+            self.current_doc = doc;
         }
         self.previous()
     }
@@ -51,87 +174,147 @@ impl<'a> Parser<'a> {
         }
     }
     
-    fn consume(&mut self, token_type: TokenType, error_msg: &str) -> Result<&LexerToken, SyntaxError> {
+    /// Consume un token del tipo indicado; si no está, reporta qué se esperaba
+    /// (`expected`, p. ej. "';' después de la expresión") y qué se encontró.
+    fn consume(&mut self, token_type: TokenType, expected: &'static str) -> Result<&Token<'src>, SyntaxError> {
         if self.check(token_type) {
             Ok(self.advance().unwrap()) // Es seguro hacer unwrap aquí
-        } else if let Some(token) = self.peek() {
-            let err = SyntaxError::UnexpectedToken(
-                format!("{}, se encontró '{}'", error_msg, token.lexeme),
-                token.line,
-                token.column,
-            );
-            self.errors.push(err.clone());
-            Err(err)
         } else {
-            let err = SyntaxError::UnexpectedEndOfFile;
+            let err = self.unexpected(expected);
+            Err(self.report(err))
+        }
+    }
+
+    /// Como `consume`, pero si el token falta solo reporta el error y sigue,
+    /// como si estuviera: para delimitadores cuya ausencia no impide entender el resto.
+    fn expect(&mut self, token_type: TokenType, expected: &'static str) {
+        let _ = self.consume(token_type, expected);
+    }
+
+    /// Registra un error de sintaxis. Si ya se reportó otro en el token actual,
+    /// se omite: una sola falta no debe producir una cascada de mensajes.
+    fn report(&mut self, err: SyntaxError) -> SyntaxError {
+        let at = self.current_start().offset;
+        if self.last_error_at != Some(at) {
             self.errors.push(err.clone());
-            Err(err)
+            self.last_error_at = Some(at);
+        }
+        err
+    }
+
+    /// Error de token inesperado: se esperaba `expected` y se encontró el token actual.
+    fn unexpected(&self, expected: &'static str) -> SyntaxError {
+        let at = self.current_start();
+        SyntaxError::UnexpectedToken {
+            expected,
+            found: describe(self.peek()),
+            span: self.peek().map_or(Span::new(at, at), token_span),
         }
     }
 
+    /// Descarta tokens hasta el final de la sentencia (un ';') o hasta el
+    /// inicio de la siguiente. Los bloques `{ ... }` se saltan enteros, y nunca
+    /// consume la '}' que cierra el bloque en el que está.
     fn synchronize(&mut self) {
-        self.advance();
+        let mut depth = 0usize;
         while !self.is_at_end() {
-            if let Some(prev) = self.previous() {
-                if prev.token_type == TokenType::Semicolon {
-                    return;
+            if depth == 0 {
+                if let Some(prev) = self.previous() {
+                    if prev.token_type == TokenType::Semicolon {
+                        return;
+                    }
                 }
-            }
 
-            if let Some(next) = self.peek() {
-                match next.lexeme.as_str() {
-                    "fn" | "let" | "const" | "return" | "if" | "while" | "for" | "struct" | "do" | "until" => return,
-                    _ => {}
+                if matches!(self.peek(), Some(next) if starts_statement(next.token_type) || next.token_type == TokenType::RightBrace) {
+                    return;
                 }
             }
 
-            self.advance();
+            match self.advance().map(|t| t.token_type) {
+                Some(TokenType::LeftBrace) => depth += 1,
+                Some(TokenType::RightBrace) => depth -= 1,
+                _ => {}
+            }
         }
     }
     
     // --- Lógica Principal del Parser ---
 
     pub fn parse(&mut self) -> Program {
-        let mut declarations = Vec::new();
+        let mut declarations = self.declarations();
 
-        while !self.is_at_end() {
-            match self.declaration() {
-                Ok(decl) => declarations.push(decl),
-                Err(_) => {
-                    self.synchronize();
-                }
-            }
+        // Una '}' sin bloque que cerrar se reporta y se descarta.
+        while self.check(TokenType::RightBrace) {
+            let start = self.current_start();
+            let err = self.unexpected("una declaración");
+            self.report(err);
+            self.advance();
+            declarations.push(Declaration::Statement(Statement::Error(self.span_from(start))));
+            declarations.append(&mut self.declarations());
         }
 
-        Program { declarations }
+        let end = self.peek().or(self.previous()).map_or(Position::START, Token::end_position);
+        Program { declarations, span: Span::new(Position::START, end) }
     }
 
-    fn declaration(&mut self) -> Result<Declaration, SyntaxError> {
-        if let Some(token) = self.peek() {
-            if token.token_type == TokenType::Keyword {
-                 match token.lexeme.as_str() {
-                    "fn" => { self.advance(); return self.function_declaration().map(Declaration::Function); },
-                    "let" => { self.advance(); return self.variable_declaration().map(Declaration::Variable); },
-                    "const" => { self.advance(); return self.constant_declaration().map(Declaration::Constant); },
-                    "struct" => { self.advance(); return self.struct_declaration().map(Declaration::Struct); },
-                     _ => {} 
-                }
+    /// Analiza declaraciones hasta la '}' que cierra el bloque o el final del
+    /// archivo. Una declaración que falla deja en su lugar un `Statement::Error`
+    /// con los tokens descartados.
+    fn declarations(&mut self) -> Vec<Declaration> {
+        let mut declarations = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let start = self.current_start();
+            let result = self.declaration();
+            if result.is_err() {
+                self.synchronize();
             }
+            // Si nadie consumió el token actual, se descarta para garantizar el avance.
+            let stuck = self.current_start() == start;
+            if stuck {
+                self.advance();
+            }
+            declarations.push(match result {
+                Ok(declaration) if !stuck => declaration,
+                _ => Declaration::Statement(Statement::Error(self.span_from(start))),
+            });
+        }
+        declarations
+    }
+
+    fn declaration(&mut self) -> Result<Declaration, SyntaxError> {
+        let doc = self.take_doc();
+        if self.match_token(TokenType::Fn) {
+            return self.function_declaration(doc).map(Declaration::Function);
+        }
+        if self.match_token(TokenType::Let) {
+            return self.variable_declaration(doc).map(Declaration::Variable);
+        }
+        if self.match_token(TokenType::Const) {
+            return self.constant_declaration(doc).map(Declaration::Constant);
+        }
+        if self.match_token(TokenType::Struct) {
+            return self.struct_declaration(doc).map(Declaration::Struct);
+        }
+        if self.match_token(TokenType::Enum) {
+            return self.enum_declaration(doc).map(Declaration::Enum);
+        }
+        if self.match_token(TokenType::Import) {
+            return self.import_declaration().map(Declaration::Import);
         }
         self.statement().map(Declaration::Statement)
     }
     
     // --- Declaraciones ---
 
-    fn function_declaration(&mut self) -> Result<Function, SyntaxError> {
-        let name_token = self.consume(TokenType::Identifier, "Se esperaba un nombre de función.")?.clone();
-        let name = Identifier { name: name_token.lexeme, line: name_token.line, column: name_token.column };
+    fn function_declaration(&mut self, doc: Option<String>) -> Result<Function, SyntaxError> {
+        let start = self.previous_start();
+        let name = identifier(self.consume(TokenType::Identifier, "un nombre de función")?);
 
-        self.consume(TokenType::LeftParen, "Se esperaba '(' después del nombre de función.")?;
+        self.consume(TokenType::LeftParen, "'(' después del nombre de función")?;
         let parameters = self.parameters()?;
-        self.consume(TokenType::RightParen, "Se esperaba ')' después de los parámetros.")?;
+        self.consume(TokenType::RightParen, "')' después de los parámetros")?;
 
-        self.consume(TokenType::ArrowRight, "Se esperaba '->' para el tipo de retorno.")?;
+        self.consume(TokenType::ArrowRight, "'->' para el tipo de retorno")?;
         let return_type = self.type_annotation()?;
 
         let body = self.block_statement()?;
@@ -141,6 +324,8 @@ impl<'a> Parser<'a> {
             parameters,
             return_type,
             body,
+            doc,
+            span: self.span_from(start),
         })
     }
     
@@ -148,11 +333,11 @@ impl<'a> Parser<'a> {
         let mut params = Vec::new();
         if !self.check(TokenType::RightParen) {
             loop {
-                let name_token = self.consume(TokenType::Identifier, "Se esperaba nombre de parámetro.")?.clone();
-                let name = Identifier { name: name_token.lexeme, line: name_token.line, column: name_token.column };
-                self.consume(TokenType::Colon, "Se esperaba ':' después del nombre del parámetro.")?;
+                let name = identifier(self.consume(TokenType::Identifier, "un nombre de parámetro")?);
+                self.consume(TokenType::Colon, "':' después del nombre del parámetro")?;
                 let param_type = self.type_annotation()?;
-                params.push(Parameter { name, param_type });
+                let span = self.span_from(name.span.start);
+                params.push(Parameter { name, param_type, span });
 
                 if !self.match_token(TokenType::Comma) {
                     break;
@@ -163,183 +348,415 @@ impl<'a> Parser<'a> {
     }
 
     fn type_annotation(&mut self) -> Result<Type, SyntaxError> {
-        let type_token = self.consume(TokenType::Identifier, "Se esperaba un nombre de tipo.")?;
-        let type_str = type_token.lexeme.to_lowercase();
-        match type_str.as_str() {
-            "int" => Ok(Type::Int),
-            "float" => Ok(Type::Float),
-            "string" => Ok(Type::String),
-            "bool" => Ok(Type::Bool),
-            "void" => Ok(Type::Void),
-            _ => Err(SyntaxError::UnexpectedToken(
-                format!("Tipo desconocido '{}'", type_token.lexeme),
-                type_token.line,
-                type_token.column,
-            )),
-        }
-    }
-
-    fn constant_declaration(&mut self) -> Result<ConstantDeclaration, SyntaxError> {
-        let name_token = self.consume(TokenType::Identifier, "Se esperaba un nombre para la constante.")?;
-        let identifier = Identifier { name: name_token.lexeme.clone(), line: name_token.line, column: name_token.column };
+        let mut type_ = if self.match_token(TokenType::LeftBracket) {
+            let element = Box::new(self.type_annotation()?);
+            let type_ = if self.match_token(TokenType::Semicolon) {
+                Type::FixedArray(element, self.array_length()?)
+            } else {
+                Type::Array(element)
+            };
+            self.consume(TokenType::RightBracket, "']' al final del tipo de arreglo")?;
+            type_
+        } else if self.match_token(TokenType::Fn) {
+            self.consume(TokenType::LeftParen, "'(' después de 'fn'")?;
+            let mut parameters = Vec::new();
+            if !self.check(TokenType::RightParen) {
+                loop {
+                    parameters.push(self.type_annotation()?);
+                    if !self.match_token(TokenType::Comma) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightParen, "')' después de los tipos de los parámetros")?;
+            self.consume(TokenType::ArrowRight, "'->' para el tipo de retorno")?;
+            Type::Function(parameters, Box::new(self.type_annotation()?))
+        } else if self.match_token(TokenType::LeftParen) {
+            // Los paréntesis permiten escribir `(fn() -> Int)?`.
+            let inner = self.type_annotation()?;
+            self.consume(TokenType::RightParen, "')' después del tipo")?;
+            inner
+        } else {
+            let type_token = self.consume(TokenType::Identifier, "un nombre de tipo")?;
+            match type_token.lexeme.to_lowercase().as_str() {
+                "int" => Type::Int,
+                "float" => Type::Float,
+                "string" => Type::String,
+                "bool" => Type::Bool,
+                "char" => Type::Char,
+                "void" => Type::Void,
+                // Si el nombre es de un struct o de un enum lo resuelve el análisis semántico.
+                _ => Type::Struct(type_token.lexeme.to_string()),
+            }
+        };
+        while self.match_token(TokenType::Question) {
+            type_ = Type::Optional(Box::new(type_));
+        }
+        Ok(type_)
+    }
+
+    /// Longitud de un tipo `[T; N]`, que debe ser un entero literal.
+    fn array_length(&mut self) -> Result<usize, SyntaxError> {
+        if !self.check(TokenType::Integer) {
+            // Se reporta, pero la declaración se conserva: se salta lo que ocupa el lugar de la longitud.
+            let err = self.unexpected("la longitud del arreglo");
+            self.report(err);
+            if !self.check(TokenType::RightBracket) {
+                self.advance();
+            }
+            return Ok(0);
+        }
+        let token = self.advance().unwrap().clone();
+        match parse_int_literal(&token.lexeme).and_then(|length| usize::try_from(length).ok()) {
+            Some(length) => Ok(length),
+            None => {
+                self.report(SyntaxError::NumberOutOfRange { found: describe(Some(&token)), span: token_span(&token) });
+                Ok(0)
+            }
+        }
+    }
+
+    fn constant_declaration(&mut self, doc: Option<String>) -> Result<ConstantDeclaration, SyntaxError> {
+        let start = self.previous_start();
+        let identifier = identifier(self.consume(TokenType::Identifier, "un nombre para la constante")?);
         let const_type = if self.match_token(TokenType::Colon) { Some(self.type_annotation()?) } else { None };
-        self.consume(TokenType::Equal, "Se esperaba '=' después del nombre de la constante.")?;
+        self.consume(TokenType::Equal, "'=' después del nombre de la constante")?;
         let value = self.expression()?;
-        self.consume(TokenType::Semicolon, "Se esperaba ';' después de la declaración de la constante.")?;
-        Ok(ConstantDeclaration { identifier, const_type, value })
+        self.expect(TokenType::Semicolon, "';' después de la declaración de la constante");
+        Ok(ConstantDeclaration { identifier, const_type, value, doc, span: self.span_from(start) })
+    }
+
+    fn import_declaration(&mut self) -> Result<ImportDeclaration, SyntaxError> {
+        let start = self.previous_start();
+        let path = if self.match_token(TokenType::String) {
+            self.previous().unwrap().lexeme.to_string()
+        } else {
+            let mut segments = vec![self.consume(TokenType::Identifier, "la ruta del módulo a importar")?.lexeme.to_string()];
+            while self.match_token(TokenType::Dot) {
+                segments.push(self.consume(TokenType::Identifier, "un nombre de módulo después de '.'")?.lexeme.to_string());
+            }
+            format!("{}.dream", segments.join("/"))
+        };
+        self.expect(TokenType::Semicolon, "';' después del import");
+        Ok(ImportDeclaration { path, span: self.span_from(start) })
     }
 
-    fn variable_declaration(&mut self) -> Result<VariableDeclaration, SyntaxError> {
-        let name_token = self.consume(TokenType::Identifier, "Se esperaba un nombre para la variable.")?;
-        let identifier = Identifier { name: name_token.lexeme.clone(), line: name_token.line, column: name_token.column };
+    fn variable_declaration(&mut self, doc: Option<String>) -> Result<VariableDeclaration, SyntaxError> {
+        let start = self.previous_start();
+        let identifier = identifier(self.consume(TokenType::Identifier, "un nombre para la variable")?);
         let var_type = if self.match_token(TokenType::Colon) { Some(self.type_annotation()?) } else { None };
-        self.consume(TokenType::Equal, "Se esperaba '=' en la declaración de la variable.")?;
+        self.consume(TokenType::Equal, "'=' en la declaración de la variable")?;
         let value = self.expression()?;
-        self.consume(TokenType::Semicolon, "Se esperaba ';' después de la declaración de la variable.")?;
-        Ok(VariableDeclaration { identifier, var_type, value })
+        self.expect(TokenType::Semicolon, "';' después de la declaración de la variable");
+        Ok(VariableDeclaration { identifier, var_type, value, doc, span: self.span_from(start) })
     }
     
-    fn struct_declaration(&mut self) -> Result<StructDeclaration, SyntaxError> {
-        let name = self.consume(TokenType::Identifier, "Se esperaba un nombre para el struct.")?.clone();
-        let name_id = Identifier { name: name.lexeme, line: name.line, column: name.column };
-        self.consume(TokenType::LeftBrace, "Se esperaba '{' después del nombre del struct.")?;
+    fn struct_declaration(&mut self, doc: Option<String>) -> Result<StructDeclaration, SyntaxError> {
+        let start = self.previous_start();
+        let name = identifier(self.consume(TokenType::Identifier, "un nombre para el struct")?);
+        self.consume(TokenType::LeftBrace, "'{' después del nombre del struct")?;
         let mut fields = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            let field_name_token = self.consume(TokenType::Identifier, "Se esperaba un nombre de campo.")?.clone();
-            let field_name = Identifier { name: field_name_token.lexeme, line: field_name_token.line, column: field_name_token.column };
-            self.consume(TokenType::Colon, "Se esperaba ':' después del nombre de campo.")?;
+            let field_doc = self.take_doc();
+            let field_name = identifier(self.consume(TokenType::Identifier, "un nombre de campo")?);
+            self.consume(TokenType::Colon, "':' después del nombre de campo")?;
             let field_type = self.type_annotation()?;
-            fields.push(FieldDeclaration { name: field_name, field_type });
-            if !self.check(TokenType::RightBrace) {
-                if !self.match_token(TokenType::Comma) {
-                     let err = self.peek().unwrap();
-                     return Err(SyntaxError::UnexpectedToken(format!("Se esperaba ',' o '}}' después del campo de struct, se encontró '{}'", err.lexeme), err.line, err.column));
+            let span = self.span_from(field_name.span.start);
+            fields.push(FieldDeclaration { name: field_name, field_type, doc: field_doc, span });
+            if !self.check(TokenType::RightBrace) && !self.match_token(TokenType::Comma) {
+                let err = self.unexpected("',' o '}' después del campo de struct");
+                let err = self.report(err);
+                // Si empieza otro campo solo falta la coma: se reporta y se sigue leyendo el struct.
+                if !self.check(TokenType::Identifier) {
+                    return Err(err);
                 }
             }
         }
-        self.consume(TokenType::RightBrace, "Se esperaba '}' al final del struct.")?;
-        Ok(StructDeclaration { name: name_id, fields })
+        self.consume(TokenType::RightBrace, "'}' al final del struct")?;
+        Ok(StructDeclaration { name, fields, doc, span: self.span_from(start) })
+    }
+
+    fn enum_declaration(&mut self, doc: Option<String>) -> Result<EnumDeclaration, SyntaxError> {
+        let start = self.previous_start();
+        let name = identifier(self.consume(TokenType::Identifier, "un nombre para el enum")?);
+        self.consume(TokenType::LeftBrace, "'{' después del nombre del enum")?;
+        let mut variants = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let variant_doc = self.take_doc();
+            let variant_name = identifier(self.consume(TokenType::Identifier, "un nombre de variante")?);
+            let mut payload = Vec::new();
+            if self.match_token(TokenType::LeftParen) {
+                loop {
+                    payload.push(self.type_annotation()?);
+                    if !self.match_token(TokenType::Comma) { break; }
+                }
+                self.consume(TokenType::RightParen, "')' después de los tipos de la variante")?;
+            }
+            let span = self.span_from(variant_name.span.start);
+            variants.push(VariantDeclaration { name: variant_name, payload, doc: variant_doc, span });
+            if !self.check(TokenType::RightBrace) && !self.match_token(TokenType::Comma) {
+                let err = self.unexpected("',' o '}' después de la variante");
+                let err = self.report(err);
+                // Como en los structs, si empieza otra variante solo falta la coma.
+                if !self.check(TokenType::Identifier) {
+                    return Err(err);
+                }
+            }
+        }
+        self.consume(TokenType::RightBrace, "'}' al final del enum")?;
+        Ok(EnumDeclaration { name, variants, doc, span: self.span_from(start) })
     }
     
     // --- Sentencias ---
     
     fn statement(&mut self) -> Result<Statement, SyntaxError> {
-        if self.peek().map_or(false, |t| t.lexeme == "do") {
-            self.advance();
-            return self.do_until_statement().map(Statement::DoUntil);
+        if self.match_token(TokenType::Label) {
+            let label = identifier(self.previous().unwrap());
+            self.consume(TokenType::Colon, "':' después de la etiqueta")?;
+            return self.loop_statement(Some(label));
+        }
+        if self.check(TokenType::Do) || self.check(TokenType::While) || self.check(TokenType::For) {
+            return self.loop_statement(None);
         }
-        if self.peek().map_or(false, |t| t.lexeme == "if") {
+        // En el bloque de una expresión `if`, un `if` sin paréntesis es otra expresión (su valor).
+        if self.check(TokenType::If)
+            && !(self.trailing_value_allowed && self.peek_next().is_some_and(|t| t.token_type != TokenType::LeftParen))
+        {
             self.advance();
             return self.if_statement().map(Statement::If);
         }
-        if self.peek().map_or(false, |t| t.lexeme == "while") {
-            self.advance();
-            return self.while_statement().map(Statement::While);
+        if self.match_token(TokenType::Match) {
+            return self.match_statement().map(Statement::Match);
         }
-        if self.peek().map_or(false, |t| t.lexeme == "return") {
-            self.advance();
+        if self.match_token(TokenType::Return) {
             return self.return_statement().map(Statement::Return);
         }
-        if self.peek().map_or(false, |t| t.lexeme == "for") {
-            self.advance();
-            return self.for_statement().map(Statement::For);
+        if self.match_token(TokenType::Break) {
+            return Ok(Statement::Break(self.jump_statement("';' después de 'break'")));
+        }
+        if self.match_token(TokenType::Continue) {
+            return Ok(Statement::Continue(self.jump_statement("';' después de 'continue'")));
         }
         if self.check(TokenType::LeftBrace) {
             return self.block_statement().map(Statement::Block);
         }
 
         let expr = self.expression()?;
-        self.consume(TokenType::Semicolon, "Se esperaba ';' después de la expresión.")?;
+        if !(self.trailing_value_allowed && self.check(TokenType::RightBrace)) {
+            self.expect(TokenType::Semicolon, "';' después de la expresión");
+        }
         Ok(Statement::Expression(expr))
     }
     
     fn block_statement(&mut self) -> Result<Block, SyntaxError> {
-        self.consume(TokenType::LeftBrace, "Se esperaba '{' para iniciar un bloque.")?;
-        let mut statements = Vec::new();
-        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            match self.declaration() {
-                Ok(decl) => statements.push(decl),
-                Err(_) => self.synchronize(),
+        let start = self.consume(TokenType::LeftBrace, "'{' para iniciar un bloque")?.start_position();
+        let statements = self.with_trailing_value(false, Self::declarations);
+        self.expect(TokenType::RightBrace, "'}' para cerrar un bloque");
+        Ok(Block { statements, span: self.span_from(start) })
+    }
+
+    /// Bloque de una expresión `if`: sus sentencias y, al final, la expresión sin ';' que le da valor.
+    fn value_block(&mut self) -> Result<Expression, SyntaxError> {
+        let start = self.consume(TokenType::LeftBrace, "'{' para iniciar un bloque")?.start_position();
+        let mut statements = self.with_trailing_value(true, Self::declarations);
+        let ends_with_value = self.previous().is_some_and(|t| t.token_type != TokenType::Semicolon);
+        let value = match statements.pop() {
+            Some(Declaration::Statement(Statement::Expression(value))) if ends_with_value => value,
+            last => {
+                statements.extend(last);
+                let at = self.current_start();
+                let err = self.unexpected("la expresión con el valor del bloque (sin ';')");
+                self.report(err);
+                Expression::Error(Span::new(at, at))
             }
+        };
+        self.expect(TokenType::RightBrace, "'}' para cerrar un bloque");
+        let block = Block { statements, span: self.span_from(start) };
+        Ok(Expression::Block { block, value: Box::new(value) })
+    }
+
+    /// Bucle `while`, `do ... until` o `for`, con la etiqueta que lo precede si la hay.
+    fn loop_statement(&mut self, label: Option<Identifier>) -> Result<Statement, SyntaxError> {
+        if self.match_token(TokenType::Do) {
+            self.do_until_statement(label).map(Statement::DoUntil)
+        } else if self.match_token(TokenType::While) {
+            self.while_statement(label).map(Statement::While)
+        } else if self.match_token(TokenType::For) {
+            self.for_statement(label).map(Statement::For)
+        } else {
+            let err = self.unexpected("un bucle después de la etiqueta");
+            Err(self.report(err))
         }
-        self.consume(TokenType::RightBrace, "Se esperaba '}' para cerrar un bloque.")?;
-        Ok(Block { statements })
+    }
+
+    /// Inicio de un bucle: su etiqueta o, si no tiene, la palabra clave que lo abre.
+    fn loop_start(&self, label: &Option<Identifier>) -> Position {
+        label.as_ref().map_or_else(|| self.previous_start(), |label| label.span.start)
+    }
+
+    fn jump_statement(&mut self, expected_semicolon: &'static str) -> JumpStatement {
+        let start = self.previous_start();
+        let label = if self.match_token(TokenType::Label) { Some(identifier(self.previous().unwrap())) } else { None };
+        self.expect(TokenType::Semicolon, expected_semicolon);
+        JumpStatement { label, span: self.span_from(start) }
     }
 
     fn return_statement(&mut self) -> Result<ReturnStatement, SyntaxError> {
+        let start = self.previous_start();
         let value = self.expression()?;
-        self.consume(TokenType::Semicolon, "Se esperaba ';' después del valor de retorno.")?;
-        Ok(ReturnStatement { value })
+        self.expect(TokenType::Semicolon, "';' después del valor de retorno");
+        Ok(ReturnStatement { value, span: self.span_from(start) })
     }
 
     fn if_statement(&mut self) -> Result<IfStatement, SyntaxError> {
-        self.consume(TokenType::LeftParen, "Se esperaba '(' después de 'if'.")?;
-        let condition = self.logical_or()?;
-        self.consume(TokenType::RightParen, "Se esperaba ')' después de la condición.")?;
+        let start = self.previous_start();
+        self.consume(TokenType::LeftParen, "'(' después de 'if'")?;
+        let condition = self.expression()?;
+        self.expect(TokenType::RightParen, "')' después de la condición");
         let then_block = self.block_statement()?;
         let mut else_block = None;
 
-        if self.peek().map_or(false, |t| t.lexeme == "else") {
-            self.advance();
-            if self.peek().map_or(false, |t| t.lexeme == "if") {
-                self.advance();
+        if self.match_token(TokenType::Else) {
+            if self.match_token(TokenType::If) {
                 else_block = Some(ElseBranch::If(Box::new(self.if_statement()?)));
             } else {
                 else_block = Some(ElseBranch::Block(Box::new(Statement::Block(self.block_statement()?))));
             }
         }
-        Ok(IfStatement { condition, then_block, else_block })
+        Ok(IfStatement { condition, then_block, else_block, span: self.span_from(start) })
     }
 
-    fn while_statement(&mut self) -> Result<WhileStatement, SyntaxError> {
-        self.consume(TokenType::LeftParen, "Se esperaba '(' después de 'while'.")?;
-        let condition = self.logical_or()?;
-        self.consume(TokenType::RightParen, "Se esperaba ')' después de la condición.")?;
+    fn while_statement(&mut self, label: Option<Identifier>) -> Result<WhileStatement, SyntaxError> {
+        let start = self.loop_start(&label);
+        self.consume(TokenType::LeftParen, "'(' después de 'while'")?;
+        let condition = self.expression()?;
+        self.expect(TokenType::RightParen, "')' después de la condición");
         let body = self.block_statement()?;
-        Ok(WhileStatement { condition, body })
+        Ok(WhileStatement { label, condition, body, span: self.span_from(start) })
     }
     
-    fn do_until_statement(&mut self) -> Result<DoUntilStatement, SyntaxError> {
+    fn do_until_statement(&mut self, label: Option<Identifier>) -> Result<DoUntilStatement, SyntaxError> {
+        let start = self.loop_start(&label);
         let body = self.block_statement()?;
 
-        // Consume 'until' keyword
-        if let Some(token) = self.peek() {
-            if token.token_type == TokenType::Keyword && token.lexeme == "until" {
-                self.advance(); // Consume 'until'
-            } else {
-                let err = SyntaxError::UnexpectedToken(
-                    format!("Se esperaba la palabra clave 'until' después del bloque 'do', pero se encontró '{}'", token.lexeme),
-                    token.line,
-                    token.column,
-                );
-                self.errors.push(err.clone());
-                return Err(err);
-            }
-        } else {
-            let err = SyntaxError::UnexpectedEndOfFile;
-            self.errors.push(err.clone());
-            return Err(err);
-        }
-        
+        self.consume(TokenType::Until, "la palabra clave 'until' después del bloque 'do'")?;
+
         // Parse condition directly without parentheses
-        let condition = self.logical_or()?;
-        self.consume(TokenType::Semicolon, "Se esperaba ';' después de la sentencia do-until.")?;
+        let condition = self.expression()?;
+        self.expect(TokenType::Semicolon, "';' después de la sentencia do-until");
 
-        Ok(DoUntilStatement { body, condition })
+        Ok(DoUntilStatement { label, body, condition, span: self.span_from(start) })
     }
 
-    fn for_statement(&mut self) -> Result<ForStatement, SyntaxError> {
-        let variable_token = self.consume(TokenType::Identifier, "Se esperaba una variable de bucle.")?.clone();
-        let variable = Identifier { name: variable_token.lexeme, line: variable_token.line, column: variable_token.column };
+    fn for_statement(&mut self, label: Option<Identifier>) -> Result<ForStatement, SyntaxError> {
+        let start = self.loop_start(&label);
+        let variable = identifier(self.consume(TokenType::Identifier, "una variable de bucle")?);
         
-        let in_keyword = self.advance().ok_or(SyntaxError::UnexpectedEndOfFile)?;
-        if in_keyword.token_type != TokenType::Keyword || in_keyword.lexeme != "in" {
-            return Err(SyntaxError::MissingInKeyword);
+        self.consume(TokenType::In, "la palabra clave 'in' en el bucle 'for'")?;
+
+        // En `for x in items { ... }` la llave abre el cuerpo, no una instancia de `items`.
+        let iterable = self.with_struct_literals(false, Self::expression)?;
+        let body = self.block_statement()?;
+        Ok(ForStatement { label, variable, iterable, body, span: self.span_from(start) })
+    }
+
+    fn match_statement(&mut self) -> Result<MatchStatement, SyntaxError> {
+        let start = self.previous_start();
+        // Como en `for`, la llave que sigue al valor abre los brazos.
+        let subject = self.with_struct_literals(false, Self::expression)?;
+        self.consume(TokenType::LeftBrace, "'{' después del valor de 'match'")?;
+        let mut arms = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            arms.push(self.match_arm()?);
+            self.match_token(TokenType::Comma);
         }
+        self.expect(TokenType::RightBrace, "'}' al final del 'match'");
+        Ok(MatchStatement { subject, arms, span: self.span_from(start) })
+    }
 
-        let iterable = self.expression()?;
+    fn match_arm(&mut self) -> Result<MatchArm, SyntaxError> {
+        let pattern = self.pattern()?;
+        let guard = if self.match_token(TokenType::If) { Some(self.expression()?) } else { None };
+        self.consume(TokenType::FatArrow, "'=>' después del patrón")?;
         let body = self.block_statement()?;
-        Ok(ForStatement { variable, iterable, body })
+        let span = pattern.span().to(body.span);
+        Ok(MatchArm { pattern, guard, body, span })
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, SyntaxError> {
+        let start = self.current_start();
+        if self.check(TokenType::Identifier) {
+            let name = identifier(self.advance().unwrap());
+            if name.name == "_" {
+                return Ok(Pattern::Wildcard(name.span));
+            }
+            if self.match_token(TokenType::DoubleColon) {
+                let variant = identifier(self.consume(TokenType::Identifier, "el nombre de la variante")?);
+                let mut payload = Vec::new();
+                if self.match_token(TokenType::LeftParen) {
+                    while !self.check(TokenType::RightParen) {
+                        payload.push(self.pattern()?);
+                        if !self.check(TokenType::RightParen) {
+                            self.consume(TokenType::Comma, "',' después del patrón")?;
+                        }
+                    }
+                    self.consume(TokenType::RightParen, "')' al final del patrón")?;
+                }
+                return Ok(Pattern::Variant { enum_name: name, variant, payload, span: self.span_from(start) });
+            }
+            if !self.match_token(TokenType::LeftBrace) {
+                return Ok(Pattern::Binding(name));
+            }
+            let mut fields = Vec::new();
+            while !self.check(TokenType::RightBrace) {
+                let field = identifier(self.consume(TokenType::Identifier, "un nombre de campo en el patrón")?);
+                let pattern = if self.match_token(TokenType::Equal) { self.pattern()? } else { Pattern::Binding(field.clone()) };
+                let span = field.span.to(pattern.span());
+                fields.push(FieldPattern { name: field, pattern, span });
+                if !self.check(TokenType::RightBrace) {
+                    self.consume(TokenType::Comma, "',' después del campo del patrón")?;
+                }
+            }
+            self.consume(TokenType::RightBrace, "'}' al final del patrón")?;
+            return Ok(Pattern::Struct { name, fields, span: self.span_from(start) });
+        }
+
+        let first = self.pattern_literal()?;
+        if !self.match_token(TokenType::DotDot) && !self.match_token(TokenType::DotDotEqual) {
+            let span = self.span_from(start);
+            return Ok(first.map_or(Pattern::Error(span), |literal| Pattern::Literal(literal, span)));
+        }
+        let inclusive = self.previous().unwrap().token_type == TokenType::DotDotEqual;
+        let last = self.pattern_literal()?;
+        let span = self.span_from(start);
+        Ok(match (first, last) {
+            (Some(start), Some(end)) => Pattern::Range { start, end, inclusive, span },
+            _ => Pattern::Error(span),
+        })
+    }
+
+    /// Literal de un patrón; los números pueden llevar un '-' delante. Devuelve
+    /// `None` si el número no cabe en su tipo (el error ya se reportó).
+    fn pattern_literal(&mut self) -> Result<Option<Literal>, SyntaxError> {
+        let negative = self.match_token(TokenType::Minus);
+        if let Some(value) = negative.then(|| self.negated_int_literal()).flatten() {
+            return Ok(Some(Literal::Int(value)));
+        }
+        let is_literal = match self.peek().map(|t| t.token_type) {
+            Some(TokenType::Integer | TokenType::Float) => true,
+            Some(TokenType::String | TokenType::Char | TokenType::True | TokenType::False) => !negative,
+            _ => false,
+        };
+        if !is_literal {
+            let err = self.unexpected("un patrón");
+            return Err(self.report(err));
+        }
+        Ok(match self.primary()? {
+            Expression::Literal(Literal::Int(value), _) if negative => Some(Literal::Int(-value)),
+            Expression::Literal(Literal::Float(value), _) if negative => Some(Literal::Float(-value)),
+            Expression::Literal(literal, _) => Some(literal),
+            _ => None,
+        })
     }
 
     // --- Expresiones y Jerarquía de Precedencia ---
@@ -349,31 +766,122 @@ impl<'a> Parser<'a> {
     }
 
     fn assignment(&mut self) -> Result<Expression, SyntaxError> {
-        let left = self.pipe()?;
+        let left = self.conditional()?;
         if self.match_token(TokenType::Equal) {
-            if let Expression::Identifier(target) = left {
-                let value = self.assignment()?;
-                return Ok(Expression::Assignment { target, value: Box::new(value) });
+            let is_target = self.check_assignment_target(&left);
+            let value = self.assignment()?;
+            let span = left.span().to(value.span());
+            if !is_target {
+                return Ok(Expression::Error(span));
             }
-            return Err(SyntaxError::InvalidAssignmentTarget);
+            return Ok(Expression::Assignment { target: Box::new(left), value: Box::new(value), span });
+        } else if let Some(op) = self.match_compound_assignment() {
+            let is_target = self.check_assignment_target(&left);
+            let value = self.assignment()?;
+            let span = left.span().to(value.span());
+            if !is_target {
+                return Ok(Expression::Error(span));
+            }
+            return Ok(Expression::CompoundAssignment { target: Box::new(left), op, value: Box::new(value), span });
         } else if self.match_token(TokenType::Swap) {
-            if let Expression::Identifier(_) = &left {
-                let right = self.assignment()?;
-                if let Expression::Identifier(_) = &right {
-                     return Ok(Expression::Binary { left: Box::new(left), op: BinaryOp::Swap, right: Box::new(right) });
-                }
+            let right = self.assignment()?;
+            if let (Expression::Identifier(_), Expression::Identifier(_)) = (&left, &right) {
+                return Ok(binary(left, BinaryOp::Swap, right));
             }
-            return Err(SyntaxError::InvalidAssignmentTarget);
+            // Ambos lados de '<=>' deben ser variables; se reporta el primero que no lo es.
+            let target = if matches!(left, Expression::Identifier(_)) { &right } else { &left };
+            if !matches!(target, Expression::Error(_)) {
+                self.report(invalid_assignment_target(target));
+            }
+            return Ok(Expression::Error(left.span().to(right.span())));
         }
         Ok(left)
     }
 
+    /// `cond ? a : b`. Asocia por la derecha: `a ? b : c ? d : e` es `a ? b : (c ? d : e)`.
+    fn conditional(&mut self) -> Result<Expression, SyntaxError> {
+        let condition = self.range()?;
+        if !self.match_token(TokenType::Question) {
+            return Ok(condition);
+        }
+        let then_branch = self.conditional()?;
+        self.consume(TokenType::Colon, "':' entre las dos opciones de '?'")?;
+        let else_branch = self.conditional()?;
+        let span = condition.span().to(else_branch.span());
+        Ok(Expression::If {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+            span,
+        })
+    }
+
+    fn range(&mut self) -> Result<Expression, SyntaxError> {
+        let start = self.pipe()?;
+        if !self.match_token(TokenType::DotDot) && !self.match_token(TokenType::DotDotEqual) {
+            return Ok(start);
+        }
+        let inclusive = self.previous().unwrap().token_type == TokenType::DotDotEqual;
+        let end = self.pipe()?;
+        // `step` no es una palabra reservada: solo tiene este significado detrás de un rango.
+        let step = if self.peek().is_some_and(|t| t.token_type == TokenType::Identifier && t.lexeme == "step") {
+            self.advance();
+            Some(Box::new(self.pipe()?))
+        } else {
+            None
+        };
+        let span = self.span_from(start.span().start);
+        Ok(Expression::Range { start: Box::new(start), end: Box::new(end), inclusive, step, span })
+    }
+
+    /// Analiza con `parse` permitiendo o no instancias de struct (`Nombre { ... }`).
+    /// Dentro de paréntesis o corchetes vuelven a estar permitidas.
+    fn with_struct_literals<T>(&mut self, allowed: bool, parse: impl FnOnce(&mut Self) -> T) -> T {
+        let previous = std::mem::replace(&mut self.struct_literals_allowed, allowed);
+        let result = parse(self);
+        self.struct_literals_allowed = previous;
+        result
+    }
+
+    /// Analiza con `parse` permitiendo o no que la última expresión de un bloque
+    /// vaya sin ';'. Los bloques de un nivel más adentro vuelven a exigirlo.
+    fn with_trailing_value<T>(&mut self, allowed: bool, parse: impl FnOnce(&mut Self) -> T) -> T {
+        let previous = std::mem::replace(&mut self.trailing_value_allowed, allowed);
+        let result = parse(self);
+        self.trailing_value_allowed = previous;
+        result
+    }
+
+    /// Consume un operador de asignación compuesta (`+=`, `-=`, ...) y devuelve
+    /// la operación que aplica.
+    fn match_compound_assignment(&mut self) -> Option<BinaryOp> {
+        let op = match self.peek()?.token_type {
+            TokenType::PlusEqual => BinaryOp::Plus,
+            TokenType::MinusEqual => BinaryOp::Minus,
+            TokenType::AsteriskEqual => BinaryOp::Asterisk,
+            TokenType::SlashEqual => BinaryOp::Slash,
+            TokenType::PercentEqual => BinaryOp::Percent,
+            _ => return None,
+        };
+        self.advance();
+        Some(op)
+    }
+
+    /// Reporta el destino si no se le puede asignar (salvo que ya sea un error) y dice si se puede.
+    fn check_assignment_target(&mut self, target: &Expression) -> bool {
+        let is_target = is_assignment_target(target);
+        if !is_target && !matches!(target, Expression::Error(_)) {
+            self.report(invalid_assignment_target(target));
+        }
+        is_target
+    }
+
     fn pipe(&mut self) -> Result<Expression, SyntaxError> {
         let mut expr = self.spread()?;
         while self.match_token(TokenType::Pipe) {
             let op = BinaryOp::Pipe;
             let right = self.spread()?;
-            expr = Expression::Binary { left: Box::new(expr), op, right: Box::new(right) };
+            expr = binary(expr, op, right);
         }
         Ok(expr)
     }
@@ -383,7 +891,7 @@ impl<'a> Parser<'a> {
         while self.match_token(TokenType::Spread) {
             let op = BinaryOp::Spread;
             let right = self.logical_or()?;
-            expr = Expression::Binary { left: Box::new(expr), op, right: Box::new(right) };
+            expr = binary(expr, op, right);
         }
         Ok(expr)
     }
@@ -393,7 +901,7 @@ impl<'a> Parser<'a> {
         while self.match_token(TokenType::DoubleBar) {
             let op = BinaryOp::DoubleBar;
             let right = self.logical_and()?;
-            expr = Expression::Binary { left: Box::new(expr), op, right: Box::new(right) };
+            expr = binary(expr, op, right);
         }
         Ok(expr)
     }
@@ -403,23 +911,51 @@ impl<'a> Parser<'a> {
         while self.match_token(TokenType::DoubleAmpersand) {
             let op = BinaryOp::DoubleAmpersand;
             let right = self.equality()?;
-            expr = Expression::Binary { left: Box::new(expr), op, right: Box::new(right) };
+            expr = binary(expr, op, right);
         }
         Ok(expr)
     }
     
     fn equality(&mut self) -> Result<Expression, SyntaxError> {
-        let mut expr = self.comparison()?;
+        let mut expr = self.bitwise_or()?;
         while self.match_token(TokenType::DoubleEqual) || self.match_token(TokenType::NotEqual) {
             let op = if self.previous().unwrap().token_type == TokenType::DoubleEqual { BinaryOp::DoubleEqual } else { BinaryOp::NotEqual };
+            let right = self.bitwise_or()?;
+            expr = binary(expr, op, right);
+        }
+        Ok(expr)
+    }
+
+    // Los operadores de bits ligan más que `==`, así que `a & m == 0` es `(a & m) == 0`.
+    fn bitwise_or(&mut self) -> Result<Expression, SyntaxError> {
+        let mut expr = self.bitwise_xor()?;
+        while self.match_token(TokenType::Bar) {
+            let right = self.bitwise_xor()?;
+            expr = binary(expr, BinaryOp::Bar, right);
+        }
+        Ok(expr)
+    }
+
+    fn bitwise_xor(&mut self) -> Result<Expression, SyntaxError> {
+        let mut expr = self.bitwise_and()?;
+        while self.match_token(TokenType::Caret) {
+            let right = self.bitwise_and()?;
+            expr = binary(expr, BinaryOp::Caret, right);
+        }
+        Ok(expr)
+    }
+
+    fn bitwise_and(&mut self) -> Result<Expression, SyntaxError> {
+        let mut expr = self.comparison()?;
+        while self.match_token(TokenType::Ampersand) {
             let right = self.comparison()?;
-            expr = Expression::Binary { left: Box::new(expr), op, right: Box::new(right) };
+            expr = binary(expr, BinaryOp::Ampersand, right);
         }
         Ok(expr)
     }
 
     fn comparison(&mut self) -> Result<Expression, SyntaxError> {
-        let mut expr = self.term()?;
+        let mut expr = self.shift()?;
         while self.match_token(TokenType::Greater) || self.match_token(TokenType::GreaterEqual) || self.match_token(TokenType::Less) || self.match_token(TokenType::LessEqual) {
             let op = match self.previous().unwrap().token_type {
                 TokenType::Greater => BinaryOp::Greater,
@@ -428,8 +964,24 @@ impl<'a> Parser<'a> {
                 TokenType::LessEqual => BinaryOp::LessEqual,
                 _ => unreachable!(),
             };
+            let right = self.shift()?;
+            expr = binary(expr, op, right);
+        }
+        // `is` compara con una variante y no con otra expresión, pero liga como las comparaciones.
+        if self.match_token(TokenType::Is) {
+            let (enum_name, variant) = self.variant_path()?;
+            let span = self.span_from(expr.span().start);
+            expr = Expression::Is { value: Box::new(expr), enum_name, variant, span };
+        }
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> Result<Expression, SyntaxError> {
+        let mut expr = self.term()?;
+        while self.match_token(TokenType::ShiftLeft) || self.match_token(TokenType::ShiftRight) {
+            let op = if self.previous().unwrap().token_type == TokenType::ShiftLeft { BinaryOp::ShiftLeft } else { BinaryOp::ShiftRight };
             let right = self.term()?;
-            expr = Expression::Binary { left: Box::new(expr), op, right: Box::new(right) };
+            expr = binary(expr, op, right);
         }
         Ok(expr)
     }
@@ -439,29 +991,43 @@ impl<'a> Parser<'a> {
         while self.match_token(TokenType::Plus) || self.match_token(TokenType::Minus) {
             let op = if self.previous().unwrap().token_type == TokenType::Plus { BinaryOp::Plus } else { BinaryOp::Minus };
             let right = self.factor()?;
-            expr = Expression::Binary { left: Box::new(expr), op, right: Box::new(right) };
+            expr = binary(expr, op, right);
         }
         Ok(expr)
     }
 
     fn factor(&mut self) -> Result<Expression, SyntaxError> {
         let mut expr = self.unary()?;
-        while self.match_token(TokenType::Asterisk) || self.match_token(TokenType::Slash) {
-            let op = if self.previous().unwrap().token_type == TokenType::Asterisk { BinaryOp::Asterisk } else { BinaryOp::Slash };
+        while self.match_token(TokenType::Asterisk) || self.match_token(TokenType::Slash) || self.match_token(TokenType::Percent) {
+            let op = match self.previous().unwrap().token_type {
+                TokenType::Asterisk => BinaryOp::Asterisk,
+                TokenType::Slash => BinaryOp::Slash,
+                TokenType::Percent => BinaryOp::Percent,
+                _ => unreachable!(),
+            };
             let right = self.unary()?;
-            expr = Expression::Binary { left: Box::new(expr), op, right: Box::new(right) };
+            expr = binary(expr, op, right);
         }
         Ok(expr)
     }
 
     fn unary(&mut self) -> Result<Expression, SyntaxError> {
-        if self.match_token(TokenType::Minus) || self.match_token(TokenType::Exclamation) {
-            let op = if self.previous().unwrap().token_type == TokenType::Minus { UnaryOp::Minus } else { UnaryOp::Exclamation };
+        if self.match_token(TokenType::Minus) || self.match_token(TokenType::Exclamation) || self.match_token(TokenType::Tilde) {
+            let start = self.previous_start();
+            let op = match self.previous().unwrap().token_type {
+                TokenType::Minus => UnaryOp::Minus,
+                TokenType::Exclamation => UnaryOp::Exclamation,
+                _ => UnaryOp::Tilde,
+            };
+            if let Some(value) = matches!(op, UnaryOp::Minus).then(|| self.negated_int_literal()).flatten() {
+                return Ok(Expression::Literal(Literal::Int(value), self.span_from(start)));
+            }
             let expr = self.unary()?;
-            return Ok(Expression::Unary { op, expr: Box::new(expr) });
+            return Ok(Expression::Unary { op, expr: Box::new(expr), span: self.span_from(start) });
         } else if self.match_token(TokenType::Splat) {
+            let start = self.previous_start();
             let expr = self.unary()?;
-            return Ok(Expression::Splat(Box::new(expr)));
+            return Ok(Expression::Splat(Box::new(expr), self.span_from(start)));
         }
         self.postfix()
     }
@@ -473,37 +1039,26 @@ impl<'a> Parser<'a> {
             if self.match_token(TokenType::LeftParen) {
                 expr = self.finish_call(expr)?;
             } else if self.match_token(TokenType::Dot) {
-                let property = self.consume(TokenType::Identifier, "Se esperaba el nombre de la propiedad después de '.'.")?;
-                expr = Expression::MemberAccess {
-                    object: Box::new(expr),
-                    property: Identifier {
-                        name: property.lexeme.clone(),
-                        line: property.line,
-                        column: property.column,
-                    },
-                };
+                let property = identifier(self.consume(TokenType::Identifier, "el nombre de la propiedad después de '.'")?);
+                let span = expr.span().to(property.span);
+                expr = Expression::MemberAccess { object: Box::new(expr), property, span };
+            } else if self.match_token(TokenType::LeftBracket) {
+                let index = self.with_struct_literals(true, Self::expression)?;
+                self.expect(TokenType::RightBracket, "']' después del índice");
+                let span = self.span_from(expr.span().start);
+                expr = Expression::Index { object: Box::new(expr), index: Box::new(index), span };
             } else if self.match_token(TokenType::Increment) || self.match_token(TokenType::Decrement) {
-                let op_type = self.previous().unwrap().token_type;
-                
-                if let Expression::Identifier(target_id) = expr {
-                    let binary_op = if op_type == TokenType::Increment {
-                        BinaryOp::Plus
-                    } else {
-                        BinaryOp::Minus
-                    };
-                    let right_hand_side = Expression::Binary {
-                        left: Box::new(Expression::Identifier(target_id.clone())),
-                        op: binary_op,
-                        right: Box::new(Expression::Literal(Literal::Int(1))),
-                    };
-
-                    expr = Expression::Assignment {
-                        target: target_id,
-                        value: Box::new(right_hand_side),
-                    };
+                let op = if self.previous().unwrap().token_type == TokenType::Increment { BinaryOp::Plus } else { BinaryOp::Minus };
+                let op_span = self.span_from(self.previous_start());
+                let span = expr.span().to(op_span);
+
+                expr = if self.check_assignment_target(&expr) {
+                    // `x++` equivale a `x += 1`; el literal implícito toma la posición del operador.
+                    let one = Expression::Literal(Literal::Int(1), op_span);
+                    Expression::CompoundAssignment { target: Box::new(expr), op, value: Box::new(one), span }
                 } else {
-                    return Err(SyntaxError::InvalidAssignmentTarget);
-                }
+                    Expression::Error(span)
+                };
             } else {
                 break;
             }
@@ -513,104 +1068,325 @@ impl<'a> Parser<'a> {
 
 
     fn finish_call(&mut self, callee: Expression) -> Result<Expression, SyntaxError> {
+        let start = callee.span().start;
         let mut arguments = Vec::new();
         if !self.check(TokenType::RightParen) {
             loop {
-                arguments.push(self.expression()?);
+                arguments.push(self.with_struct_literals(true, Self::expression)?);
                 if !self.match_token(TokenType::Comma) { break; }
             }
         }
-        self.consume(TokenType::RightParen, "Se esperaba ')' después de los argumentos.")?;
-        Ok(Expression::FunctionCall { function: Box::new(callee), arguments })
+        self.expect(TokenType::RightParen, "')' después de los argumentos");
+        Ok(Expression::FunctionCall { function: Box::new(callee), arguments, span: self.span_from(start) })
     }
 
     fn primary(&mut self) -> Result<Expression, SyntaxError> {
-        if self.peek().map_or(false, |t| t.lexeme == "true") {
-            self.advance();
-            return Ok(Expression::Literal(Literal::Bool(true)));
+        if self.match_token(TokenType::True) {
+            return Ok(Expression::Literal(Literal::Bool(true), self.span_from(self.previous_start())));
         }
-        if self.peek().map_or(false, |t| t.lexeme == "false") {
-            self.advance();
-            return Ok(Expression::Literal(Literal::Bool(false)));
+        if self.match_token(TokenType::False) {
+            return Ok(Expression::Literal(Literal::Bool(false), self.span_from(self.previous_start())));
         }
 
         if self.match_token(TokenType::Integer) {
-            let token = self.previous().unwrap();
-            return Ok(Expression::Literal(Literal::Int(token.lexeme.parse().unwrap())));
+            let token = self.previous().unwrap().clone();
+            return match parse_int_literal(&token.lexeme) {
+                Some(value) => Ok(Expression::Literal(Literal::Int(value), token_span(&token))),
+                None => Ok(self.number_out_of_range(&token)),
+            };
         }
         if self.match_token(TokenType::Float) {
-            let token = self.previous().unwrap();
-            return Ok(Expression::Literal(Literal::Float(token.lexeme.parse().unwrap())));
+            let token = self.previous().unwrap().clone();
+            return match parse_float_literal(&token.lexeme) {
+                Some(value) => Ok(Expression::Literal(Literal::Float(value), token_span(&token))),
+                None => Ok(self.number_out_of_range(&token)),
+            };
         }
         if self.match_token(TokenType::String) {
             let token = self.previous().unwrap();
-            return Ok(Expression::Literal(Literal::String(token.lexeme.clone())));
+            return Ok(Expression::Literal(Literal::String(token.lexeme.to_string()), token_span(token)));
+        }
+        if self.match_token(TokenType::Char) {
+            let token = self.previous().unwrap();
+            // El lexer solo emite `Char` para literales de exactamente un carácter.
+            let value = token.lexeme.chars().next().unwrap_or_default();
+            return Ok(Expression::Literal(Literal::Char(value), token_span(token)));
+        }
+        if self.match_token(TokenType::If) {
+            return self.if_expression();
+        }
+        if self.match_token(TokenType::InterpolatedString) {
+            let token = self.previous().unwrap().clone();
+            return Ok(self.interpolated_string(&token));
         }
         if self.match_token(TokenType::LeftBracket) {
+            let start = self.previous_start();
             let mut elements = Vec::new();
             if !self.check(TokenType::RightBracket) {
                 loop {
-                    elements.push(self.expression()?);
+                    elements.push(self.with_struct_literals(true, Self::expression)?);
                     if !self.match_token(TokenType::Comma) { break; }
                 }
             }
-            self.consume(TokenType::RightBracket, "Se esperaba ']' al final del array.")?;
-            return Ok(Expression::Array(elements));
+            self.expect(TokenType::RightBracket, "']' al final del array");
+            return Ok(Expression::Array(elements, self.span_from(start)));
         }
         if self.match_token(TokenType::LeftBrace) {
+            let start = self.previous_start();
             let mut fields = Vec::new();
             while !self.check(TokenType::RightBrace) {
-                let key_token = self.consume(TokenType::Identifier, "Se esperaba una clave en el literal de objeto.")?.clone();
-                let key = Identifier { name: key_token.lexeme, line: key_token.line, column: key_token.column };
-                self.consume(TokenType::Colon, "Se esperaba ':' después de la clave.")?;
+                let key = identifier(self.consume(TokenType::Identifier, "una clave en el literal de objeto")?);
+                self.consume(TokenType::Colon, "':' después de la clave")?;
                 let value = self.expression()?;
                 fields.push((key, value));
                 if !self.check(TokenType::RightBrace) {
-                   self.consume(TokenType::Comma, "Se esperaba ',' después del valor.")?;
+                   self.consume(TokenType::Comma, "',' después del valor")?;
                 }
             }
-            self.consume(TokenType::RightBrace, "Se esperaba '}' al final del objeto literal.")?;
-            return Ok(Expression::Object(fields));
+            self.consume(TokenType::RightBrace, "'}' al final del objeto literal")?;
+            return Ok(Expression::Object(fields, self.span_from(start)));
         }
         if self.check(TokenType::Identifier) {
-            if self.tokens.get(self.current + 1).map_or(false, |t| t.token_type == TokenType::LeftBrace) {
+            if self.peek_next().is_some_and(|t| t.token_type == TokenType::DoubleColon) {
+                return self.enum_variant();
+            }
+            if self.struct_literals_allowed && self.peek_next().map_or(false, |t| t.token_type == TokenType::LeftBrace) {
                 return self.struct_instantiation();
             } else {
                 let token = self.advance().unwrap();
-                return Ok(Expression::Identifier(Identifier { name: token.lexeme.clone(), line: token.line, column: token.column }));
+                return Ok(Expression::Identifier(identifier(token)));
             }
         }
         if self.match_token(TokenType::LeftParen) {
-            let expr = self.expression()?;
-            self.consume(TokenType::RightParen, "Se esperaba ')' después de la expresión.")?;
-            return Ok(Expression::Grouped(Box::new(expr)));
+            let start = self.previous_start();
+            let expr = self.with_struct_literals(true, Self::expression)?;
+            self.expect(TokenType::RightParen, "')' después de la expresión");
+            return Ok(Expression::Grouped(Box::new(expr), self.span_from(start)));
+        }
+
+        // Falta la expresión: se reporta y queda un hueco en su lugar. Un token
+        // que no puede continuar la construcción se consume junto con el hueco.
+        let start = self.current_start();
+        let err = self.unexpected("una expresión");
+        if self.peek().is_some_and(|t| !is_recovery_point(t.token_type)) {
+            self.advance();
         }
-        let token = self.peek().unwrap();
-        let err = SyntaxError::UnexpectedToken(format!("Token inesperado: '{}'", token.lexeme), token.line, token.column);
-        self.errors.push(err.clone());
-        Err(err)
+        self.report(err);
+        Ok(Expression::Error(self.span_from(start)))
+    }
+
+    /// `if` usado como valor. A diferencia de la sentencia, la condición no necesita
+    /// paréntesis y la rama `else` es obligatoria.
+    fn if_expression(&mut self) -> Result<Expression, SyntaxError> {
+        let start = self.previous_start();
+        // Como en `for`, la llave que sigue a la condición abre la primera rama.
+        let condition = self.with_struct_literals(false, Self::expression)?;
+        let then_branch = self.value_block()?;
+        self.consume(TokenType::Else, "'else' en una expresión 'if'")?;
+        let else_branch = if self.match_token(TokenType::If) { self.if_expression()? } else { self.value_block()? };
+        Ok(Expression::If {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+            span: self.span_from(start),
+        })
+    }
+
+    fn interpolated_string(&mut self, literal: &Token<'src>) -> Expression {
+        let parts = interpolation_parts(literal)
+            .into_iter()
+            .map(|part| match part {
+                StringPart::Text(text) => InterpolationPart::Text(text.into_owned()),
+                StringPart::Expression(tokens) => InterpolationPart::Expression(Box::new(self.interpolated_expression(tokens, literal))),
+            })
+            .collect();
+        Expression::InterpolatedString(parts, token_span(literal))
+    }
+
+    /// Analiza los tokens de un `${...}` con un parser aparte, que debe
+    /// consumirlos todos; sus errores se suman a los de este parser. Si la
+    /// expresión no es válida, el fragmento queda como `Expression::Error`.
+    fn interpolated_expression(&mut self, tokens: Vec<Token<'_>>, literal: &Token<'src>) -> Expression {
+        let end = tokens.last().map_or(literal.start_position(), Token::end_position);
+        // El final de los tokens se presenta en los errores como la '}' que cierra la interpolación.
+        let mut parser = Parser::from_tokens(tokens.into_iter().chain(iter::once(Token::new(TokenType::EndOfFile, "}", end, end))));
+        let expression = if parser.is_at_end() {
+            parser.report(SyntaxError::UnexpectedToken {
+                expected: "una expresión dentro de '${}'",
+                found: "'}'".to_string(),
+                span: token_span(literal),
+            });
+            None
+        } else {
+            match parser.expression() {
+                Ok(expr) if parser.is_at_end() => Some(expr),
+                Ok(_) => {
+                    let err = parser.unexpected("'}' al final de la interpolación");
+                    parser.report(err);
+                    None
+                }
+                Err(_) => None,
+            }
+        };
+        self.errors.append(&mut parser.errors);
+        expression.unwrap_or(Expression::Error(token_span(literal)))
+    }
+
+    /// Tras un `-`, el entero que solo cabe en `i64` con el signo (`-9223372036854775808`):
+    /// lo consume y devuelve su valor ya negado. Si le sigue un operador posfijo, el `-`
+    /// no se aplica al literal sino al resultado, y el literal queda fuera de rango.
+    fn negated_int_literal(&mut self) -> Option<i64> {
+        let token = self.peek().filter(|t| t.token_type == TokenType::Integer)?;
+        if parse_int_literal(&token.lexeme).is_some() {
+            return None;
+        }
+        let value = parse_negated_int_literal(&token.lexeme)?;
+        let postfix = [TokenType::LeftParen, TokenType::Dot, TokenType::LeftBracket, TokenType::Increment, TokenType::Decrement];
+        if self.peek_next().is_some_and(|t| postfix.contains(&t.token_type)) {
+            return None;
+        }
+        self.advance();
+        Some(value)
+    }
+
+    /// Reporta un literal numérico que no cabe en su tipo; queda como un hueco en el AST.
+    fn number_out_of_range(&mut self, token: &Token<'src>) -> Expression {
+        self.report(SyntaxError::NumberOutOfRange { found: describe(Some(token)), span: token_span(token) });
+        Expression::Error(token_span(token))
     }
 
     fn struct_instantiation(&mut self) -> Result<Expression, SyntaxError> {
-        let name_token = self.consume(TokenType::Identifier, "Se esperaba el nombre del struct.")?.clone();
-        let name = Identifier { name: name_token.lexeme, line: name_token.line, column: name_token.column };
-        self.consume(TokenType::LeftBrace, "Se esperaba '{' para instanciar el struct.")?;
+        let name = identifier(self.consume(TokenType::Identifier, "el nombre del struct")?);
+        self.consume(TokenType::LeftBrace, "'{' para instanciar el struct")?;
         let mut fields = Vec::new();
         while !self.check(TokenType::RightBrace) {
-            let key_token = self.consume(TokenType::Identifier, "Se esperaba un nombre de campo.")?.clone();
-            let key = Identifier { name: key_token.lexeme, line: key_token.line, column: key_token.column };
-            self.consume(TokenType::Equal, "Se esperaba '=' después del nombre del campo.")?;
+            let key = identifier(self.consume(TokenType::Identifier, "un nombre de campo")?);
+            self.consume(TokenType::Equal, "'=' después del nombre del campo")?;
             let value = self.expression()?;
             fields.push((key, value));
             if !self.check(TokenType::RightBrace) {
-               self.consume(TokenType::Comma, "Se esperaba ',' después del valor del campo.")?;
+               self.consume(TokenType::Comma, "',' después del valor del campo")?;
+            }
+        }
+        self.consume(TokenType::RightBrace, "'}' al final de la instanciación")?;
+        let span = self.span_from(name.span.start);
+        Ok(Expression::StructInstantiation { name, fields, span })
+    }
+
+    /// `Enum::Variante`, con los valores de su carga útil entre paréntesis si los lleva.
+    fn enum_variant(&mut self) -> Result<Expression, SyntaxError> {
+        let (enum_name, variant) = self.variant_path()?;
+        let mut arguments = Vec::new();
+        if self.match_token(TokenType::LeftParen) {
+            if !self.check(TokenType::RightParen) {
+                loop {
+                    arguments.push(self.with_struct_literals(true, Self::expression)?);
+                    if !self.match_token(TokenType::Comma) { break; }
+                }
             }
+            self.expect(TokenType::RightParen, "')' después de la carga útil");
         }
-        self.consume(TokenType::RightBrace, "Se esperaba '}' al final de la instanciación.")?;
-        Ok(Expression::StructInstantiation { name, fields })
+        let span = self.span_from(enum_name.span.start);
+        Ok(Expression::EnumVariant { enum_name, variant, arguments, span })
+    }
+
+    fn variant_path(&mut self) -> Result<(Identifier, Identifier), SyntaxError> {
+        let enum_name = identifier(self.consume(TokenType::Identifier, "el nombre del enum")?);
+        self.consume(TokenType::DoubleColon, "'::' entre el enum y la variante")?;
+        let variant = identifier(self.consume(TokenType::Identifier, "el nombre de la variante")?);
+        Ok((enum_name, variant))
+    }
+}
+
+fn token_span(token: &Token<'_>) -> Span {
+    Span::new(token.start_position(), token.end_position())
+}
+
+fn identifier(token: &Token<'_>) -> Identifier {
+    Identifier { name: token.lexeme.to_string(), span: token_span(token) }
+}
+
+/// Describe un token en los mensajes de error: su lexema entre comillas o,
+/// si ya no quedan tokens, el final del archivo.
+fn describe(token: Option<&Token<'_>>) -> String {
+    match token {
+        Some(token) if !token.lexeme.is_empty() => format!("'{}'", token.lexeme),
+        _ => "el final del archivo".to_string(),
+    }
+}
+
+/// Expresiones a las que se puede asignar: una variable o un elemento o campo
+/// de algo que a su vez se puede modificar (`a.b[i].c`).
+fn is_assignment_target(expr: &Expression) -> bool {
+    match expr {
+        Expression::Identifier(_) => true,
+        Expression::Index { object, .. } | Expression::MemberAccess { object, .. } => is_assignment_target(object),
+        _ => false,
     }
 }
 
+fn invalid_assignment_target(target: &Expression) -> SyntaxError {
+    let found = match target {
+        Expression::Literal(..) => "un literal",
+        Expression::FunctionCall { .. } => "una llamada a función",
+        Expression::Grouped(..) => "una expresión entre paréntesis",
+        _ => "una expresión",
+    };
+    SyntaxError::InvalidAssignmentTarget { found: found.to_string(), span: target.span() }
+}
+
+fn binary(left: Expression, op: BinaryOp, right: Expression) -> Expression {
+    let span = left.span().to(right.span());
+    Expression::Binary { left: Box::new(left), op, right: Box::new(right), span }
+}
+
+/// Limpia el texto de un comentario de documentación: quita el espacio que
+/// sigue a `///` y, en los bloques `/** */`, el `*` con el que suele empezar cada línea.
+fn doc_text(lexeme: &str) -> String {
+    if !lexeme.contains('\n') {
+        return lexeme.strip_prefix(' ').unwrap_or(lexeme).trim_end().to_string();
+    }
+    let lines: Vec<&str> = lexeme
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let trimmed = trimmed.strip_prefix('*').unwrap_or(trimmed);
+            trimmed.strip_prefix(' ').unwrap_or(trimmed).trim_end()
+        })
+        .collect();
+    let first = lines.iter().position(|l| !l.is_empty()).unwrap_or(lines.len());
+    let last = lines.iter().rposition(|l| !l.is_empty()).map_or(first, |i| i + 1);
+    lines[first..last].join("\n")
+}
+
+/// Convierte el lexema de un entero (con prefijo de base y separadores `_`)
+/// a `i64`. Devuelve `None` si el valor no cabe.
+fn parse_int_literal(lexeme: &str) -> Option<i64> {
+    int_literal_magnitude(lexeme).and_then(|value| i64::try_from(value).ok())
+}
+
+/// Como `parse_int_literal`, para el entero precedido de un `-`.
+fn parse_negated_int_literal(lexeme: &str) -> Option<i64> {
+    int_literal_magnitude(lexeme).and_then(|value| i64::try_from(-value).ok())
+}
+
+fn int_literal_magnitude(lexeme: &str) -> Option<i128> {
+    let digits: String = lexeme.chars().filter(|c| *c != '_').collect();
+    let (radix, body) = match digits.get(..2) {
+        Some("0x" | "0X") => (16, &digits[2..]),
+        Some("0o" | "0O") => (8, &digits[2..]),
+        Some("0b" | "0B") => (2, &digits[2..]),
+        _ => (10, &digits[..]),
+    };
+    i128::from_str_radix(body, radix).ok()
+}
+
+/// Convierte el lexema de un flotante a `f64`; los valores que desbordan a infinito se rechazan.
+fn parse_float_literal(lexeme: &str) -> Option<f64> {
+    let digits: String = lexeme.chars().filter(|c| *c != '_').collect();
+    digits.parse::<f64>().ok().filter(|value| value.is_finite())
+}
+
 pub fn parse_tokens(tokens: &[LexerToken]) -> ParseResult {
     let mut parser = Parser::new(tokens);
     let ast = parser.parse();
@@ -619,3 +1395,16 @@ pub fn parse_tokens(tokens: &[LexerToken]) -> ParseResult {
         errors: parser.errors,
     }
 }
+
+/// Escanea y analiza `source` en una sola pasada: el parser pide los tokens al
+/// lexer a medida que los necesita, sin construir la lista completa.
+pub fn parse_source(source: &str) -> (ParseResult, Vec<LexError>) {
+    let mut lexer = LexicalAnalyzer::new(source);
+    let mut parser = Parser::from_tokens(&mut lexer);
+    let ast = parser.parse();
+    let result = ParseResult {
+        ast,
+        errors: parser.errors,
+    };
+    (result, lexer.errors)
+}
@@ -1,33 +1,67 @@
 use crate::ast::*;
 use crate::grpc_services::compiler::AnnotatedNode;
+use crate::modules::Module;
 use crate::symbol_table::{Symbol, SymbolTable};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum SemanticError {
-    UndeclaredVariable(String, usize, usize),
-    RedeclaredVariable(String, usize, usize),
-    TypeMismatch(String, String, usize, usize),
-    InvalidAssignment(String, usize, usize),
-    UndefinedStruct(String, usize, usize),
-    RedeclaredStruct(String, usize, usize),
-    RedeclaredField(String, String, usize, usize),
-    FieldNotFound(String, String, usize, usize),
-    InvalidMemberAccess(String, usize, usize),
-    InvalidFunctionCallTarget(usize, usize),
-    UndefinedFunction(String, usize, usize),
-    ArgumentCountMismatch(String, usize, usize, usize, usize),
-    ArgumentTypeMismatch(String, usize, String, String, usize, usize),
-    ReturnOutsideFunction(usize, usize),
-    ReturnTypeMismatch(String, String, usize, usize),
-    MissingReturnStatement(String, usize, usize),
+    UndeclaredVariable(String, Span),
+    RedeclaredVariable(String, Span),
+    TypeMismatch(String, String, Span),
+    InvalidAssignment(String, Span),
+    UndefinedStruct(String, Span),
+    RedeclaredStruct(String, Span),
+    RedeclaredField(String, String, Span),
+    FieldNotFound(String, String, Span),
+    InvalidMemberAccess(String, Span),
+    InvalidFunctionCallTarget(Span),
+    UndefinedFunction(String, Span),
+    ArgumentCountMismatch(String, usize, usize, Span),
+    ArgumentTypeMismatch(String, usize, String, String, Span),
+    ReturnOutsideFunction(Span),
+    ReturnTypeMismatch(String, String, Span),
+    MissingReturnStatement(String, Span),
     MissingMainFunction,
-    InvalidMainFunctionSignature(String, usize, usize),
+    InvalidMainFunctionSignature(String, Span),
+    InvalidInterpolation(String, Span),
+    InvalidIndexTarget(String, Span),
+    MissingField(String, String, Span),
+    InvalidOperand(String, String, Span),
+    JumpOutsideLoop(String, Span),
+    UndefinedLabel(String, Span),
+    NotIterable(String, Span),
+    RangeOutsideFor(Span),
+    NonExhaustiveMatch(String, Span),
+    UnreachablePattern(Span),
+    UndefinedEnum(String, Span),
+    RedeclaredEnum(String, Span),
+    UndefinedVariant(String, String, Span),
+    RedeclaredVariant(String, String, Span),
+    PayloadCountMismatch(String, usize, usize, Span),
+    UndefinedType(String, Span),
+    MisplacedImport(Span),
+    InModule(String, Box<SemanticError>), // Error en un archivo importado: (módulo, error)
+}
+
+// Tipo que se infiere para los huecos que dejó el parser. Es compatible con
+// cualquier otro, para no sumar errores semánticos a uno de sintaxis ya reportado.
+const ERROR_TYPE: &str = "Error";
+
+// Operandos válidos de la aritmética que solo tiene sentido con números y de los operadores de bits.
+const NUMERIC_TYPES: &[Type] = &[Type::Int, Type::Float];
+const INTEGER_TYPES: &[Type] = &[Type::Int];
+// Tipos cuyos valores tienen un orden con el que formar un patrón de rango.
+const RANGE_PATTERN_TYPES: &[Type] = &[Type::Int, Type::Char];
+
+fn is_error(node: &AnnotatedNode) -> bool {
+    node.inferred_type == ERROR_TYPE
 }
 
 pub struct SemanticAnalyzer {
     pub symbol_table: SymbolTable,
     pub errors: Vec<SemanticError>,
     current_function: Option<(String, Type)>, // (function name, return type)
+    loop_labels: Vec<Option<String>>, // Etiquetas de los bucles que rodean a la sentencia actual
 }
 
 impl SemanticAnalyzer {
@@ -36,6 +70,7 @@ impl SemanticAnalyzer {
             symbol_table: SymbolTable::new(),
             errors: Vec::new(),
             current_function: None,
+            loop_labels: Vec::new(),
         }
     }
 
@@ -45,22 +80,53 @@ impl SemanticAnalyzer {
             .iter()
             .map(|d| self.analyze_declaration(d))
             .collect();
-        self.check_for_main_function();
-        AnnotatedNode {
-            node_type: "Program".to_string(),
-            children,
-            ..Default::default()
+        self.check_for_main_function(program);
+        with_span(
+            AnnotatedNode {
+                node_type: "Program".to_string(),
+                children,
+                ..Default::default()
+            },
+            program.span,
+        )
+    }
+
+    /// Analiza un programa de varios archivos, con las dependencias primero y el
+    /// archivo principal al final, como los deja `modules::load_program`. Los
+    /// errores de los archivos importados se envuelven en `InModule`. Devuelve
+    /// el AST anotado del archivo principal.
+    pub fn analyze_modules(&mut self, modules: &[Module]) -> AnnotatedNode {
+        let Some((entry, imported)) = modules.split_last() else {
+            self.errors.push(SemanticError::MissingMainFunction);
+            return AnnotatedNode { node_type: "Program".to_string(), ..Default::default() };
+        };
+        for module in imported {
+            let first_error = self.errors.len();
+            self.enter_module(module);
+            for declaration in &module.ast.declarations {
+                self.analyze_declaration(declaration);
+            }
+            self.symbol_table.leave_module();
+            let errors = self.errors.split_off(first_error);
+            self.errors.extend(errors.into_iter().map(|e| SemanticError::InModule(module.name(), Box::new(e))));
         }
+        self.enter_module(entry);
+        let annotated = self.analyze(&entry.ast);
+        self.symbol_table.leave_module();
+        annotated
     }
 
-    fn check_for_main_function(&mut self) {
+    fn enter_module(&mut self, module: &Module) {
+        let imports = module.imports.iter().map(|path| path.display().to_string()).collect();
+        self.symbol_table.enter_module(&module.name(), imports);
+    }
+
+    fn check_for_main_function(&mut self, program: &Program) {
         match self.symbol_table.lookup("main") {
             Some(symbol) => {
                 if let Symbol::Function {
                     parameters,
                     return_type,
-                    line,
-                    column,
                     ..
                 } = symbol
                 {
@@ -82,10 +148,16 @@ impl SemanticAnalyzer {
                             "Invalid 'main' function signature: {}",
                             signature_errors.join(" and ")
                         );
+                        let span = program
+                            .declarations
+                            .iter()
+                            .find_map(|d| match d {
+                                Declaration::Function(func) if func.name.name == "main" => Some(func.name.span),
+                                _ => None,
+                            })
+                            .unwrap_or_default();
                         self.errors
-                            .push(SemanticError::InvalidMainFunctionSignature(
-                                reason, *line, *column,
-                            ));
+                            .push(SemanticError::InvalidMainFunctionSignature(reason, span));
                     }
                 } else {
                     self.errors.push(SemanticError::MissingMainFunction);
@@ -98,31 +170,38 @@ impl SemanticAnalyzer {
     }
 
     fn analyze_declaration(&mut self, declaration: &Declaration) -> AnnotatedNode {
-        match declaration {
+        let node = match declaration {
             Declaration::Variable(var_decl) => self.analyze_variable_declaration(var_decl),
             Declaration::Function(func_decl) => self.analyze_function_declaration(func_decl),
             Declaration::Struct(struct_decl) => self.analyze_struct_declaration(struct_decl),
+            Declaration::Enum(enum_decl) => self.analyze_enum_declaration(enum_decl),
             Declaration::Constant(const_decl) => self.analyze_constant_declaration(const_decl),
+            Declaration::Import(import) => self.analyze_import(import),
             Declaration::Statement(stmt) => self.analyze_statement(stmt),
-        }
+        };
+        with_span(node, declaration.span())
     }
 
     fn analyze_variable_declaration(&mut self, var_decl: &VariableDeclaration) -> AnnotatedNode {
         let name = &var_decl.identifier.name;
-        let declared_type = self.get_type(&var_decl.var_type);
+        let declared_type = self.get_type(&var_decl.var_type, var_decl.identifier.span);
         let value_node = self.analyze_expression(&var_decl.value);
-        let value_type = Type::from_str(&value_node.inferred_type).unwrap_or(Type::Void);
+        let value_type = if is_error(&value_node) {
+            declared_type.clone()
+        } else {
+            self.type_from_str(&value_node.inferred_type).unwrap_or(Type::Void)
+        };
 
-        if declared_type != Type::Void && declared_type != value_type {
-            self.errors.push(SemanticError::TypeMismatch(
-                declared_type.to_string(),
-                value_type.to_string(),
-                var_decl.identifier.line,
-                var_decl.identifier.column,
-            ));
-        }
+        // Con un tipo escrito, el símbolo tiene ese tipo aunque el valor sea, por ejemplo, de un tipo
+        // que se convierte en opcional.
+        let value_type = if declared_type != Type::Void {
+            self.check_assignable(&declared_type, &value_node, &var_decl.value);
+            declared_type
+        } else {
+            value_type
+        };
 
-        let literal_value = if let Expression::Literal(lit) = &var_decl.value {
+        let literal_value = if let Expression::Literal(lit, _) = &var_decl.value {
             Some(lit.clone())
         } else {
             None
@@ -132,15 +211,15 @@ impl SemanticAnalyzer {
             name: name.clone(),
             type_: value_type.clone(),
             defined: true,
-            line: var_decl.identifier.line,
-            column: var_decl.identifier.column,
+            line: var_decl.identifier.span.start.line,
+            column: var_decl.identifier.span.start.column,
             value: literal_value,
+            doc: var_decl.doc.clone(),
         };
         if !self.symbol_table.insert(name.clone(), symbol) {
             self.errors.push(SemanticError::RedeclaredVariable(
                 name.clone(),
-                var_decl.identifier.line,
-                var_decl.identifier.column,
+                var_decl.identifier.span,
             ));
         }
 
@@ -148,8 +227,6 @@ impl SemanticAnalyzer {
             node_type: "VariableDeclaration".to_string(),
             value: "let".to_string(),
             children: vec![self.identifier_to_annotated(&var_decl.identifier), value_node],
-            start_line: var_decl.identifier.line as u32,
-            start_column: var_decl.identifier.column as u32,
             inferred_type: value_type.to_string(),
             ..Default::default()
         }
@@ -157,20 +234,22 @@ impl SemanticAnalyzer {
 
     fn analyze_constant_declaration(&mut self, const_decl: &ConstantDeclaration) -> AnnotatedNode {
         let name = &const_decl.identifier.name;
-        let declared_type = self.get_type(&const_decl.const_type);
+        let declared_type = self.get_type(&const_decl.const_type, const_decl.identifier.span);
         let value_node = self.analyze_expression(&const_decl.value);
-        let value_type = Type::from_str(&value_node.inferred_type).unwrap_or(Type::Void);
+        let value_type = if is_error(&value_node) {
+            declared_type.clone()
+        } else {
+            self.type_from_str(&value_node.inferred_type).unwrap_or(Type::Void)
+        };
 
-        if declared_type != Type::Void && declared_type != value_type {
-            self.errors.push(SemanticError::TypeMismatch(
-                declared_type.to_string(),
-                value_type.to_string(),
-                const_decl.identifier.line,
-                const_decl.identifier.column,
-            ));
-        }
+        let value_type = if declared_type != Type::Void {
+            self.check_assignable(&declared_type, &value_node, &const_decl.value);
+            declared_type
+        } else {
+            value_type
+        };
 
-        let literal_value = if let Expression::Literal(lit) = &const_decl.value {
+        let literal_value = if let Expression::Literal(lit, _) = &const_decl.value {
             Some(lit.clone())
         } else {
             None
@@ -179,16 +258,16 @@ impl SemanticAnalyzer {
         let symbol = Symbol::Constant {
             name: name.clone(),
             type_: value_type.clone(),
-            line: const_decl.identifier.line,
-            column: const_decl.identifier.column,
+            line: const_decl.identifier.span.start.line,
+            column: const_decl.identifier.span.start.column,
             value: literal_value,
+            doc: const_decl.doc.clone(),
         };
 
         if !self.symbol_table.insert(name.clone(), symbol) {
             self.errors.push(SemanticError::RedeclaredVariable(
                 name.clone(),
-                const_decl.identifier.line,
-                const_decl.identifier.column,
+                const_decl.identifier.span,
             ));
         }
 
@@ -199,34 +278,46 @@ impl SemanticAnalyzer {
                 self.identifier_to_annotated(&const_decl.identifier),
                 value_node,
             ],
-            start_line: const_decl.identifier.line as u32,
-            start_column: const_decl.identifier.column as u32,
             inferred_type: value_type.to_string(),
             ..Default::default()
         }
     }
 
+    // Los archivos importados ya los cargó `modules::load_program`; aquí solo se
+    // comprueba que el `import` no esté dentro de una función o un bloque.
+    fn analyze_import(&mut self, import: &ImportDeclaration) -> AnnotatedNode {
+        if !self.symbol_table.at_top_level() {
+            self.errors.push(SemanticError::MisplacedImport(import.span));
+        }
+        AnnotatedNode {
+            node_type: "ImportDeclaration".to_string(),
+            value: import.path.clone(),
+            inferred_type: "Void".to_string(),
+            ..Default::default()
+        }
+    }
+
     fn analyze_function_declaration(&mut self, func_decl: &Function) -> AnnotatedNode {
         let name = &func_decl.name.name;
         let parameters: Vec<Type> = func_decl
             .parameters
             .iter()
-            .map(|p| p.param_type.clone())
+            .map(|p| self.check_type(&p.param_type, p.span))
             .collect();
-        let return_type = func_decl.return_type.clone();
+        let return_type = self.check_type(&func_decl.return_type, func_decl.name.span);
         let symbol = Symbol::Function {
             name: name.clone(),
             parameters: parameters.clone(),
             return_type: return_type.clone(),
-            line: func_decl.name.line,
-            column: func_decl.name.column,
+            line: func_decl.name.span.start.line,
+            column: func_decl.name.span.start.column,
+            doc: func_decl.doc.clone(),
         };
 
         if !self.symbol_table.insert(name.clone(), symbol) {
             self.errors.push(SemanticError::RedeclaredVariable(
                 name.clone(),
-                func_decl.name.line,
-                func_decl.name.column,
+                func_decl.name.span,
             ));
         }
 
@@ -237,31 +328,33 @@ impl SemanticAnalyzer {
         let params_nodes: Vec<AnnotatedNode> = func_decl
             .parameters
             .iter()
-            .map(|p| {
+            .zip(&parameters)
+            .map(|(p, param_type)| {
                 let param_name = &p.name.name;
                 let param_symbol = Symbol::Variable {
                     name: param_name.clone(),
-                    type_: p.param_type.clone(),
+                    type_: param_type.clone(),
                     defined: true,
-                    line: p.name.line,
-                    column: p.name.column,
+                    line: p.name.span.start.line,
+                    column: p.name.span.start.column,
                     value: None,
+                    doc: None,
                 };
                 if !self.symbol_table.insert(param_name.clone(), param_symbol) {
                     self.errors.push(SemanticError::RedeclaredVariable(
                         param_name.clone(),
-                        p.name.line,
-                        p.name.column,
+                        p.name.span,
                     ));
                 }
-                AnnotatedNode {
-                    node_type: "Parameter".to_string(),
-                    value: p.name.name.clone(),
-                    inferred_type: p.param_type.to_string(),
-                    start_line: p.name.line as u32,
-                    start_column: p.name.column as u32,
-                    ..Default::default()
-                }
+                with_span(
+                    AnnotatedNode {
+                        node_type: "Parameter".to_string(),
+                        value: p.name.name.clone(),
+                        inferred_type: param_type.to_string(),
+                        ..Default::default()
+                    },
+                    p.span,
+                )
             })
             .collect();
 
@@ -271,8 +364,7 @@ impl SemanticAnalyzer {
         if return_type != Type::Void && !has_return {
             self.errors.push(SemanticError::MissingReturnStatement(
                 name.clone(),
-                func_decl.name.line,
-                func_decl.name.column,
+                func_decl.name.span,
             ));
         }
 
@@ -290,8 +382,6 @@ impl SemanticAnalyzer {
                 },
                 body_node,
             ],
-            start_line: func_decl.name.line as u32,
-            start_column: func_decl.name.column as u32,
             inferred_type: return_type.to_string(),
             ..Default::default()
         }
@@ -312,11 +402,14 @@ impl SemanticAnalyzer {
             }
         }
         self.symbol_table.leave_scope();
-        AnnotatedNode {
-            node_type: "Block".to_string(),
-            children,
-            ..Default::default()
-        }
+        with_span(
+            AnnotatedNode {
+                node_type: "Block".to_string(),
+                children,
+                ..Default::default()
+            },
+            block.span,
+        )
     }
 
     fn analyze_statement_with_return_check(
@@ -330,6 +423,11 @@ impl SemanticAnalyzer {
                 self.analyze_return_statement(r)
             }
             Statement::Block(block) => self.analyze_block_with_return_check(block, has_return),
+            // El código descartado podría contener el 'return': no se reporta que falta.
+            Statement::Error(_) => {
+                *has_return = true;
+                self.analyze_statement(stmt)
+            }
             Statement::If(if_stmt) => {
                 let then_node = self.analyze_statement_with_return_check(
                     &Statement::Block(if_stmt.then_block.clone()),
@@ -348,16 +446,20 @@ impl SemanticAnalyzer {
                 } else {
                     None
                 };
-                let mut children = vec![self.analyze_expression(&if_stmt.condition), then_node];
+                let mut children = vec![self.analyze_condition(&if_stmt.condition), then_node];
                 if let Some(node) = else_node {
                     children.push(node);
                 }
-                AnnotatedNode {
-                    node_type: "IfStatement".to_string(),
-                    children,
-                    ..Default::default()
-                }
+                with_span(
+                    AnnotatedNode {
+                        node_type: "IfStatement".to_string(),
+                        children,
+                        ..Default::default()
+                    },
+                    if_stmt.span,
+                )
             }
+            Statement::Match(match_stmt) => with_span(self.analyze_match(match_stmt, has_return), match_stmt.span),
             _ => self.analyze_statement(stmt),
         }
     }
@@ -372,32 +474,33 @@ impl SemanticAnalyzer {
                 self.errors.push(SemanticError::RedeclaredField(
                     name.clone(),
                     field.name.name.clone(),
-                    field.name.line,
-                    field.name.column,
+                    field.name.span,
                 ));
             }
-            fields.insert(field.name.name.clone(), field.field_type.clone());
-            field_nodes.push(AnnotatedNode {
-                node_type: "FieldDeclaration".to_string(),
-                value: field.name.name.clone(),
-                inferred_type: field.field_type.to_string(),
-                start_line: field.name.line as u32,
-                start_column: field.name.column as u32,
-                ..Default::default()
-            });
+            let field_type = self.check_type(&field.field_type, field.span);
+            field_nodes.push(with_span(
+                AnnotatedNode {
+                    node_type: "FieldDeclaration".to_string(),
+                    value: field.name.name.clone(),
+                    inferred_type: field_type.to_string(),
+                    ..Default::default()
+                },
+                field.span,
+            ));
+            fields.insert(field.name.name.clone(), field_type);
         }
 
         let symbol = Symbol::Struct {
             name: name.clone(),
             fields,
-            line: struct_decl.name.line,
-            column: struct_decl.name.column,
+            line: struct_decl.name.span.start.line,
+            column: struct_decl.name.span.start.column,
+            doc: struct_decl.doc.clone(),
         };
         if !self.symbol_table.insert(name.clone(), symbol) {
             self.errors.push(SemanticError::RedeclaredStruct(
                 name.clone(),
-                struct_decl.name.line,
-                struct_decl.name.column,
+                struct_decl.name.span,
             ));
         }
 
@@ -405,18 +508,71 @@ impl SemanticAnalyzer {
             node_type: "StructDeclaration".to_string(),
             value: name.clone(),
             children: field_nodes,
-            start_line: struct_decl.name.line as u32,
-            start_column: struct_decl.name.column as u32,
+            ..Default::default()
+        }
+    }
+
+    fn analyze_enum_declaration(&mut self, enum_decl: &EnumDeclaration) -> AnnotatedNode {
+        let name = &enum_decl.name.name;
+        let mut variants: Vec<(String, Vec<Type>)> = Vec::new();
+        let mut variant_nodes = vec![];
+
+        for variant in &enum_decl.variants {
+            let payload: Vec<Type> = variant.payload.iter().map(|t| self.check_type(t, variant.span)).collect();
+            if variants.iter().any(|(declared, _)| *declared == variant.name.name) {
+                self.errors.push(SemanticError::RedeclaredVariant(
+                    name.clone(),
+                    variant.name.name.clone(),
+                    variant.name.span,
+                ));
+            } else {
+                variants.push((variant.name.name.clone(), payload.clone()));
+            }
+            let payload_nodes = payload
+                .iter()
+                .map(|payload_type| AnnotatedNode {
+                    node_type: "PayloadType".to_string(),
+                    inferred_type: payload_type.to_string(),
+                    ..Default::default()
+                })
+                .collect();
+            variant_nodes.push(with_span(
+                AnnotatedNode {
+                    node_type: "VariantDeclaration".to_string(),
+                    value: variant.name.name.clone(),
+                    children: payload_nodes,
+                    inferred_type: name.clone(),
+                    ..Default::default()
+                },
+                variant.span,
+            ));
+        }
+
+        let symbol = Symbol::Enum {
+            name: name.clone(),
+            variants,
+            line: enum_decl.name.span.start.line,
+            column: enum_decl.name.span.start.column,
+            doc: enum_decl.doc.clone(),
+        };
+        if !self.symbol_table.insert(name.clone(), symbol) {
+            self.errors.push(SemanticError::RedeclaredEnum(name.clone(), enum_decl.name.span));
+        }
+
+        AnnotatedNode {
+            node_type: "EnumDeclaration".to_string(),
+            value: name.clone(),
+            children: variant_nodes,
             ..Default::default()
         }
     }
 
     fn analyze_statement(&mut self, statement: &Statement) -> AnnotatedNode {
-        match statement {
+        let node = match statement {
             Statement::Expression(expr) => self.analyze_expression(expr),
             Statement::Block(block) => self.analyze_block(block),
             Statement::If(if_stmt) => {
-                let cond_node = self.analyze_expression(&if_stmt.condition);
+                let cond_node = self.analyze_condition(&if_stmt.condition);
                 let then_node = self.analyze_block(&if_stmt.then_block);
                 let else_node = if_stmt.else_block.as_ref().map(|branch| match branch {
                     ElseBranch::Block(block) => self.analyze_statement(block),
@@ -435,8 +591,8 @@ impl SemanticAnalyzer {
                 }
             }
             Statement::While(while_stmt) => {
-                let cond_node = self.analyze_expression(&while_stmt.condition);
-                let body_node = self.analyze_block(&while_stmt.body);
+                let cond_node = self.analyze_condition(&while_stmt.condition);
+                let body_node = self.analyze_loop_body(&while_stmt.label, &while_stmt.body);
                 AnnotatedNode {
                     node_type: "WhileStatement".to_string(),
                     children: vec![cond_node, body_node],
@@ -445,56 +601,358 @@ impl SemanticAnalyzer {
             }
             Statement::Return(return_stmt) => self.analyze_return_statement(return_stmt),
             Statement::For(for_stmt) => {
+                // El iterable se analiza antes de declarar la variable: en `for i in 0..i`
+                // el segundo `i` es el de fuera del bucle.
+                let (iterable_node, element_type) = self.analyze_iterable(&for_stmt.iterable);
                 self.symbol_table.enter_scope("for_loop".to_string());
                 let var_name = &for_stmt.variable.name;
                 let symbol = Symbol::Variable {
                     name: var_name.clone(),
-                    type_: Type::Int, // Assuming loop variable is an integer
+                    type_: element_type.clone(),
                     defined: true,
-                    line: for_stmt.variable.line,
-                    column: for_stmt.variable.column,
+                    line: for_stmt.variable.span.start.line,
+                    column: for_stmt.variable.span.start.column,
                     value: None,
+                    doc: None,
                 };
                 self.symbol_table.insert(var_name.clone(), symbol);
-                let iterable_node = self.analyze_expression(&for_stmt.iterable);
-                let body_node = self.analyze_block(&for_stmt.body);
+                let mut variable_node = self.identifier_to_annotated(&for_stmt.variable);
+                variable_node.inferred_type = element_type.to_string();
+                let body_node = self.analyze_loop_body(&for_stmt.label, &for_stmt.body);
                 self.symbol_table.leave_scope();
                 AnnotatedNode {
                     node_type: "ForStatement".to_string(),
-                    children: vec![
-                        self.identifier_to_annotated(&for_stmt.variable),
-                        iterable_node,
-                        body_node,
-                    ],
+                    children: vec![variable_node, iterable_node, body_node],
                     ..Default::default()
                 }
             }
-            _ => AnnotatedNode::default(),
+            Statement::DoUntil(do_until) => {
+                let body_node = self.analyze_loop_body(&do_until.label, &do_until.body);
+                let cond_node = self.analyze_condition(&do_until.condition);
+                AnnotatedNode {
+                    node_type: "DoUntilStatement".to_string(),
+                    children: vec![body_node, cond_node],
+                    ..Default::default()
+                }
+            }
+            Statement::Match(match_stmt) => self.analyze_match(match_stmt, &mut false),
+            Statement::Break(jump) => self.analyze_jump("break", jump),
+            Statement::Continue(jump) => self.analyze_jump("continue", jump),
+            Statement::Error(_) => AnnotatedNode {
+                node_type: "Error".to_string(),
+                ..Default::default()
+            },
+        };
+        with_span(node, statement.span())
+    }
+
+    /// Analiza lo que recorre un `for` (un rango o un arreglo) y devuelve el tipo de
+    /// sus elementos. Si no se puede recorrer, la variable del bucle queda con el tipo
+    /// de error, para que sus usos no sumen más errores.
+    fn analyze_iterable(&mut self, iterable: &Expression) -> (AnnotatedNode, Type) {
+        if let Expression::Range { start, end, inclusive, step, span } = iterable {
+            let node = self.analyze_range(start, end, *inclusive, step.as_deref());
+            return (with_span(node, *span), Type::Int);
+        }
+        let node = self.analyze_expression(iterable);
+        let element_type = match self.type_from_str(&node.inferred_type) {
+            Some(Type::Array(element) | Type::FixedArray(element, _)) if !is_error(&node) => *element,
+            _ => {
+                if !is_error(&node) {
+                    self.errors.push(SemanticError::NotIterable(node.inferred_type.clone(), iterable.span()));
+                }
+                Type::Struct(ERROR_TYPE.to_string())
+            }
+        };
+        (node, element_type)
+    }
+
+    /// Analiza un rango; sus límites y su paso deben ser Int.
+    fn analyze_range(&mut self, start: &Expression, end: &Expression, inclusive: bool, step: Option<&Expression>) -> AnnotatedNode {
+        let children = [start, end]
+            .into_iter()
+            .chain(step)
+            .map(|part| {
+                let node = self.analyze_expression(part);
+                self.check_operand("..", &node.inferred_type, INTEGER_TYPES, part.span());
+                node
+            })
+            .collect();
+        AnnotatedNode {
+            node_type: "Range".to_string(),
+            value: if inclusive { "..=" } else { ".." }.to_string(),
+            children,
+            ..Default::default()
+        }
+    }
+
+    /// Analiza un `match`: los patrones deben ser del tipo del valor, las guardas
+    /// Bool y, entre todos, los brazos deben cubrir cualquier valor. Un brazo que ya
+    /// cubren los anteriores es inalcanzable. Si todos los brazos terminan en un
+    /// `return`, lo anota en `has_return`.
+    fn analyze_match(&mut self, match_stmt: &MatchStatement, has_return: &mut bool) -> AnnotatedNode {
+        let subject_node = self.analyze_expression(&match_stmt.subject);
+        let subject_type = self.type_from_str(&subject_node.inferred_type).filter(|_| !is_error(&subject_node));
+        let mut coverage = MatchCoverage::default();
+        let mut every_arm_returns = !match_stmt.arms.is_empty();
+        let mut children = vec![subject_node];
+
+        for arm in &match_stmt.arms {
+            self.symbol_table.enter_scope("match_arm".to_string());
+            let mut arm_children = vec![self.analyze_pattern(&arm.pattern, subject_type.as_ref())];
+            if let Some(guard) = &arm.guard {
+                let guard_node = self.analyze_condition(guard);
+                arm_children.push(guard_node);
+            }
+
+            // Un brazo con guarda puede no elegirse, así que no cubre ningún valor.
+            if !contains_error(&arm.pattern) && coverage.covers(&arm.pattern) {
+                self.errors.push(SemanticError::UnreachablePattern(arm.pattern.span()));
+            } else if arm.guard.is_none() {
+                coverage.add(&arm.pattern);
+            }
+
+            let mut arm_returns = false;
+            arm_children.push(self.analyze_block_with_return_check(&arm.body, &mut arm_returns));
+            every_arm_returns &= arm_returns;
+            self.symbol_table.leave_scope();
+            children.push(with_span(
+                AnnotatedNode {
+                    node_type: "MatchArm".to_string(),
+                    children: arm_children,
+                    ..Default::default()
+                },
+                arm.span,
+            ));
+        }
+
+        let has_pattern_errors = match_stmt.arms.iter().any(|arm| contains_error(&arm.pattern));
+        let enum_variants: Vec<String> = match subject_type.as_ref().map(|t| self.symbol_table.lookup(&t.to_string())) {
+            Some(Some(Symbol::Enum { variants, .. })) => variants.iter().map(|(name, _)| name.clone()).collect(),
+            _ => Vec::new(),
+        };
+        if let Some(subject_type) = subject_type.filter(|t| !has_pattern_errors && !coverage.is_exhaustive(t, &enum_variants)) {
+            self.errors.push(SemanticError::NonExhaustiveMatch(subject_type.to_string(), match_stmt.subject.span()));
+        }
+        *has_return |= every_arm_returns;
+        AnnotatedNode {
+            node_type: "MatchStatement".to_string(),
+            children,
+            ..Default::default()
+        }
+    }
+
+    /// Analiza un patrón contra el tipo que debe tener (`None` si no se conoce) y
+    /// declara en el ámbito actual las variables que introduce.
+    fn analyze_pattern(&mut self, pattern: &Pattern, expected: Option<&Type>) -> AnnotatedNode {
+        let node = match pattern {
+            Pattern::Wildcard(_) => AnnotatedNode {
+                node_type: "WildcardPattern".to_string(),
+                inferred_type: expected.map_or(ERROR_TYPE.to_string(), Type::to_string),
+                ..Default::default()
+            },
+            Pattern::Binding(id) => {
+                let type_ = expected.cloned().unwrap_or_else(|| Type::Struct(ERROR_TYPE.to_string()));
+                let symbol = Symbol::Variable {
+                    name: id.name.clone(),
+                    type_: type_.clone(),
+                    defined: true,
+                    line: id.span.start.line,
+                    column: id.span.start.column,
+                    value: None,
+                    doc: None,
+                };
+                if !self.symbol_table.insert(id.name.clone(), symbol) {
+                    self.errors.push(SemanticError::RedeclaredVariable(id.name.clone(), id.span));
+                }
+                AnnotatedNode {
+                    node_type: "BindingPattern".to_string(),
+                    value: id.name.clone(),
+                    inferred_type: type_.to_string(),
+                    ..Default::default()
+                }
+            }
+            Pattern::Literal(literal, span) => {
+                let literal_node = self.analyze_expression(&Expression::Literal(literal.clone(), *span));
+                self.check_pattern_type(expected, &literal_node.inferred_type, *span);
+                AnnotatedNode {
+                    node_type: "LiteralPattern".to_string(),
+                    inferred_type: literal_node.inferred_type.clone(),
+                    children: vec![literal_node],
+                    ..Default::default()
+                }
+            }
+            Pattern::Range { start, end, inclusive, span } => {
+                let start_node = self.analyze_expression(&Expression::Literal(start.clone(), *span));
+                let end_node = self.analyze_expression(&Expression::Literal(end.clone(), *span));
+                let ordered = self.check_operand("..", &start_node.inferred_type, RANGE_PATTERN_TYPES, *span);
+                if ordered && start_node.inferred_type != end_node.inferred_type {
+                    self.errors.push(SemanticError::TypeMismatch(
+                        start_node.inferred_type.clone(),
+                        end_node.inferred_type.clone(),
+                        *span,
+                    ));
+                } else if ordered {
+                    self.check_pattern_type(expected, &start_node.inferred_type, *span);
+                }
+                AnnotatedNode {
+                    node_type: "RangePattern".to_string(),
+                    value: if *inclusive { "..=" } else { ".." }.to_string(),
+                    inferred_type: start_node.inferred_type.clone(),
+                    children: vec![start_node, end_node],
+                    ..Default::default()
+                }
+            }
+            Pattern::Struct { name, fields, .. } => {
+                let declared = match self.symbol_table.lookup(&name.name) {
+                    Some(Symbol::Struct { fields, .. }) => Some(fields.clone()),
+                    _ => None,
+                };
+                match &declared {
+                    Some(_) => self.check_pattern_type(expected, &name.name, name.span),
+                    None => self.errors.push(SemanticError::UndefinedStruct(name.name.clone(), name.span)),
+                }
+
+                let mut children = vec![];
+                for field in fields {
+                    let field_type = match declared.as_ref().map(|declared| declared.get(&field.name.name)) {
+                        Some(Some(field_type)) => Some(field_type.clone()),
+                        Some(None) => {
+                            self.errors.push(SemanticError::FieldNotFound(
+                                name.name.clone(),
+                                field.name.name.clone(),
+                                field.name.span,
+                            ));
+                            None
+                        }
+                        None => None,
+                    };
+                    let field_node = self.analyze_pattern(&field.pattern, field_type.as_ref());
+                    children.push(with_span(
+                        AnnotatedNode {
+                            node_type: "FieldPattern".to_string(),
+                            value: field.name.name.clone(),
+                            inferred_type: field_node.inferred_type.clone(),
+                            children: vec![field_node],
+                            ..Default::default()
+                        },
+                        field.span,
+                    ));
+                }
+
+                AnnotatedNode {
+                    node_type: "StructPattern".to_string(),
+                    value: name.name.clone(),
+                    children,
+                    inferred_type: if declared.is_some() { name.name.clone() } else { ERROR_TYPE.to_string() },
+                    ..Default::default()
+                }
+            }
+            Pattern::Variant { enum_name, variant, payload, span } => {
+                let declared = self.variant_payload(enum_name, variant);
+                if declared.is_some() {
+                    self.check_pattern_type(expected, &enum_name.name, enum_name.span);
+                }
+                if let Some(declared) = declared.as_ref().filter(|declared| declared.len() != payload.len()) {
+                    self.errors.push(SemanticError::PayloadCountMismatch(
+                        format!("{}::{}", enum_name.name, variant.name),
+                        declared.len(),
+                        payload.len(),
+                        *span,
+                    ));
+                }
+                let children = payload
+                    .iter()
+                    .enumerate()
+                    .map(|(i, pattern)| {
+                        let payload_type = declared.as_ref().and_then(|declared| declared.get(i)).cloned();
+                        self.analyze_pattern(pattern, payload_type.as_ref())
+                    })
+                    .collect();
+
+                AnnotatedNode {
+                    node_type: "VariantPattern".to_string(),
+                    value: format!("{}::{}", enum_name.name, variant.name),
+                    children,
+                    inferred_type: if declared.is_some() { enum_name.name.clone() } else { ERROR_TYPE.to_string() },
+                    ..Default::default()
+                }
+            }
+            Pattern::Error(_) => AnnotatedNode {
+                node_type: "Error".to_string(),
+                inferred_type: ERROR_TYPE.to_string(),
+                ..Default::default()
+            },
+        };
+        with_span(node, pattern.span())
+    }
+
+    /// Reporta un patrón de tipo `found` donde se esperaba un valor de tipo `expected`.
+    fn check_pattern_type(&mut self, expected: Option<&Type>, found: &str, span: Span) {
+        if let Some(expected) = expected.filter(|expected| expected.to_string() != found) {
+            self.errors.push(SemanticError::TypeMismatch(expected.to_string(), found.to_string(), span));
+        }
+    }
+
+    /// Analiza una condición (de `if`, de un bucle o la guarda de un brazo), que debe ser `Bool`.
+    fn analyze_condition(&mut self, condition: &Expression) -> AnnotatedNode {
+        let node = self.analyze_expression(condition);
+        if node.inferred_type != Type::Bool.to_string() && !is_error(&node) {
+            self.errors.push(SemanticError::TypeMismatch(
+                Type::Bool.to_string(),
+                node.inferred_type.clone(),
+                condition.span(),
+            ));
+        }
+        node
+    }
+
+    /// Analiza el cuerpo de un bucle, dentro del cual valen `break` y `continue`.
+    fn analyze_loop_body(&mut self, label: &Option<Identifier>, body: &Block) -> AnnotatedNode {
+        self.loop_labels.push(label.as_ref().map(|label| label.name.clone()));
+        let node = self.analyze_block(body);
+        self.loop_labels.pop();
+        node
+    }
+
+    /// `break` o `continue`: debe estar dentro de un bucle y, si lleva etiqueta,
+    /// dentro de un bucle con esa etiqueta.
+    fn analyze_jump(&mut self, keyword: &str, jump: &JumpStatement) -> AnnotatedNode {
+        if self.loop_labels.is_empty() {
+            self.errors.push(SemanticError::JumpOutsideLoop(keyword.to_string(), jump.span));
+        } else if let Some(label) = jump.label.as_ref().filter(|label| !self.loop_labels.contains(&Some(label.name.clone()))) {
+            self.errors.push(SemanticError::UndefinedLabel(label.name.clone(), label.span));
+        }
+        AnnotatedNode {
+            node_type: if keyword == "break" { "BreakStatement" } else { "ContinueStatement" }.to_string(),
+            value: jump.label.as_ref().map(|label| label.name.clone()).unwrap_or_default(),
+            ..Default::default()
         }
     }
 
     fn analyze_return_statement(&mut self, return_stmt: &ReturnStatement) -> AnnotatedNode {
-        let (line, column) = return_stmt.value.get_line_col();
         let value_node = self.analyze_expression(&return_stmt.value);
         if let Some((_fn_name, return_type)) = &self.current_function {
-            let expr_type = Type::from_str(&value_node.inferred_type).unwrap_or(Type::Void);
-            if expr_type != *return_type {
+            let expr_type = self.type_from_str(&value_node.inferred_type).unwrap_or(Type::Void);
+            if !accepts(return_type, &expr_type) && !is_error(&value_node) {
                 self.errors.push(SemanticError::ReturnTypeMismatch(
                     return_type.to_string(),
                     expr_type.to_string(),
-                    line,
-                    column,
+                    return_stmt.value.span(),
                 ));
             }
         } else {
             self.errors
-                .push(SemanticError::ReturnOutsideFunction(line, column));
-        }
-        AnnotatedNode {
-            node_type: "ReturnStatement".to_string(),
-            children: vec![value_node],
-            ..Default::default()
+                .push(SemanticError::ReturnOutsideFunction(return_stmt.span));
         }
+        with_span(
+            AnnotatedNode {
+                node_type: "ReturnStatement".to_string(),
+                children: vec![value_node],
+                ..Default::default()
+            },
+            return_stmt.span,
+        )
     }
 
     fn analyze_block(&mut self, block: &Block) -> AnnotatedNode {
@@ -505,29 +963,43 @@ impl SemanticAnalyzer {
             .map(|d| self.analyze_declaration(d))
             .collect();
         self.symbol_table.leave_scope();
-        AnnotatedNode {
-            node_type: "Block".to_string(),
-            children,
-            ..Default::default()
-        }
+        with_span(
+            AnnotatedNode {
+                node_type: "Block".to_string(),
+                children,
+                ..Default::default()
+            },
+            block.span,
+        )
     }
 
     fn analyze_expression(&mut self, expression: &Expression) -> AnnotatedNode {
+        let node = self.annotate_expression(expression);
+        with_span(node, expression.span())
+    }
+
+    fn annotate_expression(&mut self, expression: &Expression) -> AnnotatedNode {
         match expression {
+            Expression::Range { start, end, inclusive, step, span } => {
+                // Los rangos no son valores: solo existen como lo que recorre un `for`.
+                self.errors.push(SemanticError::RangeOutsideFor(*span));
+                let mut node = self.analyze_range(start, end, *inclusive, step.as_deref());
+                node.inferred_type = ERROR_TYPE.to_string();
+                node
+            }
             Expression::Identifier(id) => {
                 let type_ = self.symbol_table.lookup(&id.name).map_or(Type::Void, |s| s.get_type());
                 if self.symbol_table.lookup(&id.name).is_none() {
                     self.errors.push(SemanticError::UndeclaredVariable(
                         id.name.clone(),
-                        id.line,
-                        id.column,
+                        id.span,
                     ));
                 }
                 let mut node = self.identifier_to_annotated(id);
                 node.inferred_type = type_.to_string();
                 node
             }
-            Expression::Literal(lit) => match lit {
+            Expression::Literal(lit, _) => match lit {
                 Literal::Int(v) => AnnotatedNode {
                     node_type: "IntLiteral".to_string(),
                     value: v.to_string(),
@@ -552,71 +1024,210 @@ impl SemanticAnalyzer {
                     inferred_type: "Bool".to_string(),
                     ..Default::default()
                 },
+                Literal::Char(v) => AnnotatedNode {
+                    node_type: "CharLiteral".to_string(),
+                    value: v.to_string(),
+                    inferred_type: "Char".to_string(),
+                    ..Default::default()
+                },
             },
-            Expression::Binary { left, op, right } => {
+            Expression::Binary { left, op, right, span } => {
                 let left_node = self.analyze_expression(left);
                 let right_node = self.analyze_expression(right);
-                let left_type = Type::from_str(&left_node.inferred_type).unwrap_or(Type::Void);
-                let right_type = Type::from_str(&right_node.inferred_type).unwrap_or(Type::Void);
+                let left_type = self.type_from_str(&left_node.inferred_type).unwrap_or(Type::Void);
+                let right_type = self.type_from_str(&right_node.inferred_type).unwrap_or(Type::Void);
+                let has_error = is_error(&left_node) || is_error(&right_node);
+                let allowed = match op {
+                    BinaryOp::Percent => Some(NUMERIC_TYPES),
+                    BinaryOp::Ampersand | BinaryOp::Bar | BinaryOp::Caret
+                    | BinaryOp::ShiftLeft | BinaryOp::ShiftRight => Some(INTEGER_TYPES),
+                    _ => None,
+                };
+                let valid_operand = match allowed {
+                    Some(allowed) => self.check_operand(op.symbol(), &left_node.inferred_type, allowed, left.span()),
+                    None => true,
+                };
 
-                if left_type != right_type {
+                if left_type != right_type && !has_error && valid_operand {
                     self.errors.push(SemanticError::TypeMismatch(
                         left_type.to_string(),
                         right_type.to_string(),
-                        0, // Add line/col info
-                        0,
+                        *span,
                     ));
                 }
 
+                // Las comparaciones producen Bool sea cual sea el tipo de sus operandos (ej. 'a' < c).
+                let result_type = match op {
+                    BinaryOp::Greater | BinaryOp::Less | BinaryOp::GreaterEqual
+                    | BinaryOp::LessEqual | BinaryOp::DoubleEqual | BinaryOp::NotEqual => Type::Bool,
+                    _ => left_type, // Simplification
+                };
+                let inferred_type = if has_error { ERROR_TYPE.to_string() } else { result_type.to_string() };
+
                 AnnotatedNode {
                     node_type: "BinaryExpression".to_string(),
                     value: format!("{:?}", op),
                     children: vec![left_node, right_node],
-                    inferred_type: left_type.to_string(), // Simplification
+                    inferred_type,
                     ..Default::default()
                 }
             }
-            Expression::Assignment { target, value } => {
-                let symbol_info = self.symbol_table.lookup(&target.name).map(|s| (s.is_constant(), s.get_type()));
+            Expression::Assignment { target, value, .. } => {
+                let (target_node, target_type) = self.analyze_assignment_target(target);
                 let value_node = self.analyze_expression(value);
-                let value_type = Type::from_str(&value_node.inferred_type).unwrap_or(Type::Void);
-
-                if let Some((is_constant, target_type)) = symbol_info {
-                    if is_constant {
-                        self.errors.push(SemanticError::InvalidAssignment(
-                            format!("Cannot assign to constant '{}'", target.name),
-                            target.line,
-                            target.column,
-                        ));
-                    } else if target_type != value_type {
-                        self.errors.push(SemanticError::TypeMismatch(
-                            target_type.to_string(),
-                            value_type.to_string(),
-                            target.line,
-                            target.column,
-                        ));
-                    }
-                } else {
-                    self.errors.push(SemanticError::UndeclaredVariable(
-                        target.name.clone(),
-                        target.line,
-                        target.column,
-                    ));
+                if let Some(target_type) = target_type {
+                    self.check_assignable(&target_type, &value_node, value);
                 }
 
                 AnnotatedNode {
                     node_type: "Assignment".to_string(),
-                    children: vec![self.identifier_to_annotated(target), value_node],
+                    children: vec![target_node, value_node],
+                    inferred_type: "Void".to_string(),
+                    ..Default::default()
+                }
+            }
+            Expression::CompoundAssignment { target, op, value, .. } => {
+                let (target_node, target_type) = self.analyze_assignment_target(target);
+                let value_node = self.analyze_expression(value);
+                let value_type = self.type_from_str(&value_node.inferred_type).unwrap_or(Type::Void);
+
+                // `a op= b` se tipa como `a = a op b`: los dos lados deben ser del mismo tipo numérico.
+                let numeric = match &target_type {
+                    Some(target_type) => self.check_operand(op.symbol(), &target_type.to_string(), NUMERIC_TYPES, target.span()),
+                    None => true,
+                };
+                if let Some(target_type) = target_type.filter(|t| numeric && *t != value_type && !is_error(&value_node)) {
+                    self.errors.push(SemanticError::TypeMismatch(
+                        target_type.to_string(),
+                        value_type.to_string(),
+                        value.span(),
+                    ));
+                }
+
+                AnnotatedNode {
+                    node_type: "CompoundAssignment".to_string(),
+                    value: op.symbol().to_string(),
+                    children: vec![target_node, value_node],
                     inferred_type: "Void".to_string(),
                     ..Default::default()
                 }
             }
-            Expression::FunctionCall { function, arguments } => {
+            Expression::StructInstantiation { name, fields, span } => {
+                let declared = match self.symbol_table.lookup(&name.name) {
+                    Some(Symbol::Struct { fields, .. }) => Some(fields.clone()),
+                    _ => None,
+                };
+
+                let mut children = vec![];
+                for (field, value) in fields {
+                    let value_node = self.analyze_expression(value);
+                    let value_type = self.type_from_str(&value_node.inferred_type).unwrap_or(Type::Void);
+                    match declared.as_ref().map(|declared| declared.get(&field.name)) {
+                        Some(None) => self.errors.push(SemanticError::FieldNotFound(
+                            name.name.clone(),
+                            field.name.clone(),
+                            field.span,
+                        )),
+                        Some(Some(field_type)) if !accepts(field_type, &value_type) && !is_error(&value_node) => {
+                            self.errors.push(SemanticError::TypeMismatch(
+                                field_type.to_string(),
+                                value_type.to_string(),
+                                value.span(),
+                            ))
+                        }
+                        _ => {}
+                    }
+                    children.push(with_span(
+                        AnnotatedNode {
+                            node_type: "FieldInitializer".to_string(),
+                            value: field.name.clone(),
+                            inferred_type: value_node.inferred_type.clone(),
+                            children: vec![value_node],
+                            ..Default::default()
+                        },
+                        field.span.to(value.span()),
+                    ));
+                }
+
+                let inferred_type = match declared {
+                    Some(declared) => {
+                        let mut missing: Vec<&String> = declared
+                            .keys()
+                            .filter(|field| !fields.iter().any(|(given, _)| given.name == **field))
+                            .collect();
+                        missing.sort();
+                        for field in missing {
+                            self.errors.push(SemanticError::MissingField(name.name.clone(), field.clone(), *span));
+                        }
+                        name.name.clone()
+                    }
+                    None => {
+                        self.errors.push(SemanticError::UndefinedStruct(name.name.clone(), name.span));
+                        ERROR_TYPE.to_string()
+                    }
+                };
+
+                AnnotatedNode {
+                    node_type: "StructInstantiation".to_string(),
+                    value: name.name.clone(),
+                    children,
+                    inferred_type,
+                    ..Default::default()
+                }
+            }
+            Expression::MemberAccess { object, property, .. } => {
+                let object_node = self.analyze_expression(object);
+                let inferred_type = if is_error(&object_node) {
+                    ERROR_TYPE.to_string()
+                } else {
+                    self.field_type(&object_node, object.span(), property)
+                };
+                AnnotatedNode {
+                    node_type: "MemberAccess".to_string(),
+                    value: property.name.clone(),
+                    children: vec![object_node],
+                    inferred_type,
+                    ..Default::default()
+                }
+            }
+            Expression::Array(elements, _) => {
+                let children: Vec<AnnotatedNode> = elements.iter().map(|e| self.analyze_expression(e)).collect();
+                // El primer elemento fija el tipo del arreglo; un arreglo vacío queda como `[Void]`.
+                let element_type = children
+                    .iter()
+                    .find(|node| !is_error(node))
+                    .map_or(Type::Void, |node| self.type_from_str(&node.inferred_type).unwrap_or(Type::Void));
+                for (element, node) in elements.iter().zip(&children) {
+                    let found = self.type_from_str(&node.inferred_type).unwrap_or(Type::Void);
+                    if found != element_type && !is_error(node) {
+                        self.errors.push(SemanticError::TypeMismatch(
+                            element_type.to_string(),
+                            found.to_string(),
+                            element.span(),
+                        ));
+                    }
+                }
+                AnnotatedNode {
+                    node_type: "ArrayLiteral".to_string(),
+                    children,
+                    inferred_type: Type::Array(Box::new(element_type)).to_string(),
+                    ..Default::default()
+                }
+            }
+            Expression::Index { object, index, .. } => {
+                let (children, element_type) = self.analyze_index(object, index);
+                AnnotatedNode {
+                    node_type: "Index".to_string(),
+                    children,
+                    inferred_type: element_type,
+                    ..Default::default()
+                }
+            }
+            Expression::FunctionCall { function, arguments, .. } => {
                 let fn_identifier = match &**function {
                     Expression::Identifier(ident) => ident,
                     _ => {
-                        let (line, col) = function.get_line_col();
-                        self.errors.push(SemanticError::InvalidFunctionCallTarget(line, col));
+                        self.errors.push(SemanticError::InvalidFunctionCallTarget(function.span()));
                         return AnnotatedNode {
                             node_type: "Error".to_string(),
                             value: "Invalid function call target".to_string(),
@@ -630,7 +1241,11 @@ impl SemanticAnalyzer {
                     arg_nodes.push(self.analyze_expression(arg));
                 }
 
-                let return_type = self.symbol_table.lookup(&fn_identifier.name).map_or(Type::Void, |s| s.get_type());
+                // Se puede llamar a una función declarada o a una variable de tipo función.
+                let return_type = match self.symbol_table.lookup(&fn_identifier.name).map(|s| s.get_type()) {
+                    Some(Type::Function(_, return_type)) => *return_type,
+                    callee_type => callee_type.unwrap_or(Type::Void),
+                };
 
                 AnnotatedNode {
                     node_type: "FunctionCall".to_string(),
@@ -640,20 +1255,27 @@ impl SemanticAnalyzer {
                     ..Default::default()
                 }
             }
-            Expression::Unary { op, expr } => {
+            Expression::Unary { op, expr, .. } => {
                 let expr_node = self.analyze_expression(expr);
-                let expr_type = Type::from_str(&expr_node.inferred_type).unwrap_or(Type::Void);
-                
+                if *op == UnaryOp::Tilde {
+                    self.check_operand("~", &expr_node.inferred_type, INTEGER_TYPES, expr.span());
+                }
+                let expr_type = if is_error(&expr_node) {
+                    ERROR_TYPE.to_string()
+                } else {
+                    self.type_from_str(&expr_node.inferred_type).unwrap_or(Type::Void).to_string()
+                };
+
                 // Unary operations preserve the type of their operand
                 AnnotatedNode {
                     node_type: "UnaryExpression".to_string(),
                     value: format!("{:?}", op),
                     children: vec![expr_node],
-                    inferred_type: expr_type.to_string(),
+                    inferred_type: expr_type,
                     ..Default::default()
                 }
             }
-            Expression::Grouped(expr) => {
+            Expression::Grouped(expr, _) => {
                 // Grouped expressions just preserve the type of the inner expression
                 let inner = self.analyze_expression(expr);
                 AnnotatedNode {
@@ -663,6 +1285,134 @@ impl SemanticAnalyzer {
                     ..Default::default()
                 }
             }
+            Expression::InterpolatedString(parts, _) => {
+                let children = parts
+                    .iter()
+                    .map(|part| match part {
+                        InterpolationPart::Text(text) => AnnotatedNode {
+                            node_type: "StringLiteral".to_string(),
+                            value: text.clone(),
+                            inferred_type: "String".to_string(),
+                            ..Default::default()
+                        },
+                        InterpolationPart::Expression(expr) => {
+                            let node = self.analyze_expression(expr);
+                            // Solo los valores escalares y las cadenas tienen una representación textual.
+                            let part_type = self.type_from_str(&node.inferred_type).unwrap_or(Type::Void);
+                            let printable = matches!(part_type, Type::Int | Type::Float | Type::String | Type::Bool | Type::Char);
+                            if !printable && !is_error(&node) {
+                                self.errors.push(SemanticError::InvalidInterpolation(
+                                    part_type.to_string(),
+                                    expr.span(),
+                                ));
+                            }
+                            node
+                        }
+                    })
+                    .collect();
+                AnnotatedNode {
+                    node_type: "InterpolatedString".to_string(),
+                    children,
+                    inferred_type: "String".to_string(),
+                    ..Default::default()
+                }
+            }
+            Expression::If { condition, then_branch, else_branch, .. } => {
+                let condition_node = self.analyze_condition(condition);
+                let then_node = self.analyze_expression(then_branch);
+                let else_node = self.analyze_expression(else_branch);
+
+                // Las dos ramas deben dar un valor del mismo tipo, que es el de toda la expresión.
+                let inferred_type = if is_error(&then_node) {
+                    else_node.inferred_type.clone()
+                } else if is_error(&else_node) || then_node.inferred_type == else_node.inferred_type {
+                    then_node.inferred_type.clone()
+                } else {
+                    self.errors.push(SemanticError::TypeMismatch(
+                        then_node.inferred_type.clone(),
+                        else_node.inferred_type.clone(),
+                        else_branch.span(),
+                    ));
+                    ERROR_TYPE.to_string()
+                };
+
+                AnnotatedNode {
+                    node_type: "IfExpression".to_string(),
+                    children: vec![condition_node, then_node, else_node],
+                    inferred_type,
+                    ..Default::default()
+                }
+            }
+            Expression::Block { block, value } => {
+                // El valor se analiza dentro del ámbito del bloque, donde se ven sus variables.
+                self.symbol_table.enter_scope("block".to_string());
+                let mut children: Vec<AnnotatedNode> = block
+                    .statements
+                    .iter()
+                    .map(|d| self.analyze_declaration(d))
+                    .collect();
+                let value_node = self.analyze_expression(value);
+                self.symbol_table.leave_scope();
+                let inferred_type = value_node.inferred_type.clone();
+                children.push(value_node);
+                AnnotatedNode {
+                    node_type: "BlockExpression".to_string(),
+                    children,
+                    inferred_type,
+                    ..Default::default()
+                }
+            }
+            Expression::EnumVariant { enum_name, variant, arguments, span } => {
+                let children: Vec<AnnotatedNode> = arguments.iter().map(|a| self.analyze_expression(a)).collect();
+                let inferred_type = match self.variant_payload(enum_name, variant) {
+                    Some(payload) if payload.len() != arguments.len() => {
+                        self.errors.push(SemanticError::PayloadCountMismatch(
+                            format!("{}::{}", enum_name.name, variant.name),
+                            payload.len(),
+                            arguments.len(),
+                            *span,
+                        ));
+                        enum_name.name.clone()
+                    }
+                    Some(payload) => {
+                        for ((argument, node), expected) in arguments.iter().zip(&children).zip(&payload) {
+                            self.check_assignable(expected, node, argument);
+                        }
+                        enum_name.name.clone()
+                    }
+                    None => ERROR_TYPE.to_string(),
+                };
+                AnnotatedNode {
+                    node_type: "EnumVariant".to_string(),
+                    value: format!("{}::{}", enum_name.name, variant.name),
+                    children,
+                    inferred_type,
+                    ..Default::default()
+                }
+            }
+            Expression::Is { value, enum_name, variant, .. } => {
+                let value_node = self.analyze_expression(value);
+                let declared = self.variant_payload(enum_name, variant).is_some();
+                if declared && value_node.inferred_type != enum_name.name && !is_error(&value_node) {
+                    self.errors.push(SemanticError::TypeMismatch(
+                        enum_name.name.clone(),
+                        value_node.inferred_type.clone(),
+                        value.span(),
+                    ));
+                }
+                AnnotatedNode {
+                    node_type: "IsExpression".to_string(),
+                    value: format!("{}::{}", enum_name.name, variant.name),
+                    children: vec![value_node],
+                    inferred_type: Type::Bool.to_string(),
+                    ..Default::default()
+                }
+            }
+            Expression::Error(_) => AnnotatedNode {
+                node_type: "Error".to_string(),
+                inferred_type: ERROR_TYPE.to_string(),
+                ..Default::default()
+            },
             _ => AnnotatedNode {
                 node_type: "UnsupportedExpression".to_string(),
                 value: format!("{:?}", expression),
@@ -672,19 +1422,343 @@ impl SemanticAnalyzer {
         }
     }
 
-    fn get_type(&self, opt_type: &Option<Type>) -> Type {
-        opt_type.clone().unwrap_or(Type::Void)
+    /// Analiza el destino de una asignación. Devuelve su nodo y el tipo que debe
+    /// tener el valor, o `None` si ya se reportó un error sobre el destino.
+    fn analyze_assignment_target(&mut self, target: &Expression) -> (AnnotatedNode, Option<Type>) {
+        if let Expression::Identifier(id) = target {
+            let symbol_info = self.symbol_table.lookup(&id.name).map(|s| (s.is_constant(), s.get_type()));
+            let node = self.identifier_to_annotated(id);
+            return match symbol_info {
+                Some((false, target_type)) => (node, Some(target_type)),
+                Some((true, _)) => {
+                    self.errors.push(SemanticError::InvalidAssignment(
+                        format!("Cannot assign to constant '{}'", id.name),
+                        id.span,
+                    ));
+                    (node, None)
+                }
+                None => {
+                    self.errors.push(SemanticError::UndeclaredVariable(id.name.clone(), id.span));
+                    (node, None)
+                }
+            };
+        }
+
+        // Un elemento o un campo se analiza como cualquier otra expresión, pero
+        // además no puede formar parte de una constante.
+        let node = self.analyze_expression(target);
+        let constant = root_identifier(target)
+            .filter(|root| self.symbol_table.lookup(&root.name).is_some_and(|s| s.is_constant()));
+        if let Some(root) = constant {
+            let part = if matches!(target, Expression::Index { .. }) { "an element" } else { "a field" };
+            self.errors.push(SemanticError::InvalidAssignment(
+                format!("Cannot assign to {} of constant '{}'", part, root.name),
+                root.span,
+            ));
+            return (node, None);
+        }
+        let target_type = if is_error(&node) { None } else { self.type_from_str(&node.inferred_type) };
+        (node, target_type)
+    }
+
+    /// Reporta el operando si el operador `op` no se puede aplicar a su tipo,
+    /// que debe ser uno de `allowed`. Devuelve si el operando es válido.
+    fn check_operand(&mut self, op: &str, operand_type: &str, allowed: &[Type], span: Span) -> bool {
+        if operand_type == ERROR_TYPE || self.type_from_str(operand_type).is_some_and(|t| allowed.contains(&t)) {
+            return true;
+        }
+        self.errors.push(SemanticError::InvalidOperand(op.to_string(), operand_type.to_string(), span));
+        false
+    }
+
+    /// Tipo del campo `property` del struct al que pertenece `object_node`.
+    fn field_type(&mut self, object_node: &AnnotatedNode, object_span: Span, property: &Identifier) -> String {
+        let Some(Type::Struct(struct_name)) = self.type_from_str(&object_node.inferred_type) else {
+            self.errors.push(SemanticError::InvalidMemberAccess(
+                format!("value of type '{}' has no fields", object_node.inferred_type),
+                object_span,
+            ));
+            return ERROR_TYPE.to_string();
+        };
+        let field_type = match self.symbol_table.lookup(&struct_name) {
+            Some(Symbol::Struct { fields, .. }) => fields.get(&property.name).cloned(),
+            // El struct no existe: ya se reportó donde se creó el valor.
+            _ => return ERROR_TYPE.to_string(),
+        };
+        match field_type {
+            Some(field_type) => field_type.to_string(),
+            None => {
+                self.errors.push(SemanticError::FieldNotFound(struct_name, property.name.clone(), property.span));
+                ERROR_TYPE.to_string()
+            }
+        }
+    }
+
+    /// Analiza `object[index]`: el objeto debe ser un arreglo y el índice un `Int`.
+    /// Devuelve los nodos de ambos y el tipo del elemento (`Error` si no se puede saber).
+    fn analyze_index(&mut self, object: &Expression, index: &Expression) -> (Vec<AnnotatedNode>, String) {
+        let object_node = self.analyze_expression(object);
+        let index_node = self.analyze_expression(index);
+
+        let index_type = self.type_from_str(&index_node.inferred_type).unwrap_or(Type::Void);
+        if index_type != Type::Int && !is_error(&index_node) {
+            self.errors.push(SemanticError::TypeMismatch(
+                Type::Int.to_string(),
+                index_type.to_string(),
+                index.span(),
+            ));
+        }
+
+        let element_type = match self.type_from_str(&object_node.inferred_type) {
+            Some(Type::Array(element) | Type::FixedArray(element, _)) => element.to_string(),
+            _ if is_error(&object_node) => ERROR_TYPE.to_string(),
+            object_type => {
+                self.errors.push(SemanticError::InvalidIndexTarget(
+                    object_type.unwrap_or(Type::Void).to_string(),
+                    object.span(),
+                ));
+                ERROR_TYPE.to_string()
+            }
+        };
+        (vec![object_node, index_node], element_type)
+    }
+
+    /// Tipos de la carga útil de `Enum::Variante`. Si el enum o la variante no
+    /// existen, lo reporta y devuelve `None`.
+    fn variant_payload(&mut self, enum_name: &Identifier, variant: &Identifier) -> Option<Vec<Type>> {
+        let Some(Symbol::Enum { variants, .. }) = self.symbol_table.lookup(&enum_name.name) else {
+            self.errors.push(SemanticError::UndefinedEnum(enum_name.name.clone(), enum_name.span));
+            return None;
+        };
+        match variants.iter().find(|(name, _)| *name == variant.name) {
+            Some((_, payload)) => Some(payload.clone()),
+            None => {
+                self.errors.push(SemanticError::UndefinedVariant(
+                    enum_name.name.clone(),
+                    variant.name.clone(),
+                    variant.span,
+                ));
+                None
+            }
+        }
+    }
+
+    /// Tipo escrito como en `Type::to_string`. `Type::from_str` lee cualquier
+    /// nombre como un struct; aquí se reconocen los que son enums declarados.
+    fn type_from_str(&self, s: &str) -> Option<Type> {
+        Type::from_str(s).map(|type_| self.resolve_type(type_))
+    }
+
+    fn resolve_type(&self, type_: Type) -> Type {
+        match type_ {
+            Type::Array(element) => Type::Array(Box::new(self.resolve_type(*element))),
+            Type::FixedArray(element, length) => Type::FixedArray(Box::new(self.resolve_type(*element)), length),
+            Type::Optional(inner) => Type::Optional(Box::new(self.resolve_type(*inner))),
+            Type::Function(parameters, return_type) => Type::Function(
+                parameters.into_iter().map(|p| self.resolve_type(p)).collect(),
+                Box::new(self.resolve_type(*return_type)),
+            ),
+            Type::Struct(name) if matches!(self.symbol_table.lookup(&name), Some(Symbol::Enum { .. })) => Type::Enum(name),
+            type_ => type_,
+        }
+    }
+
+    /// Resuelve un tipo escrito en el código y reporta en `span` el primer nombre
+    /// que no es ni un struct ni un enum declarados.
+    fn check_type(&mut self, type_: &Type, span: Span) -> Type {
+        let type_ = self.resolve_type(type_.clone());
+        if let Some(name) = self.undefined_type_name(&type_) {
+            self.errors.push(SemanticError::UndefinedType(name, span));
+        }
+        type_
+    }
+
+    fn undefined_type_name(&self, type_: &Type) -> Option<String> {
+        match type_ {
+            Type::Array(inner) | Type::FixedArray(inner, _) | Type::Optional(inner) => self.undefined_type_name(inner),
+            Type::Function(parameters, return_type) => parameters
+                .iter()
+                .chain(std::iter::once(&**return_type))
+                .find_map(|t| self.undefined_type_name(t)),
+            Type::Struct(name) if !matches!(self.symbol_table.lookup(name), Some(Symbol::Struct { .. })) => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    fn get_type(&mut self, opt_type: &Option<Type>, span: Span) -> Type {
+        match opt_type {
+            Some(type_) => self.check_type(type_, span),
+            None => Type::Void,
+        }
+    }
+
+    /// Reporta un `TypeMismatch` si el valor de `value` no se puede guardar donde se espera `expected`.
+    fn check_assignable(&mut self, expected: &Type, value_node: &AnnotatedNode, value: &Expression) {
+        if is_error(value_node) {
+            return;
+        }
+        let found = self.type_from_str(&value_node.inferred_type).unwrap_or(Type::Void);
+        // Un arreglo literal tiene la longitud de sus elementos.
+        let found = match (expected, found, value) {
+            (Type::FixedArray(..), Type::Array(element), Expression::Array(elements, _)) => Type::FixedArray(element, elements.len()),
+            (_, found, _) => found,
+        };
+        if !accepts(expected, &found) {
+            self.errors.push(SemanticError::TypeMismatch(expected.to_string(), found.to_string(), value.span()));
+        }
     }
 
     fn identifier_to_annotated(&self, id: &Identifier) -> AnnotatedNode {
-        AnnotatedNode {
-            node_type: "Identifier".to_string(),
-            value: id.name.clone(),
-            start_line: id.line as u32,
-            start_column: id.column as u32,
-            end_line: id.line as u32,
-            end_column: (id.column + id.name.len()) as u32,
-            ..Default::default()
+        with_span(
+            AnnotatedNode {
+                node_type: "Identifier".to_string(),
+                value: id.name.clone(),
+                ..Default::default()
+            },
+            id.span,
+        )
+    }
+}
+
+/// Si un valor de tipo `found` se puede guardar donde se espera `expected`: un valor sirve
+/// como opcional de su tipo y un arreglo de longitud fija como arreglo sin longitud.
+fn accepts(expected: &Type, found: &Type) -> bool {
+    match (expected, found) {
+        _ if expected == found => true,
+        (Type::Optional(inner), _) => accepts(inner, found),
+        (Type::Array(element), Type::FixedArray(found_element, _)) => element == found_element,
+        _ => false,
+    }
+}
+
+/// Variable sobre la que opera una cadena de accesos como `a[i].x`, si la hay.
+fn root_identifier(expr: &Expression) -> Option<&Identifier> {
+    match expr {
+        Expression::Identifier(id) => Some(id),
+        Expression::Index { object, .. }
+        | Expression::MemberAccess { object, .. }
+        | Expression::Grouped(object, _) => root_identifier(object),
+        _ => None,
+    }
+}
+
+/// Valores que ya cubren los brazos sin guarda de un `match`.
+#[derive(Default)]
+struct MatchCoverage {
+    everything: bool,           // Ya hubo un brazo que encaja con cualquier valor
+    intervals: Vec<(i64, i64)>, // Enteros, caracteres y booleanos cubiertos, como intervalos cerrados
+    literals: Vec<Literal>,     // Flotantes y cadenas cubiertos
+    variants: Vec<String>,      // Variantes de enum cubiertas con cualquier carga útil
+}
+
+impl MatchCoverage {
+    /// Si todos los valores con los que encaja `pattern` ya están cubiertos.
+    fn covers(&self, pattern: &Pattern) -> bool {
+        if self.everything {
+            return true;
+        }
+        match (pattern_interval(pattern), pattern) {
+            (Some((low, high)), _) => self.covers_interval(low, high),
+            (None, Pattern::Literal(literal, _)) => self.literals.contains(literal),
+            (None, Pattern::Variant { variant, .. }) => self.variants.contains(&variant.name),
+            _ => false,
         }
     }
+
+    fn add(&mut self, pattern: &Pattern) {
+        if is_irrefutable(pattern) {
+            self.everything = true;
+        } else if let Some(interval) = pattern_interval(pattern) {
+            self.intervals.push(interval);
+        } else if let Pattern::Literal(literal, _) = pattern {
+            self.literals.push(literal.clone());
+        } else if let Some(variant) = pattern_variant(pattern) {
+            self.variants.push(variant.to_string());
+        }
+    }
+
+    fn covers_interval(&self, low: i64, high: i64) -> bool {
+        let mut next = low;
+        loop {
+            // De los intervalos que contienen a `next`, el que llega más lejos.
+            let reach = self.intervals.iter()
+                .filter(|(start, end)| *start <= next && next <= *end)
+                .map(|(_, end)| *end)
+                .max();
+            match reach {
+                Some(end) if end >= high => return true,
+                Some(end) => next = end + 1,
+                None => return false,
+            }
+        }
+    }
+
+    /// Si ya no queda ningún valor de tipo `subject_type` sin cubrir. Solo los
+    /// booleanos, enteros, caracteres y enums se pueden cubrir sin un brazo que
+    /// encaje con todo; de un enum hay que dar sus `variants`.
+    fn is_exhaustive(&self, subject_type: &Type, variants: &[String]) -> bool {
+        self.everything || match subject_type {
+            Type::Enum(_) => variants.iter().all(|variant| self.variants.contains(variant)),
+            Type::Bool => self.covers_interval(0, 1),
+            Type::Int => self.covers_interval(i64::MIN, i64::MAX),
+            Type::Char => self.covers_interval(0, 0x7F), // Los caracteres son ASCII
+            _ => false,
+        }
+    }
+}
+
+/// Valores de un patrón literal o de rango como intervalo cerrado, si son enteros,
+/// caracteres o booleanos. Un rango vacío (`5..5`) no tiene intervalo.
+fn pattern_interval(pattern: &Pattern) -> Option<(i64, i64)> {
+    let ordinal = |literal: &Literal| match literal {
+        Literal::Int(value) => Some(*value),
+        Literal::Char(value) => Some(*value as i64),
+        Literal::Bool(value) => Some(*value as i64),
+        Literal::Float(_) | Literal::String(_) => None,
+    };
+    match pattern {
+        Pattern::Literal(literal, _) => ordinal(literal).map(|value| (value, value)),
+        Pattern::Range { start, end, inclusive, .. } => {
+            let (low, high) = (ordinal(start)?, ordinal(end)?);
+            let high = if *inclusive { high } else { high.checked_sub(1)? };
+            (low <= high).then_some((low, high))
+        }
+        _ => None,
+    }
+}
+
+/// Variante de un patrón que encaja con ella sea cual sea su carga útil.
+fn pattern_variant(pattern: &Pattern) -> Option<&str> {
+    match pattern {
+        Pattern::Variant { variant, payload, .. } if payload.iter().all(is_irrefutable) => Some(&variant.name),
+        _ => None,
+    }
+}
+
+/// Patrones que encajan con cualquier valor de su tipo.
+fn is_irrefutable(pattern: &Pattern) -> bool {
+    match pattern {
+        Pattern::Wildcard(_) | Pattern::Binding(_) => true,
+        Pattern::Struct { fields, .. } => fields.iter().all(|field| is_irrefutable(&field.pattern)),
+        Pattern::Literal(..) | Pattern::Range { .. } | Pattern::Variant { .. } | Pattern::Error(_) => false,
+    }
+}
+
+/// Si el patrón tiene partes que el parser no pudo analizar.
+fn contains_error(pattern: &Pattern) -> bool {
+    match pattern {
+        Pattern::Error(_) => true,
+        Pattern::Struct { fields, .. } => fields.iter().any(|field| contains_error(&field.pattern)),
+        Pattern::Variant { payload, .. } => payload.iter().any(contains_error),
+        _ => false,
+    }
+}
+
+/// Copia en el nodo anotado la ubicación del fragmento de código del que proviene.
+fn with_span(mut node: AnnotatedNode, span: Span) -> AnnotatedNode {
+    node.start_line = span.start.line as u32;
+    node.start_column = span.start.column as u32;
+    node.end_line = span.end.line as u32;
+    node.end_column = span.end.column as u32;
+    node
 }
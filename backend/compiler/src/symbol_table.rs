@@ -10,6 +10,7 @@ pub enum Symbol {
         line: usize,
         column: usize,
         value: Option<Literal>,
+        doc: Option<String>,
     },
     Function {
         name: String,
@@ -17,12 +18,21 @@ pub enum Symbol {
         return_type: Type,
         line: usize,
         column: usize,
+        doc: Option<String>,
     },
     Struct {
         name: String,
         fields: HashMap<String, Type>,
         line: usize,
         column: usize,
+        doc: Option<String>,
+    },
+    Enum {
+        name: String,
+        variants: Vec<(String, Vec<Type>)>, // Cada variante con los tipos de su carga útil, en orden de declaración
+        line: usize,
+        column: usize,
+        doc: Option<String>,
     },
     Constant {  // Add this variant
         name: String,
@@ -30,6 +40,7 @@ pub enum Symbol {
         line: usize,
         column: usize,
         value: Option<Literal>,
+        doc: Option<String>,
     },
 }
 
@@ -37,12 +48,23 @@ impl Symbol {
     pub fn get_type(&self) -> Type {
         match self {
             Symbol::Variable { type_, .. } => type_.clone(),
-            Symbol::Function { return_type, .. } => return_type.clone(),
-            Symbol::Struct { .. } => Type::Void, // Structs don't have a single type
+            Symbol::Function { parameters, return_type, .. } => Type::Function(parameters.clone(), Box::new(return_type.clone())),
+            Symbol::Struct { .. } | Symbol::Enum { .. } => Type::Void, // Structs don't have a single type
             Symbol::Constant { type_, .. } => type_.clone(), // Handle Constant type
         }
     }
 
+    /// Comentario de documentación de la declaración, si lo tiene.
+    pub fn doc(&self) -> Option<&str> {
+        match self {
+            Symbol::Variable { doc, .. }
+            | Symbol::Function { doc, .. }
+            | Symbol::Struct { doc, .. }
+            | Symbol::Enum { doc, .. }
+            | Symbol::Constant { doc, .. } => doc.as_deref(),
+        }
+    }
+
     pub fn is_constant(&self) -> bool {
         matches!(self, Symbol::Constant { .. })
     }
@@ -92,15 +114,23 @@ impl Scope {
     }
 }
 
+// Prefijo del nombre del ámbito de cada archivo en un programa de varios archivos.
+pub const MODULE_SCOPE_PREFIX: &str = "module: ";
+
+/// Tabla de símbolos. En un programa de varios archivos cada módulo tiene su
+/// ámbito, hijo del global, y ve sus propios símbolos y los de los módulos que
+/// importa directamente.
 #[derive(Debug, Clone)]
 pub struct SymbolTable {
     pub current_scope: Scope,
+    imports: Vec<String>, // Módulos que importa el módulo que se está analizando
 }
 
 impl SymbolTable {
     pub fn new() -> Self {
         SymbolTable {
             current_scope: Scope::new(None, "global".to_string()),
+            imports: Vec::new(),
         }
     }
 
@@ -116,12 +146,48 @@ impl SymbolTable {
         }
     }
 
+    pub fn enter_module(&mut self, module: &str, imports: Vec<String>) {
+        self.enter_scope(format!("{}{}", MODULE_SCOPE_PREFIX, module));
+        self.imports = imports;
+    }
+
+    pub fn leave_module(&mut self) {
+        self.leave_scope();
+        self.imports.clear();
+    }
+
+    /// Verdadero en el ámbito global o en el de un módulo, fuera de toda función y bloque.
+    pub fn at_top_level(&self) -> bool {
+        self.current_scope.level == 0 || self.current_scope.name.starts_with(MODULE_SCOPE_PREFIX)
+    }
+
+    /// Inserta un símbolo en el ámbito actual. Todo el programa se compila a un
+    /// solo módulo LLVM, así que un nombre de nivel de módulo no puede repetirse
+    /// en otro archivo aunque ninguno de los dos importe al otro.
     pub fn insert(&mut self, name: String, symbol: Symbol) -> bool {
+        if self.current_scope.name.starts_with(MODULE_SCOPE_PREFIX)
+            && self.module_scopes().any(|scope| scope.symbols.contains_key(&name))
+        {
+            return false;
+        }
         self.current_scope.insert(name, symbol)
     }
 
     pub fn lookup(&self, name: &str) -> Option<&Symbol> {
-        self.current_scope.lookup(name)
+        self.current_scope.lookup(name).or_else(|| {
+            self.module_scopes()
+                .filter(|scope| self.imports.iter().any(|module| scope.name.strip_prefix(MODULE_SCOPE_PREFIX) == Some(module)))
+                .find_map(|scope| scope.symbols.get(name))
+        })
+    }
+
+    /// Ámbitos de los módulos ya analizados.
+    fn module_scopes(&self) -> impl Iterator<Item = &Scope> {
+        let mut root = &self.current_scope;
+        while let Some(parent) = root.parent.as_deref() {
+            root = parent;
+        }
+        root.children.iter().filter(|scope| scope.name.starts_with(MODULE_SCOPE_PREFIX))
     }
 
     pub fn get_root_scope(&self) -> Scope {
@@ -1,28 +1,58 @@
 use core::fmt;
+use std::borrow::Cow;
 
 // Se añade `Copy` para optimizar, ya que los enums son baratos de copiar.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TokenType {
     CommentSingle,    // Comentario de una sola línea (ej. // comentario)
     CommentMultiLine, // Comentario de múltiples líneas (ej. /* comentario */)
-    Keyword,          // Palabra reservada del lenguaje (ej. let, if, while)
+    DocComment,       // Comentario de documentación (ej. /// doc o /** doc */)
     Identifier,       // Nombre dado por el usuario a variables, funciones, etc.
     Integer,          // Número entero (ej. 10, 42)
     Float,            // Número de punto flotante (ej. 3.14, 0.5)
-    String,           // Cadena de caracteres (ej. "hola", 'mundo')
-    Boolean,          // Valor booleano (true, false)
-    
+    String,           // Cadena de caracteres (ej. "hola", r"C:\ruta", """varias líneas""")
+    Char,             // Carácter ASCII (ej. 'a', '\n'); el lexema es el carácter ya decodificado
+    InterpolatedString, // Cadena con expresiones incrustadas (ej. "nivel ${n}"); el lexema es el literal completo
+    Label,            // Etiqueta de bucle (ej. 'outer); el lexema es el nombre sin la comilla
+
+    // --- Palabras Reservadas ---
+    Fn,               // fn
+    Let,              // let
+    Const,            // const
+    Struct,           // struct
+    Enum,             // enum
+    Import,           // import
+    If,               // if
+    Else,             // else
+    While,            // while
+    Do,               // do
+    Until,            // until
+    For,              // for
+    In,               // in
+    Return,           // return
+    Break,            // break
+    Continue,         // continue
+    Match,            // match
+    Is,               // is
+    True,             // true
+    False,            // false
+
     // --- Operadores ---
     Plus,             // Operador de suma (+)
     Minus,            // Operador de resta (-)
     Asterisk,         // Operador de multiplicación (*)
     Slash,            // Operador de división (/)
+    Percent,          // Operador de módulo (%)
     Equal,            // Operador de asignación (=)
     Greater,          // Operador mayor que (>)
     Less,             // Operador menor que (<)
     Exclamation,      // Signo de exclamación (!)
     Ampersand,        // Ampersand (&) - Generalmente para operaciones a nivel de bit
     Bar,              // Barra vertical (|) - Generalmente para operaciones a nivel de bit
+    Caret,            // O exclusivo a nivel de bit (^)
+    Tilde,            // Negación a nivel de bit (~)
+    ShiftLeft,        // Desplazamiento a la izquierda (<<)
+    ShiftRight,       // Desplazamiento aritmético a la derecha (>>)
     DoubleEqual,      // Operador de igualdad (==)
     GreaterEqual,     // Operador mayor o igual que (>=)
     LessEqual,        // Operador menor o igual que (<=)
@@ -31,6 +61,11 @@ pub enum TokenType {
     DoubleBar,        // Operador lógico OR (||)
     Increment,        // Operador de incremento (++)
     Decrement,        // Operador de decremento (--)
+    PlusEqual,        // Asignación compuesta de suma (+=)
+    MinusEqual,       // Asignación compuesta de resta (-=)
+    AsteriskEqual,    // Asignación compuesta de multiplicación (*=)
+    SlashEqual,       // Asignación compuesta de división (/=)
+    PercentEqual,     // Asignación compuesta de módulo (%=)
     
     // --- Operadores Especiales (Nombres Corregidos) ---
     Splat,            // @*
@@ -40,6 +75,7 @@ pub enum TokenType {
 
     // --- Delimitadores y Puntuación ---
     ArrowRight,       // Flecha (->)
+    FatArrow,         // Flecha de los brazos de un `match` (=>)
     LeftParen,        // Paréntesis izquierdo (()
     RightParen,       // Paréntesis derecho ())
     LeftBrace,        // Llave izquierda ({)
@@ -49,7 +85,11 @@ pub enum TokenType {
     Comma,            // Coma (,)
     Semicolon,        // Punto y coma (;)
     Colon,            // Dos puntos (:)
+    DoubleColon,      // Separador entre un enum y su variante (::)
+    Question,         // Signo de interrogación del operador condicional (?)
     Dot,              // Punto (.)
+    DotDot,           // Rango exclusivo (..)
+    DotDotEqual,      // Rango inclusivo (..=)
     
     // --- Tokens Misceláneos ---
     Whitespace,       // Espacio en blanco, tabulación, etc.
@@ -59,20 +99,70 @@ pub enum TokenType {
 }
 
 impl TokenType {
+    /// Devuelve el tipo de token de una palabra reservada, o `None` si no lo es.
+    pub fn keyword(identifier: &str) -> Option<Self> {
+        match identifier {
+            "fn" => Some(TokenType::Fn),
+            "let" => Some(TokenType::Let),
+            "const" => Some(TokenType::Const),
+            "struct" => Some(TokenType::Struct),
+            "enum" => Some(TokenType::Enum),
+            "import" => Some(TokenType::Import),
+            "if" => Some(TokenType::If),
+            "else" => Some(TokenType::Else),
+            "while" => Some(TokenType::While),
+            "do" => Some(TokenType::Do),
+            "until" => Some(TokenType::Until),
+            "for" => Some(TokenType::For),
+            "in" => Some(TokenType::In),
+            "return" => Some(TokenType::Return),
+            "break" => Some(TokenType::Break),
+            "continue" => Some(TokenType::Continue),
+            "match" => Some(TokenType::Match),
+            "is" => Some(TokenType::Is),
+            "true" => Some(TokenType::True),
+            "false" => Some(TokenType::False),
+            _ => None,
+        }
+    }
+
     /// Convierte un string a un TokenType.
     /// Necesario para la comunicación con gRPC.
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
-            "Keyword" => Some(TokenType::Keyword),
+            "DocComment" => Some(TokenType::DocComment),
             "Identifier" => Some(TokenType::Identifier),
             "Integer" => Some(TokenType::Integer),
             "Float" => Some(TokenType::Float),
             "String" => Some(TokenType::String),
-            "Boolean" => Some(TokenType::Boolean),
+            "Char" => Some(TokenType::Char),
+            "InterpolatedString" => Some(TokenType::InterpolatedString),
+            "Label" => Some(TokenType::Label),
+            "Fn" => Some(TokenType::Fn),
+            "Let" => Some(TokenType::Let),
+            "Const" => Some(TokenType::Const),
+            "Struct" => Some(TokenType::Struct),
+            "Enum" => Some(TokenType::Enum),
+            "Import" => Some(TokenType::Import),
+            "If" => Some(TokenType::If),
+            "Else" => Some(TokenType::Else),
+            "While" => Some(TokenType::While),
+            "Do" => Some(TokenType::Do),
+            "Until" => Some(TokenType::Until),
+            "For" => Some(TokenType::For),
+            "In" => Some(TokenType::In),
+            "Return" => Some(TokenType::Return),
+            "Break" => Some(TokenType::Break),
+            "Continue" => Some(TokenType::Continue),
+            "Match" => Some(TokenType::Match),
+            "Is" => Some(TokenType::Is),
+            "True" => Some(TokenType::True),
+            "False" => Some(TokenType::False),
             "Plus" => Some(TokenType::Plus),
             "Minus" => Some(TokenType::Minus),
             "Asterisk" => Some(TokenType::Asterisk),
             "Slash" => Some(TokenType::Slash),
+            "Percent" => Some(TokenType::Percent),
             "Equal" => Some(TokenType::Equal),
             "DoubleEqual" => Some(TokenType::DoubleEqual),
             "NotEqual" => Some(TokenType::NotEqual),
@@ -83,12 +173,23 @@ impl TokenType {
             "DoubleAmpersand" => Some(TokenType::DoubleAmpersand),
             "DoubleBar" => Some(TokenType::DoubleBar),
             "Exclamation" => Some(TokenType::Exclamation),
+            "Ampersand" => Some(TokenType::Ampersand),
+            "Bar" => Some(TokenType::Bar),
+            "Caret" => Some(TokenType::Caret),
+            "Tilde" => Some(TokenType::Tilde),
+            "ShiftLeft" => Some(TokenType::ShiftLeft),
+            "ShiftRight" => Some(TokenType::ShiftRight),
             "Splat" => Some(TokenType::Splat),
             "Spread" => Some(TokenType::Spread),
             "Pipe" => Some(TokenType::Pipe),
             "Swap" => Some(TokenType::Swap),
             "Increment" => Some(TokenType::Increment),
             "Decrement" => Some(TokenType::Decrement),
+            "PlusEqual" => Some(TokenType::PlusEqual),
+            "MinusEqual" => Some(TokenType::MinusEqual),
+            "AsteriskEqual" => Some(TokenType::AsteriskEqual),
+            "SlashEqual" => Some(TokenType::SlashEqual),
+            "PercentEqual" => Some(TokenType::PercentEqual),
             "LeftParen" => Some(TokenType::LeftParen),
             "RightParen" => Some(TokenType::RightParen),
             "LeftBrace" => Some(TokenType::LeftBrace),
@@ -98,8 +199,13 @@ impl TokenType {
             "Comma" => Some(TokenType::Comma),
             "Semicolon" => Some(TokenType::Semicolon),
             "Colon" => Some(TokenType::Colon),
+            "DoubleColon" => Some(TokenType::DoubleColon),
+            "Question" => Some(TokenType::Question),
             "Dot" => Some(TokenType::Dot),
+            "DotDot" => Some(TokenType::DotDot),
+            "DotDotEqual" => Some(TokenType::DotDotEqual),
             "ArrowRight" => Some(TokenType::ArrowRight),
+            "FatArrow" => Some(TokenType::FatArrow),
             _ => None,
         }
     }
@@ -111,21 +217,133 @@ impl fmt::Display for TokenType {
     }
 }
 
+/// Unidad en que se cuentan las columnas. El lexer cuenta caracteres (escalares
+/// Unicode); los editores basados en JavaScript cuentan unidades UTF-16.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PositionEncoding {
+    Utf8,    // Bytes
+    Utf16,   // Unidades de código UTF-16
+    #[default]
+    Utf32,   // Caracteres
+}
+
+/// Posición dentro del código fuente.
+/// `line` y las columnas empiezan en 1; `offset` es el desplazamiento en bytes desde el inicio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,       // En caracteres
+    pub offset: usize,
+    pub utf8_column: usize,  // La misma columna, en bytes
+    pub utf16_column: usize, // La misma columna, en unidades UTF-16
+}
+
+impl Position {
+    /// Inicio del código fuente.
+    pub const START: Position = Position { line: 1, column: 1, offset: 0, utf8_column: 1, utf16_column: 1 };
+
+    pub fn column_in(&self, encoding: PositionEncoding) -> usize {
+        match encoding {
+            PositionEncoding::Utf8 => self.utf8_column,
+            PositionEncoding::Utf16 => self.utf16_column,
+            PositionEncoding::Utf32 => self.column,
+        }
+    }
+}
+
+/// Token producido por el lexer. El lexema toma prestado el texto fuente
+/// siempre que puede; solo las cadenas con secuencias de escape lo copian.
 #[derive(Debug, Clone, PartialEq)]
-pub struct LexerToken {
+pub struct Token<'src> {
     pub token_type: TokenType,
-    pub lexeme: String,
+    pub lexeme: Cow<'src, str>,
     pub line: usize,
     pub column: usize,
+    pub end_line: usize,   // Línea donde termina el token
+    pub end_column: usize, // Columna justo después del último carácter del token
+    pub start: usize,      // Desplazamiento en bytes del primer carácter
+    pub end: usize,        // Desplazamiento en bytes justo después del último carácter
+    pub utf8_column: usize,      // `column` contada en bytes
+    pub utf16_column: usize,     // `column` contada en unidades UTF-16
+    pub end_utf8_column: usize,  // `end_column` contada en bytes
+    pub end_utf16_column: usize, // `end_column` contada en unidades UTF-16
 }
 
-impl LexerToken {
-    pub fn new(token_type: TokenType, lexeme: String, line: usize, column: usize) -> Self {
+/// Token con el lexema en propiedad, independiente del texto fuente.
+pub type LexerToken = Token<'static>;
+
+impl<'src> Token<'src> {
+    pub fn new(token_type: TokenType, lexeme: impl Into<Cow<'src, str>>, start: Position, end: Position) -> Self {
         Self {
             token_type,
-            lexeme,
-            line,
-            column,
+            lexeme: lexeme.into(),
+            line: start.line,
+            column: start.column,
+            end_line: end.line,
+            end_column: end.column,
+            start: start.offset,
+            end: end.offset,
+            utf8_column: start.utf8_column,
+            utf16_column: start.utf16_column,
+            end_utf8_column: end.utf8_column,
+            end_utf16_column: end.utf16_column,
         }
     }
+
+    pub fn start_position(&self) -> Position {
+        Position {
+            line: self.line,
+            column: self.column,
+            offset: self.start,
+            utf8_column: self.utf8_column,
+            utf16_column: self.utf16_column,
+        }
+    }
+
+    pub fn end_position(&self) -> Position {
+        Position {
+            line: self.end_line,
+            column: self.end_column,
+            offset: self.end,
+            utf8_column: self.end_utf8_column,
+            utf16_column: self.end_utf16_column,
+        }
+    }
+
+    /// Copia el lexema para que el token sobreviva al texto fuente.
+    pub fn into_owned(self) -> LexerToken {
+        Token { lexeme: Cow::Owned(self.lexeme.into_owned()), ..self }
+    }
+
+    /// Vista prestada del token, sin copiar el lexema.
+    pub fn borrowed(&self) -> Token<'_> {
+        Token { lexeme: Cow::Borrowed(&self.lexeme), ..*self }
+    }
+}
+
+/// Convierte columnas contadas en caracteres a otra unidad. Sirve para las
+/// posiciones que solo guardan la columna en caracteres, como las del AST.
+pub struct LineIndex<'src> {
+    lines: Vec<&'src str>,
+}
+
+impl<'src> LineIndex<'src> {
+    pub fn new(source: &'src str) -> Self {
+        Self { lines: source.split('\n').collect() }
+    }
+
+    /// Columna `column` (en caracteres) de la línea `line`, en la unidad pedida.
+    /// Las columnas más allá del final de la línea avanzan de una en una y las
+    /// posiciones desconocidas (línea o columna 0) se devuelven sin cambios.
+    pub fn column(&self, line: usize, column: usize, encoding: PositionEncoding) -> usize {
+        let Some(text) = line.checked_sub(1).and_then(|i| self.lines.get(i)) else { return column };
+        let Some(before) = column.checked_sub(1) else { return column };
+        let width: usize = text.chars().take(before).map(|ch| match encoding {
+            PositionEncoding::Utf8 => ch.len_utf8(),
+            PositionEncoding::Utf16 => ch.len_utf16(),
+            PositionEncoding::Utf32 => 1,
+        }).sum();
+        let past_end = before.saturating_sub(text.chars().count());
+        1 + width + past_end
+    }
 }
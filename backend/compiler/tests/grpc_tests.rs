@@ -0,0 +1,135 @@
+// Pruebas de los servicios gRPC, llamados directamente sin levantar el servidor.
+
+use compiler::grpc_services::compiler::compiler_server::Compiler;
+use compiler::grpc_services::compiler::lexer_server::Lexer;
+use compiler::grpc_services::compiler::parser_server::Parser;
+use compiler::grpc_services::compiler::{
+    self as proto, AnalyzeRequest, AstNode, CompilerRequest, ParseSourceRequest, PositionEncoding, Scope, Symbol,
+};
+use compiler::grpc_services::{CompilerService, LexerService, ParserService};
+use tonic::Request;
+
+/// Busca un símbolo en el ámbito o en cualquiera de sus descendientes.
+fn find_symbol<'a>(scope: &'a Scope, name: &str) -> Option<&'a Symbol> {
+    scope.symbols.iter().find(|s| s.name == name)
+        .or_else(|| scope.children.iter().find_map(|child| find_symbol(child, name)))
+}
+
+/// Busca en profundidad el primer nodo del AST con el tipo indicado.
+fn find_node<'a>(node: &'a AstNode, node_type: &str) -> Option<&'a AstNode> {
+    if node.node_type == node_type {
+        return Some(node);
+    }
+    node.children.iter().find_map(|child| find_node(child, node_type))
+}
+
+const SOURCE: &str = "fn main() -> Int {\n    let s = \"😀\"; let n = y;\n    return 0;\n}\n";
+
+#[tokio::test]
+async fn test_lexer_reports_columns_in_requested_encoding() {
+    let columns = |encoding: PositionEncoding| async move {
+        let request = AnalyzeRequest { input: SOURCE.to_string(), position_encoding: encoding as i32 };
+        let tokens = LexerService::default().analyze(Request::new(request)).await.unwrap().into_inner().tokens;
+        let n = tokens.iter().find(|t| t.lexeme == "n").unwrap();
+        (n.column, n.end_column)
+    };
+
+    assert_eq!(columns(PositionEncoding::Utf32).await, (22, 23));
+    assert_eq!(columns(PositionEncoding::Utf16).await, (23, 24));
+    assert_eq!(columns(PositionEncoding::Utf8).await, (25, 26));
+}
+
+#[tokio::test]
+async fn test_diagnostics_use_requested_encoding() {
+    let request = CompilerRequest { source: SOURCE.to_string(), position_encoding: PositionEncoding::Utf16 as i32, ..Default::default() };
+    let response = CompilerService.compile(Request::new(request)).await.unwrap().into_inner();
+
+    let semantic = response.semantic_response.unwrap();
+    let undeclared = semantic.errors.iter().find(|e| e.message.contains('y')).unwrap();
+    assert_eq!((undeclared.line, undeclared.column), (2, 27));
+    assert_eq!((undeclared.end_line, undeclared.end_column), (2, 28));
+
+    let root = semantic.symbol_table.unwrap().root_scope.unwrap();
+    let n = find_symbol(&root, "n").unwrap();
+    assert_eq!((n.line, n.column), (2, 23));
+}
+
+#[tokio::test]
+async fn test_ast_nodes_carry_their_span() {
+    let request = ParseSourceRequest { source: SOURCE.to_string(), position_encoding: PositionEncoding::Utf16 as i32 };
+    let ast = ParserService.parse_source(Request::new(request)).await.unwrap().into_inner().ast.unwrap();
+
+    let function = find_node(&ast, "Function").unwrap();
+    assert_eq!((function.start_line, function.start_column, function.end_line, function.end_column), (1, 1, 4, 2));
+
+    // El emoji ocupa dos unidades UTF-16, así que el literal termina una columna más allá.
+    let literal = find_node(&ast, "StringLiteral").unwrap();
+    assert_eq!((literal.start_line, literal.start_column, literal.end_line, literal.end_column), (2, 13, 2, 17));
+
+    let ret = find_node(&ast, "Return").unwrap();
+    assert_eq!((ret.start_line, ret.start_column, ret.end_line, ret.end_column), (3, 5, 3, 14));
+}
+
+#[tokio::test]
+async fn test_syntax_errors_report_expected_and_found() {
+    let request = ParseSourceRequest { source: "let a = 1\nlet b = 2;".to_string(), position_encoding: PositionEncoding::Utf32 as i32 };
+    let response = ParserService.parse_source(Request::new(request)).await.unwrap().into_inner();
+
+    let [error] = response.errors.as_slice() else { panic!("se esperaba un único error: {:?}", response.errors) };
+    assert_eq!(error.error_type, "UnexpectedToken");
+    assert_eq!((error.expected.as_str(), error.found.as_str()), ("';' después de la declaración de la variable", "'let'"));
+    assert_eq!((error.line, error.column, error.end_line, error.end_column), (2, 1, 2, 4));
+}
+
+#[tokio::test]
+async fn test_symbols_carry_user_types() {
+    let source = "struct Point { x: Int }\nfn main() -> Int {\n    let p: Point? = Point { x = 1 };\n    let row: [Int; 4] = [1, 2, 3, 4];\n    let f = main;\n    return 0;\n}\n";
+    let request = CompilerRequest { source: source.to_string(), position_encoding: PositionEncoding::Utf32 as i32, ..Default::default() };
+    let semantic = CompilerService.compile(Request::new(request)).await.unwrap().into_inner().semantic_response.unwrap();
+    assert!(semantic.errors.is_empty(), "{:?}", semantic.errors);
+
+    let root = semantic.symbol_table.unwrap().root_scope.unwrap();
+    let data_type = |name: &str| find_symbol(&root, name).unwrap().data_type.clone();
+    assert_eq!(data_type("p"), "Point?");
+    assert_eq!(data_type("row"), "[Int; 4]");
+    assert_eq!(data_type("f"), "fn() -> Int");
+
+    // El mismo tipo llega también con su estructura.
+    let typed = |name: &str| find_symbol(&root, name).unwrap().r#type.clone().unwrap().kind.unwrap();
+    assert_eq!(typed("p"), proto::r#type::Kind::Optional(Box::new(proto::Type { kind: Some(proto::r#type::Kind::Named("Point".to_string())) })));
+    let proto::r#type::Kind::Function(function) = typed("main") else { panic!("se esperaba una función") };
+    assert!(function.parameters.is_empty());
+    assert!(find_symbol(&root, "Point").unwrap().r#type.is_none());
+    let proto::r#type::Kind::Array(array) = typed("row") else { panic!("se esperaba un arreglo") };
+    assert_eq!(array.length, Some(4));
+    assert_eq!(array.element.unwrap().kind, Some(proto::r#type::Kind::Primitive(proto::PrimitiveType::Int as i32)));
+}
+
+#[tokio::test]
+async fn test_compile_follows_imports() {
+    let request = CompilerRequest {
+        source: "import \"lib/math.dream\";\nimport \"lib/gone.dream\";\nfn main() -> Int { return twice(2); }\n".to_string(),
+        path: "app/main.dream".to_string(),
+        files: [(
+            "app/lib/math.dream".to_string(),
+            "fn twice(n: Int) -> Int {\n    let s = \"😀\"; let m = y;\n    return n * 2;\n}\n".to_string(),
+        )].into(),
+        position_encoding: PositionEncoding::Utf16 as i32,
+    };
+    let semantic = CompilerService.compile(Request::new(request)).await.unwrap().into_inner().semantic_response.unwrap();
+
+    let missing = &semantic.errors[0];
+    assert_eq!(missing.file, "");
+    assert_eq!((missing.line, missing.column), (2, 1));
+    assert!(missing.message.contains("app/lib/gone.dream"), "{}", missing.message);
+
+    // Las columnas del error se convierten con el texto del archivo importado.
+    let undeclared = semantic.errors.iter().find(|e| e.message.contains('y')).unwrap();
+    assert_eq!(undeclared.file, "app/lib/math.dream");
+    assert_eq!((undeclared.line, undeclared.column, undeclared.end_column), (2, 27, 28));
+
+    let root = semantic.symbol_table.unwrap().root_scope.unwrap();
+    let modules: Vec<&str> = root.children.iter().map(|s| s.scope_name.as_str()).collect();
+    assert_eq!(modules, vec!["module: app/lib/math.dream", "module: app/main.dream"]);
+    assert!(find_symbol(&root.children[0], "twice").is_some());
+}
@@ -0,0 +1,582 @@
+// Pruebas del analizador léxico: posiciones, rangos y diagnósticos de los tokens.
+
+use std::borrow::Cow;
+
+use compiler::incremental_lexer::{relex, RelexResult, TextEdit};
+use compiler::lexer::{interpolation_parts, LexError, LexErrorKind, LexicalAnalyzer, StringPart};
+use compiler::parser::{parse_source, parse_tokens};
+use compiler::token::{LexerToken, LineIndex, PositionEncoding, Token, TokenType};
+
+/// Escanea el código y descarta espacios y saltos de línea.
+fn lex(source: &str) -> Vec<LexerToken> {
+    LexicalAnalyzer::new(source)
+        .scan_tokens()
+        .into_iter()
+        .filter(|t| !matches!(t.token_type, TokenType::Whitespace | TokenType::NewLine))
+        .collect()
+}
+
+#[test]
+fn test_token_spans_single_line() {
+    let source = "let total = 42;";
+    let tokens = lex(source);
+
+    let total = &tokens[1];
+    assert_eq!(total.lexeme, "total");
+    assert_eq!((total.line, total.column), (1, 5));
+    assert_eq!((total.end_line, total.end_column), (1, 10));
+    assert_eq!(&source[total.start..total.end], "total");
+
+    let number = &tokens[3];
+    assert_eq!(&source[number.start..number.end], "42");
+    assert_eq!((number.column, number.end_column), (13, 15));
+}
+
+#[test]
+fn test_token_spans_multi_line() {
+    let source = "/* uno\ndos */ x";
+    let tokens = lex(source);
+
+    let comment = &tokens[0];
+    assert_eq!(comment.token_type, TokenType::CommentMultiLine);
+    assert_eq!((comment.line, comment.column), (1, 1));
+    assert_eq!((comment.end_line, comment.end_column), (2, 7));
+    assert_eq!(&source[comment.start..comment.end], "/* uno\ndos */");
+
+    let ident = &tokens[1];
+    assert_eq!((ident.line, ident.column), (2, 8));
+}
+
+#[test]
+fn test_token_spans_use_byte_offsets() {
+    let source = "\"ñandú\" + 1";
+    let tokens = lex(source);
+
+    let string = &tokens[0];
+    assert_eq!(string.start, 0);
+    assert_eq!(string.end, "\"ñandú\"".len());
+    assert_eq!(&source[tokens[1].start..tokens[1].end], "+");
+
+    let eof = tokens.last().unwrap();
+    assert_eq!(eof.token_type, TokenType::EndOfFile);
+    assert_eq!((eof.start, eof.end), (source.len(), source.len()));
+}
+
+/// Escanea el código y devuelve solo los errores léxicos.
+fn lex_errors(source: &str) -> Vec<LexError> {
+    let mut lexer = LexicalAnalyzer::new(source);
+    lexer.scan_tokens();
+    lexer.errors
+}
+
+#[test]
+fn test_unterminated_string_is_reported() {
+    let errors = lex_errors("let s = \"hola;\nlet x = 1;");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, LexErrorKind::UnterminatedString);
+    assert_eq!((errors[0].start.line, errors[0].start.column), (1, 9));
+    assert_eq!(errors[0].end.line, 1);
+}
+
+#[test]
+fn test_unterminated_comment_is_reported() {
+    let errors = lex_errors("let x = 1; /* sin cerrar\n");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, LexErrorKind::UnterminatedComment);
+    assert_eq!(errors[0].end.line, 2);
+}
+
+#[test]
+fn test_invalid_characters_are_reported() {
+    let errors = lex_errors("let a = 1 @ 2;\nlet b = #;");
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(kinds, vec![
+        LexErrorKind::InvalidCharacter('@'),
+        LexErrorKind::InvalidCharacter('#'),
+    ]);
+    assert_eq!((errors[1].start.line, errors[1].start.column), (2, 9));
+}
+
+#[test]
+fn test_invalid_escape_is_reported() {
+    let errors = lex_errors(r#"let s = "a\qb";"#);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, LexErrorKind::InvalidEscape("\\q".to_string()));
+    assert_eq!((errors[0].start.column, errors[0].end.column), (11, 13));
+}
+
+#[test]
+fn test_malformed_number_is_reported() {
+    let tokens = lex("let n = 12abc;");
+    assert_eq!(tokens[3].token_type, TokenType::Unknown);
+    assert_eq!(tokens[3].lexeme, "12abc");
+
+    let errors = lex_errors("let n = 12abc;");
+    assert_eq!(errors[0].kind, LexErrorKind::MalformedNumber("12abc".to_string()));
+}
+
+#[test]
+fn test_valid_source_has_no_lex_errors() {
+    let errors = lex_errors("fn main() -> Int {\n    let s = \"dice \\\"hola\\\"\\n\";\n    return 0;\n}");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn test_string_escapes_are_decoded() {
+    let tokens = lex(r#""a\tb\n\\ \"c\" \'d\' \0 \x41 \u{1F600}""#);
+    assert_eq!(tokens[0].token_type, TokenType::String);
+    assert_eq!(tokens[0].lexeme, "a\tb\n\\ \"c\" 'd' \0 A \u{1F600}");
+    assert_eq!(tokens.len(), 2); // Cadena + EOF: la comilla escapada no cierra la cadena
+}
+
+#[test]
+fn test_malformed_hex_and_unicode_escapes() {
+    let errors = lex_errors(r#""\x4" "\xFF" "\u{110000}" "\u41""#);
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(kinds, vec![
+        LexErrorKind::InvalidEscape("\\x4".to_string()),
+        LexErrorKind::InvalidEscape("\\xFF".to_string()),
+        LexErrorKind::InvalidEscape("\\u{110000}".to_string()),
+        LexErrorKind::InvalidEscape("\\u".to_string()),
+    ]);
+}
+
+#[test]
+fn test_extended_numeric_literals() {
+    let tokens = lex("0xFF_FF 0o17 0b1010 1_000_000 1.5e-3 2E10 3.0e+2 1.len");
+    let kinds: Vec<_> = tokens.iter().map(|t| (t.token_type, t.lexeme.as_ref())).collect();
+    assert_eq!(kinds, vec![
+        (TokenType::Integer, "0xFF_FF"),
+        (TokenType::Integer, "0o17"),
+        (TokenType::Integer, "0b1010"),
+        (TokenType::Integer, "1_000_000"),
+        (TokenType::Float, "1.5e-3"),
+        (TokenType::Float, "2E10"),
+        (TokenType::Float, "3.0e+2"),
+        (TokenType::Integer, "1"),
+        (TokenType::Dot, "."),
+        (TokenType::Identifier, "len"),
+        (TokenType::EndOfFile, ""),
+    ]);
+}
+
+#[test]
+fn test_malformed_radix_literals_are_reported() {
+    let errors = lex_errors("0x 0b102 0o8 1e 0xFG");
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(kinds, vec![
+        LexErrorKind::MalformedNumber("0x".to_string()),
+        LexErrorKind::MalformedNumber("0b102".to_string()),
+        LexErrorKind::MalformedNumber("0o8".to_string()),
+        LexErrorKind::MalformedNumber("1e".to_string()),
+        LexErrorKind::MalformedNumber("0xFG".to_string()),
+    ]);
+}
+
+#[test]
+fn test_separators_only_go_between_digits() {
+    let errors = lex_errors("1_ 1__0 0x_FF 1_e5 1.5_");
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(kinds, vec![
+        LexErrorKind::MalformedNumber("1_".to_string()),
+        LexErrorKind::MalformedNumber("1__0".to_string()),
+        LexErrorKind::MalformedNumber("0x_FF".to_string()),
+        LexErrorKind::MalformedNumber("1_e5".to_string()),
+        LexErrorKind::MalformedNumber("1.5_".to_string()),
+    ]);
+    assert!(lex_errors("0b1_0 1_0.2_5e1_0").is_empty());
+}
+
+/// Aplica la edición al texto, re-escanea de forma incremental y compara con un escaneo completo.
+fn assert_relex_matches(source: &str, range: std::ops::Range<usize>, text: &str) -> RelexResult {
+    let mut analyzer = LexicalAnalyzer::new(source);
+    let previous = analyzer.scan_tokens();
+    let mut edited = source.to_string();
+    edited.replace_range(range.clone(), text);
+
+    let result = relex(&edited, &previous, &analyzer.errors, &TextEdit { range, text: text.to_string() });
+    let mut full = LexicalAnalyzer::new(&edited);
+    assert_eq!(result.tokens, full.scan_tokens(), "edición sobre {:?}", source);
+    assert_eq!(result.errors, full.errors);
+    result
+}
+
+#[test]
+fn test_relex_matches_full_scan() {
+    let source = "fn main() -> Int {\n    let x = 1;\n    let s = \"hola\";\n    return x;\n}\n";
+    let x = source.find("x =").unwrap();
+    assert_relex_matches(source, x..x + 1, "total");            // Renombrar
+    assert_relex_matches(source, x + 1..x + 1, "yz");           // Extender un identificador
+    assert_relex_matches(source, x..x, "let y = 2;\n    ");     // Insertar líneas
+    assert_relex_matches(source, 0..source.len(), "");          // Borrar todo
+    assert_relex_matches("", 0..0, "let a = 1;");                // Documento vacío
+
+    let one = source.find("1;").unwrap();
+    assert_relex_matches(source, one + 1..one + 1, ".5e+3");    // Un entero pasa a flotante
+    let quote = source.find("\"hola").unwrap();
+    assert_relex_matches(source, quote..quote + 1, "");         // La cadena queda sin cerrar
+    assert_relex_matches(source, x..x, "/* ");                  // El comentario engulle el resto
+    assert_relex_matches(source, x..x, "é😀 + ");               // Caracteres de varios bytes
+
+    // Errores antes y después de la edición, en la misma línea y en otras.
+    let source = "let a = \"\\q\"; @\nlet b = 1; let c = \"\\w\";\nlet d = 'ab';\n";
+    let b = source.find("b =").unwrap();
+    let result = assert_relex_matches(source, b..b + 1, "bcd");
+    assert_eq!(result.errors.len(), 4);
+    assert_relex_matches(source, b..b, "x\n");                  // Desplaza las líneas siguientes
+    assert_relex_matches(source, b..b, "\\");                   // Un error nuevo entre los anteriores
+}
+
+#[test]
+fn test_relex_only_rescans_the_edited_region() {
+    let line = "let value = 1 + 2;\n";
+    let source = line.repeat(1000);
+    let edit_at = line.len() * 500 + 4; // `value` en la línea 501
+    let result = assert_relex_matches(&source, edit_at..edit_at + 5, "renamed");
+
+    assert!(result.changed.len() <= 5, "{:?}", result.changed);
+    let renamed = result.tokens.iter().find(|t| t.lexeme == "renamed").unwrap();
+    assert_eq!((renamed.line, renamed.column), (501, 5));
+    assert_eq!(result.replaced.start, result.changed.start);
+    assert!(result.replaced.len() <= 5, "{:?}", result.replaced);
+}
+
+#[test]
+fn test_streaming_lexer_borrows_lexemes() {
+    let source = "let nombre = \"sin escapes\"; let otro = \"con\\tescape\";";
+    let tokens: Vec<Token> = LexicalAnalyzer::new(source)
+        .filter(|t| t.token_type != TokenType::Whitespace)
+        .collect();
+
+    assert!(matches!(tokens[1].lexeme, Cow::Borrowed("nombre")));
+    assert!(matches!(tokens[3].lexeme, Cow::Borrowed("sin escapes")));
+    assert!(matches!(&tokens[8].lexeme, Cow::Owned(s) if s == "con\tescape"));
+    assert_eq!(tokens.last().unwrap().token_type, TokenType::EndOfFile);
+}
+
+#[test]
+fn test_streaming_lexer_matches_scan_tokens() {
+    let source = "fn main() -> Int {\n    /* doc **/ let ñ = 0x1F; // fin\n    return ñ;\n}";
+    let streamed: Vec<LexerToken> = LexicalAnalyzer::new(source).map(Token::into_owned).collect();
+    assert_eq!(streamed, LexicalAnalyzer::new(source).scan_tokens());
+
+    let comment = streamed.iter().find(|t| t.token_type == TokenType::CommentMultiLine).unwrap();
+    assert_eq!(comment.lexeme, " doc *");
+}
+
+#[test]
+fn test_parser_pulls_tokens_from_lexer() {
+    let source = "struct P { x: Int }\nfn main() -> Int {\n    let p = P { x = 1 };\n    return p.x;\n}";
+    let (streamed, lex_errors) = parse_source(source);
+    assert!(lex_errors.is_empty());
+    assert!(streamed.errors.is_empty(), "{:?}", streamed.errors);
+
+    let tokens = LexicalAnalyzer::new(source).scan_tokens();
+    let from_list = parse_tokens(&tokens);
+    assert_eq!(format!("{:?}", streamed.ast), format!("{:?}", from_list.ast));
+}
+
+#[test]
+fn test_each_keyword_has_its_own_token_type() {
+    let source = "fn let const struct if else while do until for in return true false fnord";
+    let kinds: Vec<_> = lex(source).iter().map(|t| t.token_type).collect();
+    assert_eq!(kinds, vec![
+        TokenType::Fn, TokenType::Let, TokenType::Const, TokenType::Struct,
+        TokenType::If, TokenType::Else, TokenType::While, TokenType::Do,
+        TokenType::Until, TokenType::For, TokenType::In, TokenType::Return,
+        TokenType::True, TokenType::False, TokenType::Identifier, TokenType::EndOfFile,
+    ]);
+
+    // El nombre que viaja por gRPC debe poder reconstruir el tipo.
+    for kind in &kinds[..14] {
+        assert_eq!(TokenType::from_str(&kind.to_string()), Some(*kind));
+    }
+}
+
+#[test]
+fn test_doc_comments_have_their_own_token_type() {
+    let source = "/// doc\n//// normal\n// normal\n/** bloque */ /**/ /*** normal */";
+    let tokens = lex(source);
+    let kinds: Vec<_> = tokens.iter().map(|t| (t.token_type, t.lexeme.as_ref())).collect();
+    assert_eq!(kinds, vec![
+        (TokenType::DocComment, " doc"),
+        (TokenType::CommentSingle, "// normal"),
+        (TokenType::CommentSingle, " normal"),
+        (TokenType::DocComment, " bloque "),
+        (TokenType::CommentMultiLine, ""),
+        (TokenType::CommentMultiLine, "** normal "),
+        (TokenType::EndOfFile, ""),
+    ]);
+}
+
+#[test]
+fn test_raw_and_multi_line_strings() {
+    let source = "r\"C:\\ruta\\${x}\" \"\"\"\nuno\n  \"dos\"\n\"\"\" x";
+    let mut lexer = LexicalAnalyzer::new(source);
+    let tokens: Vec<_> = lexer.by_ref().filter(|t| t.token_type != TokenType::Whitespace).collect();
+    assert!(lexer.errors.is_empty(), "{:?}", lexer.errors);
+
+    // Las cadenas crudas no interpretan escapes ni interpolaciones.
+    assert_eq!(tokens[0].token_type, TokenType::String);
+    assert_eq!(tokens[0].lexeme, "C:\\ruta\\${x}");
+    // El salto de línea tras las comillas de apertura no forma parte del contenido.
+    assert_eq!(tokens[1].token_type, TokenType::String);
+    assert_eq!(tokens[1].lexeme, "uno\n  \"dos\"\n");
+    assert_eq!((tokens[1].line, tokens[1].end_line), (1, 4));
+    assert_eq!((tokens[2].lexeme.as_ref(), tokens[2].line, tokens[2].column), ("x", 4, 5));
+}
+
+#[test]
+fn test_interpolated_string_is_one_token() {
+    let source = "\"score: ${level * 2} de ${max}\\n\" fin";
+    let tokens = lex(source);
+    let literal = &tokens[0];
+    assert_eq!(literal.token_type, TokenType::InterpolatedString);
+    assert_eq!(literal.lexeme, &source[literal.start..literal.end]);
+    assert_eq!(tokens[1].lexeme, "fin");
+
+    let parts = interpolation_parts(literal);
+    assert_eq!(parts.len(), 5);
+    assert_eq!(parts[0], StringPart::Text(Cow::Borrowed("score: ")));
+    let StringPart::Expression(level) = &parts[1] else { panic!("se esperaba una expresión") };
+    let level: Vec<_> = level.iter().filter(|t| t.token_type != TokenType::Whitespace).collect();
+    assert_eq!(level.iter().map(|t| t.token_type).collect::<Vec<_>>(),
+        vec![TokenType::Identifier, TokenType::Asterisk, TokenType::Integer]);
+    // Las posiciones de los tokens interiores son las del fuente original.
+    assert_eq!(&source[level[0].start..level[0].end], "level");
+    assert_eq!((level[0].line, level[0].column), (1, 11));
+    assert_eq!(parts[2], StringPart::Text(Cow::Borrowed(" de ")));
+    assert!(matches!(&parts[3], StringPart::Expression(max) if max[0].lexeme == "max"));
+    assert_eq!(parts[4], StringPart::Text(Cow::Owned("\n".to_string())));
+}
+
+#[test]
+fn test_nested_braces_and_strings_in_interpolation() {
+    let source = "\"${f(\"}\") + g({ a: 1 })} \\${literal}\"";
+    let mut lexer = LexicalAnalyzer::new(source);
+    let tokens = lexer.scan_tokens();
+    assert!(lexer.errors.is_empty(), "{:?}", lexer.errors);
+    assert_eq!(tokens.len(), 2);
+
+    let parts = interpolation_parts(&tokens[0]);
+    assert!(matches!(&parts[0], StringPart::Expression(tokens) if tokens.last().unwrap().lexeme == ")"));
+    assert_eq!(parts[1], StringPart::Text(Cow::Owned(" ${literal}".to_string())));
+}
+
+#[test]
+fn test_unterminated_interpolation_is_reported() {
+    let mut lexer = LexicalAnalyzer::new("\"a ${x + \nlet y = 1;");
+    let tokens = lexer.scan_tokens();
+    assert_eq!(lexer.errors.iter().map(|e| &e.kind).collect::<Vec<_>>(), vec![&LexErrorKind::UnterminatedString]);
+    // El escaneo continúa en la línea siguiente.
+    assert!(tokens.iter().any(|t| t.token_type == TokenType::Let && t.line == 2));
+}
+
+#[test]
+fn test_columns_in_every_encoding() {
+    let source = "let s = \"é😀\"; x\n😀 y";
+    let tokens = lex(source);
+
+    let string = &tokens[3];
+    assert_eq!(
+        (string.column, string.utf16_column, string.utf8_column),
+        (9, 9, 9),
+    );
+    // `é` ocupa dos bytes y una unidad UTF-16; `😀` cuatro bytes y dos unidades.
+    assert_eq!(
+        (string.end_column, string.end_utf16_column, string.end_utf8_column),
+        (13, 14, 17),
+    );
+    let x = &tokens[5];
+    assert_eq!(x.start_position().column_in(PositionEncoding::Utf32), 15);
+    assert_eq!(x.start_position().column_in(PositionEncoding::Utf16), 16);
+    assert_eq!(x.start_position().column_in(PositionEncoding::Utf8), 19);
+    assert_eq!(x.utf8_column - 1, x.start - source[..x.start].rfind('\n').map_or(0, |i| i + 1));
+
+    // Al cambiar de línea las columnas vuelven a empezar.
+    let y = &tokens[7];
+    assert_eq!((y.line, y.column, y.utf16_column, y.utf8_column), (2, 3, 4, 6));
+}
+
+#[test]
+fn test_line_index_converts_character_columns() {
+    let source = "let s = \"é😀\"; x\n😀 y";
+    let index = LineIndex::new(source);
+    for token in lex(source) {
+        if token.token_type == TokenType::EndOfFile {
+            continue;
+        }
+        for encoding in [PositionEncoding::Utf8, PositionEncoding::Utf16, PositionEncoding::Utf32] {
+            assert_eq!(
+                index.column(token.line, token.column, encoding),
+                token.start_position().column_in(encoding),
+                "{:?} en {:?}", token.lexeme, encoding,
+            );
+        }
+    }
+    // Las posiciones desconocidas no se alteran.
+    assert_eq!(index.column(0, 0, PositionEncoding::Utf16), 0);
+    assert_eq!(index.column(2, 10, PositionEncoding::Utf16), 11);
+}
+
+#[test]
+fn test_char_literals() {
+    let mut lexer = LexicalAnalyzer::new(r#"'a' '\n' '\'' '"' "it's""#);
+    let tokens: Vec<_> = lexer.by_ref().filter(|t| t.token_type != TokenType::Whitespace).collect();
+    assert!(lexer.errors.is_empty(), "{:?}", lexer.errors);
+    let kinds: Vec<_> = tokens.iter().map(|t| (t.token_type, t.lexeme.as_ref())).collect();
+    assert_eq!(kinds, vec![
+        (TokenType::Char, "a"),
+        (TokenType::Char, "\n"),
+        (TokenType::Char, "'"),
+        (TokenType::Char, "\""),
+        (TokenType::String, "it's"),
+        (TokenType::EndOfFile, ""),
+    ]);
+    assert!(matches!(tokens[0].lexeme, Cow::Borrowed(_)));
+}
+
+#[test]
+fn test_invalid_char_literals_are_reported() {
+    let mut lexer = LexicalAnalyzer::new("'ab' '' 'é' '\\q' 'a\nx");
+    let tokens = lexer.scan_tokens();
+    let kinds: Vec<_> = lexer.errors.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(kinds, vec![
+        LexErrorKind::InvalidCharLiteral("'ab'".to_string()),
+        LexErrorKind::InvalidCharLiteral("''".to_string()),
+        LexErrorKind::InvalidCharLiteral("'é'".to_string()),
+        LexErrorKind::InvalidEscape("\\q".to_string()),
+        LexErrorKind::UnterminatedChar,
+    ]);
+    assert!(tokens.iter().all(|t| t.token_type != TokenType::Char));
+    // Tras el literal sin cerrar el escaneo sigue en la línea siguiente.
+    assert!(tokens.iter().any(|t| t.lexeme == "x" && t.line == 2));
+}
+
+#[test]
+fn test_modulo_and_compound_assignment_operators() {
+    let tokens = lex("a %= b % c; a += 1; a -= 1; a *= 2; a /= 2; a++ -1 /2");
+    let kinds: Vec<_> = tokens.iter().map(|t| (t.token_type, t.lexeme.as_ref())).collect();
+    assert_eq!(&kinds[1..4], &[(TokenType::PercentEqual, "%="), (TokenType::Identifier, "b"), (TokenType::Percent, "%")]);
+    let operators: Vec<_> = kinds.iter().filter(|(_, lexeme)| lexeme.ends_with('=')).map(|(kind, _)| *kind).collect();
+    assert_eq!(operators, vec![
+        TokenType::PercentEqual, TokenType::PlusEqual, TokenType::MinusEqual,
+        TokenType::AsteriskEqual, TokenType::SlashEqual,
+    ]);
+    let tail: Vec<_> = kinds[kinds.len() - 6..].iter().map(|(kind, _)| *kind).collect();
+    assert_eq!(tail, vec![
+        TokenType::Increment, TokenType::Minus, TokenType::Integer,
+        TokenType::Slash, TokenType::Integer, TokenType::EndOfFile,
+    ]);
+}
+
+#[test]
+fn test_bitwise_operators() {
+    let tokens = lex("a & b | c ^ ~d << 2 >> 1 <= e <=> f && g || h |> i");
+    let operators: Vec<_> = tokens.iter()
+        .filter(|t| !matches!(t.token_type, TokenType::Identifier | TokenType::Integer | TokenType::EndOfFile))
+        .map(|t| t.token_type)
+        .collect();
+    assert_eq!(operators, vec![
+        TokenType::Ampersand, TokenType::Bar, TokenType::Caret, TokenType::Tilde,
+        TokenType::ShiftLeft, TokenType::ShiftRight, TokenType::LessEqual, TokenType::Swap,
+        TokenType::DoubleAmpersand, TokenType::DoubleBar, TokenType::Pipe,
+    ]);
+}
+
+#[test]
+fn test_loop_labels() {
+    let mut lexer = LexicalAnalyzer::new("'outer: while 'a' { break 'outer; continue '_b2 }");
+    let tokens: Vec<_> = lexer.by_ref().filter(|t| t.token_type != TokenType::Whitespace).collect();
+    assert!(lexer.errors.is_empty(), "{:?}", lexer.errors);
+    let kinds: Vec<_> = tokens.iter().map(|t| (t.token_type, t.lexeme.as_ref())).collect();
+    assert_eq!(kinds, vec![
+        (TokenType::Label, "outer"),
+        (TokenType::Colon, ":"),
+        (TokenType::While, "while"),
+        (TokenType::Char, "a"),
+        (TokenType::LeftBrace, "{"),
+        (TokenType::Break, "break"),
+        (TokenType::Label, "outer"),
+        (TokenType::Semicolon, ";"),
+        (TokenType::Continue, "continue"),
+        (TokenType::Label, "_b2"),
+        (TokenType::RightBrace, "}"),
+        (TokenType::EndOfFile, ""),
+    ]);
+    // El token de la etiqueta incluye la comilla.
+    assert_eq!((tokens[0].start_position().column, tokens[0].end_position().column), (1, 7));
+}
+
+#[test]
+fn test_range_operators() {
+    let tokens = lex("0..n 1..=10 1.5 a.b ...+");
+    let kinds: Vec<_> = tokens.iter().map(|t| (t.token_type, t.lexeme.as_ref())).collect();
+    assert_eq!(kinds, vec![
+        (TokenType::Integer, "0"),
+        (TokenType::DotDot, ".."),
+        (TokenType::Identifier, "n"),
+        (TokenType::Integer, "1"),
+        (TokenType::DotDotEqual, "..="),
+        (TokenType::Integer, "10"),
+        (TokenType::Float, "1.5"),
+        (TokenType::Identifier, "a"),
+        (TokenType::Dot, "."),
+        (TokenType::Identifier, "b"),
+        (TokenType::Spread, "...+"),
+        (TokenType::EndOfFile, ""),
+    ]);
+}
+
+#[test]
+fn test_fat_arrow() {
+    let tokens = lex("match x { _ => y == z }");
+    let kinds: Vec<_> = tokens.iter().map(|t| t.token_type).collect();
+    assert_eq!(kinds, vec![
+        TokenType::Match,
+        TokenType::Identifier,
+        TokenType::LeftBrace,
+        TokenType::Identifier,
+        TokenType::FatArrow,
+        TokenType::Identifier,
+        TokenType::DoubleEqual,
+        TokenType::Identifier,
+        TokenType::RightBrace,
+        TokenType::EndOfFile,
+    ]);
+}
+
+#[test]
+fn test_conditional_operator() {
+    let tokens = lex("a ? b : c");
+    let kinds: Vec<_> = tokens.iter().map(|t| t.token_type).collect();
+    assert_eq!(kinds, vec![
+        TokenType::Identifier,
+        TokenType::Question,
+        TokenType::Identifier,
+        TokenType::Colon,
+        TokenType::Identifier,
+        TokenType::EndOfFile,
+    ]);
+}
+
+#[test]
+fn test_enum_tokens() {
+    let tokens = lex("enum s is Shape::Rect : x");
+    let kinds: Vec<_> = tokens.iter().map(|t| t.token_type).collect();
+    assert_eq!(kinds, vec![
+        TokenType::Enum,
+        TokenType::Identifier,
+        TokenType::Is,
+        TokenType::Identifier,
+        TokenType::DoubleColon,
+        TokenType::Identifier,
+        TokenType::Colon,
+        TokenType::Identifier,
+        TokenType::EndOfFile,
+    ]);
+    assert_eq!(tokens[4].lexeme, "::");
+    for kind in [TokenType::Enum, TokenType::Is, TokenType::DoubleColon] {
+        assert_eq!(TokenType::from_str(&kind.to_string()), Some(kind));
+    }
+}
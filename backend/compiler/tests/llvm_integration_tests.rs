@@ -413,6 +413,354 @@ fn main() -> Int {
     assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
 }
 
+#[test]
+fn test_array_indexing_is_bounds_checked() {
+    let source = r#"
+fn sum() -> Int {
+    let values = [1, 2, 3];
+    values[0] = values[1] + values[2];
+    return values[0];
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    assert!(llvm_ir.contains("%array.i64 = type { i64, "), "{}", llvm_ir);
+    assert!(llvm_ir.contains("@malloc(i64"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("icmp ult i64"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("getelementptr inbounds i64"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("call void @exit(i32 1)"), "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_nested_arrays() {
+    let source = r#"
+fn corner() -> Float {
+    let grid = [[1.5, 2.5], [3.5, 4.5]];
+    grid[1][0] = 0.5;
+    return grid[1][1];
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    assert!(llvm_ir.contains("%array.array.double = type { i64, "), "{}", llvm_ir);
+    assert!(llvm_ir.contains("getelementptr inbounds %array.double"), "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_struct_field_assignment() {
+    let source = r#"
+struct Point { x: Int, y: Int }
+fn shift() -> Int {
+    let p = Point { x = 1, y = 2 };
+    p.x = p.y + 1;
+    let points = [Point { x = 0, y = 0 }];
+    points[0].y = p.x;
+    return points[0].y - p.x;
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    assert!(llvm_ir.contains("%Point = type { i64, i64 }"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("getelementptr inbounds %Point"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("extractvalue %Point"), "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_modulo_and_compound_assignment() {
+    let source = r#"
+fn next() -> Int {
+    return 1;
+}
+
+fn update() -> Float {
+    let counts = [1, 2, 3];
+    counts[next()] += counts[2] % 3;
+    counts[0] *= 2;
+    let ratio = 7.5;
+    ratio %= 2.0;
+    return ratio;
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    assert!(llvm_ir.contains("srem i64"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("frem double"), "{}", llvm_ir);
+    // El índice de `counts[next()] += ...` se evalúa una sola vez.
+    assert_eq!(llvm_ir.matches("call i64 @next()").count(), 1, "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_bitwise_operators() {
+    let source = r#"
+fn mix(a: Int, b: Int) -> Int {
+    return (a & b | a ^ b) << 2 >> 1 | ~a;
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    for instruction in ["and i64", "or i64", "xor i64", "shl i64", "ashr i64", "%tmpbitnot = xor i64"] {
+        assert!(llvm_ir.contains(instruction), "{}: {}", instruction, llvm_ir);
+    }
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_break_and_continue() {
+    let source = r#"
+fn search(limit: Int) -> Int {
+    let found = 0;
+    let i = 0;
+    'rows: while (i < limit) {
+        i += 1;
+        if (i % 2 == 0) {
+            continue;
+        }
+        let j = 0;
+        do {
+            j += 1;
+            if (i * j > 20) {
+                found = i * j;
+                break 'rows;
+            }
+            if (j > 3) {
+                break;
+            }
+        } until j > 10;
+    }
+    return found;
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    // `continue` vuelve a la condición del while; `break 'rows` sale del while
+    // desde el do-until, y `break` solo sale del do-until.
+    assert!(llvm_ir.matches("br label %whilecond").count() >= 2, "{}", llvm_ir);
+    assert!(llvm_ir.contains("br label %afterwhile"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("br label %afterDo"), "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_for_loops() {
+    let source = r#"
+fn total(n: Int, stride: Int) -> Int {
+    let sum = 0;
+    for i in 0..=n step 2 {
+        sum += i;
+    }
+    for i in n..0 step stride {
+        sum -= i;
+    }
+    'outer: for row in [[1, 2], [3, 4]] {
+        for value in row {
+            if (value == 3) { continue 'outer; }
+            sum += value;
+        }
+    }
+    return sum;
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    assert!(llvm_ir.contains("icmp sle i64"), "{}", llvm_ir);
+    // Con un paso que solo se conoce al ejecutar, el sentido se elige según su signo.
+    assert!(llvm_ir.contains("select i1 %ascending"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("br label %forstep"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("getelementptr inbounds %array.i64"), "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_match_statements() {
+    let source = r#"
+struct Point { x: Int, y: Int }
+fn classify(n: Int, grade: Char) -> Int {
+    let score = 0;
+    let p = Point { x = n, y = 2 };
+    match n {
+        0 => { score = 10; }
+        1..=3 => { score = 20; }
+        m if m < 0 => { return -1; }
+        _ => { }
+    }
+    match p {
+        Point { x = 0, y } => { score += y; }
+        Point { x = 1..=100, y = _ } => { score += 1; }
+        _ => { }
+    }
+    match grade {
+        'a'..='f' => { score += 5; }
+        _ => { }
+    }
+    return score;
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    // Los brazos literales del principio se reparten con un `switch`, incluidos los rangos pequeños.
+    assert!(llvm_ir.contains("switch i64 %n"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("i64 3, label %matcharm"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("switch i8 %grade"), "{}", llvm_ir);
+    // El brazo con guarda y los patrones de struct se prueban en orden.
+    assert!(llvm_ir.contains("br i1 %tmpcmp, label %matcharm"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("extractvalue %Point"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("%patrange = and i1"), "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_if_expressions() {
+    let source = r#"
+fn pick(a: Int, b: Int) -> Int {
+    let max = if a > b { a } else { b };
+    let sign = a < 0 ? -1 : a == 0 ? 0 : 1;
+    let scaled = if max > 10 { let half = max / 2; half + 1 } else { max };
+    let nested = if a > 0 { if b > 0 { 1 } else { 2 } } else { 3 };
+    let chained = if a > b { let d = a - b; if d > 5 { d } else if d > 1 { 1 } else { 0 } } else { -1 };
+    if (a > b ? a > 0 : b > 0) { return nested + chained; }
+    return scaled * sign;
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    // Cada rama salta a `ifexprcont`, donde un phi elige el valor.
+    assert!(llvm_ir.contains("ifexprcont:"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("%iftmp = phi i64 [ %a"), "{}", llvm_ir);
+    // El `?:` anidado llega al phi exterior desde el bloque de continuación del interior.
+    assert!(llvm_ir.contains("phi i64 [ -1, %ifexprthen"), "{}", llvm_ir);
+    // Los `if` anidados como valor de una rama dan un phi por nivel.
+    assert_eq!(llvm_ir.matches("phi i64").count(), 9, "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_enums() {
+    let source = r#"
+enum Shape { Circle(Float), Rect(Float, Float), Empty }
+fn area(r: Float, w: Float) -> Float {
+    let s = r > 0.0 ? Shape::Circle(r) : Shape::Rect(w, w);
+    let none = Shape::Empty;
+    let total = 0.0;
+    if (s is Shape::Circle) { total += 1.0; }
+    if (s == none) { return 0.0; }
+    match s {
+        Shape::Circle(radius) => { total += 3.14 * radius * radius; }
+        Shape::Rect(a, 0.0) => { }
+        Shape::Rect(a, b) => { total += a * b; }
+        Shape::Empty => { }
+    }
+    return total;
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    // Etiqueta `i32` seguida de los huecos de todas las variantes.
+    assert!(llvm_ir.contains("%Shape = type { i32, double, double, double }"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("phi %Shape"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("%isvariant = icmp eq i32 %tag, 0"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("%enumeq = and i1"), "{}", llvm_ir);
+    // Solo `Rect(a, 0.0)` necesita comprobar la carga útil.
+    assert!(llvm_ir.contains("matchpayload:"), "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_user_types() {
+    let source = r#"
+struct Point { x: Int, y: Int }
+fn inc(n: Int) -> Int { return n + 1; }
+fn apply(f: fn(Int) -> Int, p: Point) -> Int {
+    return f(p.x) + p.y;
+}
+fn first(p: Point) -> Int? {
+    let row: [Int; 2] = [p.x, p.y];
+    return row[0];
+}
+fn main() -> Int {
+    let step: fn(Int) -> Int = inc;
+    let ratio: Float? = 2.5;
+    return apply(step, Point { x = 1, y = 2 });
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    // Los structs viajan por valor y una función se pasa como puntero.
+    assert!(llvm_ir.contains("define i64 @apply(ptr %0, %Point %1)"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("store ptr @inc"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("call i64 %f1(i64 %x)"), "{}", llvm_ir);
+    // `T?` es `{ i1, T }`; un valor de `T` se envuelve con el indicador encendido.
+    assert!(llvm_ir.contains("%optional.i64 = type { i1, i64 }"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("define %optional.i64 @first(%Point %0)"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("store %optional.double { i1 true, double 2.500000e+00 }"), "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_imported_modules_compile_into_one_module() {
+    use compiler::modules::{link, load_program};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    let files: HashMap<PathBuf, String> = [
+        ("main.dream", "import geometry.point;\nimport util;\nfn main() -> Int {\n    return norm(Point { x = 3, y = 4 }) - square(5);\n}\n"),
+        ("geometry/point.dream", "import \"../util.dream\";\nstruct Point { x: Int, y: Int }\nfn norm(p: Point) -> Int { return square(p.x) + square(p.y); }\n"),
+        ("util.dream", "fn square(n: Int) -> Int { return n * n; }\n"),
+    ]
+    .into_iter()
+    .map(|(path, source)| (PathBuf::from(path), source.to_string()))
+    .collect();
+
+    let (modules, errors) = load_program(&PathBuf::from("main.dream"), &files);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let mut semantic_analyzer = SemanticAnalyzer::new();
+    semantic_analyzer.analyze_modules(&modules);
+    assert!(semantic_analyzer.errors.is_empty(), "{:?}", semantic_analyzer.errors);
+
+    // Las funciones de los tres archivos terminan en el mismo módulo LLVM.
+    let llvm_ir = compile_to_llvm_ir(&link(&modules)).unwrap();
+    assert!(llvm_ir.contains("define i64 @square(i64 %0)"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("define i64 @norm(%Point %0)"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("call i64 @norm(%Point"), "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
 #[test]
 fn test_syntax_error_detection() {
     let source = r#"
@@ -440,3 +788,111 @@ fn test() -> Int {
     // This may pass or fail depending on semantic analysis strictness
     // The test documents current behavior
 }
+
+#[test]
+fn test_string_escape_sequences() {
+    let source = r#"
+fn greet() -> Void {
+    printf("tab:\t\"hola\"\x21 \u{e9}\n");
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    assert!(llvm_ir.contains(r#"c"tab:\09\22hola\22! \C3\A9\0A\00""#), "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_interpolated_string_uses_snprintf() {
+    let source = r#"
+fn report(level: Int, ratio: Float, done: Bool) -> Void {
+    puts("nivel ${level * 2} (100%): ${ratio} ${done} ${"fin"}");
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    assert!(llvm_ir.contains("declare i32 @snprintf"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("@malloc"), "{}", llvm_ir);
+    assert!(llvm_ir.contains(r#"c"nivel %lld (100%%): %g %s %s\00""#), "{}", llvm_ir);
+    assert!(llvm_ir.contains(r#"c"false\00""#), "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_char_literals_lower_to_i8() {
+    let source = r#"
+fn initial(c: Char) -> Char {
+    let first: Char = 'a';
+    if (c == '\n') {
+        return first;
+    }
+    printf("%c\n", c);
+    puts("letra: ${c}");
+    return c;
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    assert!(llvm_ir.contains("define i8 @initial(i8"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("store i8 97"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("icmp eq i8"), "{}", llvm_ir);
+    assert!(llvm_ir.contains("sext i8"), "{}", llvm_ir);
+    assert!(llvm_ir.contains(r#"c"letra: %c\00""#), "{}", llvm_ir);
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_extended_numeric_literals() {
+    let source = r#"
+fn numbers() -> Int {
+    let hex: Int = 0xFF;
+    let oct: Int = 0o17;
+    let bin: Int = 0b1010;
+    let big: Int = 1_000_000;
+    let small: Float = 2.5e-3;
+    let min: Int = -9223372036854775808;
+    return hex + oct + bin + big + min;
+}
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let llvm_ir = result.unwrap();
+    for value in ["255", "15", "10", "1000000", "2.500000e-03", "-9223372036854775808"] {
+        assert!(llvm_ir.contains(value), "Missing constant {}: {}", value, llvm_ir);
+    }
+    assert!(validate_llvm_ir(&llvm_ir), "Generated LLVM IR is invalid");
+}
+
+#[test]
+fn test_out_of_range_literal_is_syntax_error() {
+    let source = r#"
+fn overflow() -> Int {
+    let a: Int = 9223372036854775808;
+    let b: Int = 0x1_0000_0000_0000_0000;
+    let c: Float = 1e400;
+    let d: Int = -9223372036854775809;
+    let e: Int = -9223372036854775808[0];
+    return 0;
+}
+"#;
+
+    let result = compile_source(source);
+    let message = result.expect_err("Out-of-range literals should be reported");
+    assert!(message.contains("NumberOutOfRange { found: \"'9223372036854775808'\", span: 3:18..3:37 }"), "{}", message);
+    assert!(message.contains("NumberOutOfRange { found: \"'0x1_0000_0000_0000_0000'\", span: 4:18..4:41 }"), "{}", message);
+    assert!(message.contains("NumberOutOfRange { found: \"'1e400'\", span: 5:20..5:25 }"), "{}", message);
+    // `-9223372036854775808` solo cabe si el `-` se aplica directamente al literal.
+    assert!(message.contains("NumberOutOfRange { found: \"'9223372036854775809'\", span: 6:19..6:38 }"), "{}", message);
+    assert!(message.contains("NumberOutOfRange { found: \"'9223372036854775808'\", span: 7:19..7:38 }"), "{}", message);
+}
@@ -0,0 +1,91 @@
+// Pruebas de los programas de varios archivos: carga de los `import` y análisis
+// con una tabla de símbolos por módulo.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use compiler::ast::*;
+use compiler::modules::{link, load_program, Module, ModuleError};
+use compiler::semantic_analyzer::SemanticAnalyzer;
+
+/// Archivos en memoria a partir de pares (ruta, código).
+fn files(sources: &[(&str, &str)]) -> HashMap<PathBuf, String> {
+    sources.iter().map(|(path, source)| (PathBuf::from(path), source.to_string())).collect()
+}
+
+fn paths(modules: &[Module]) -> Vec<String> {
+    modules.iter().map(Module::name).collect()
+}
+
+#[test]
+fn test_imports_resolve_relative_to_the_importer() {
+    let files = files(&[
+        ("main.dream", "import \"geometry/shapes.dream\";\nimport util;\nfn main() -> Int { return 0; }"),
+        ("geometry/shapes.dream", "import \"../util.dream\";\nimport point;\nconst SIDES: Int = 4;"),
+        ("geometry/point.dream", "struct Point { x: Int, y: Int }"),
+        ("util.dream", "const LIMIT: Int = 10;"),
+    ]);
+    let (modules, errors) = load_program(&PathBuf::from("./main.dream"), &files);
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    // Cada archivo una sola vez, después de los que importa.
+    assert_eq!(paths(&modules), vec!["util.dream", "geometry/point.dream", "geometry/shapes.dream", "main.dream"]);
+    assert_eq!(modules[2].imports, vec![PathBuf::from("util.dream"), PathBuf::from("geometry/point.dream")]);
+    assert_eq!(modules[3].imports, vec![PathBuf::from("geometry/shapes.dream"), PathBuf::from("util.dream")]);
+
+    let program = link(&modules);
+    assert_eq!(program.declarations.len(), 8);
+    assert_eq!(program.span, modules[3].ast.span);
+}
+
+#[test]
+fn test_import_cycles_and_missing_files_are_reported() {
+    let files = files(&[
+        ("main.dream", "import \"a.dream\";\nimport \"missing.dream\";\nfn main() -> Int { return 0; }"),
+        ("a.dream", "import \"b.dream\";"),
+        ("b.dream", "const B: Int = 1;\nimport \"a.dream\";"),
+    ]);
+    let (modules, errors) = load_program(&PathBuf::from("main.dream"), &files);
+    let errors: Vec<String> = errors.iter().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(errors, vec![
+        "ImportCycle([\"a.dream\", \"b.dream\", \"a.dream\"], 2:1..2:18)",
+        "FileNotFound(\"missing.dream\", Some((\"main.dream\", 2:1..2:24)))",
+    ]);
+    // Lo que se pudo cargar sigue disponible para el análisis.
+    assert_eq!(paths(&modules), vec!["b.dream", "a.dream", "main.dream"]);
+
+    let cycle = ModuleError::ImportCycle(vec![PathBuf::from("a.dream"), PathBuf::from("a.dream")], Span::default());
+    assert_eq!(cycle.to_string(), "Importación circular: a.dream -> a.dream");
+    let (_, errors) = load_program(&PathBuf::from("none.dream"), &files);
+    assert_eq!(errors, vec![ModuleError::FileNotFound(PathBuf::from("none.dream"), None)]);
+}
+
+#[test]
+fn test_modules_only_see_what_they_import() {
+    let files = files(&[
+        ("main.dream", "import \"shapes.dream\";\nfn main() -> Int {\n    let p = Point { x = 1, y = SIDES };\n    let limit = LIMIT;\n    return 0;\n}"),
+        ("shapes.dream", "import \"util.dream\";\nconst SIDES: Int = LIMIT;\nstruct Point { x: Int, y: Int }\nconst LIMIT: Int = 3;"),
+        ("util.dream", "const LIMIT: Int = 10;\nconst TOP = MISSING;"),
+    ]);
+    let (modules, errors) = load_program(&PathBuf::from("main.dream"), &files);
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze_modules(&modules);
+    let errors: Vec<String> = analyzer.errors.iter().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(errors, vec![
+        // Los errores de los archivos importados llevan el módulo donde ocurrieron.
+        "InModule(\"util.dream\", UndeclaredVariable(\"MISSING\", 2:13..2:20))",
+        // Todo termina en un solo módulo LLVM: un nombre no se repite entre archivos.
+        "InModule(\"shapes.dream\", RedeclaredVariable(\"LIMIT\", 4:7..4:12))",
+        // `main.dream` no importa `util.dream`, aunque `shapes.dream` sí.
+        "UndeclaredVariable(\"LIMIT\", 4:17..4:22)",
+    ]);
+
+    let root = analyzer.symbol_table.get_root_scope();
+    let scopes: Vec<&str> = root.children.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(scopes, vec!["module: util.dream", "module: shapes.dream", "module: main.dream"]);
+    assert!(root.symbols.is_empty());
+    assert!(root.children[1].symbols.contains_key("Point"));
+    assert!(root.children[2].children[0].name.starts_with("function: main"));
+}
@@ -0,0 +1,929 @@
+// Pruebas del parser sobre el AST que produce a partir del código fuente.
+
+use compiler::ast::*;
+use compiler::parser::parse_source;
+use compiler::semantic_analyzer::{SemanticAnalyzer, SemanticError};
+
+/// Analiza el código y comprueba que no haya errores léxicos ni sintácticos.
+fn parse(source: &str) -> Program {
+    let (result, lex_errors) = parse_source(source);
+    assert!(lex_errors.is_empty(), "{:?}", lex_errors);
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    result.ast
+}
+
+/// Resume cada error sintáctico como (esperado, encontrado, línea, columna).
+fn expected_vs_found(errors: &[SyntaxError]) -> Vec<(&str, &str, usize, usize)> {
+    errors.iter()
+        .map(|e| (e.expected(), e.found(), e.span().start.line, e.span().start.column))
+        .collect()
+}
+
+const DOCUMENTED: &str = r#"
+/// Punto en el plano.
+struct Point {
+    /// Coordenada horizontal.
+    x: Int,
+    y: Int
+}
+
+/**
+ * Suma dos enteros.
+ *
+ * Devuelve `a + b`.
+ */
+fn add(a: Int, b: Int) -> Int {
+    // Un comentario normal no es documentación.
+    let total = a + b;
+    return total;
+}
+
+/// Límite superior.
+/// Se usa en los bucles.
+const MAX: Int = 10;
+
+/// Contador global.
+let counter = 0;
+"#;
+
+#[test]
+fn test_doc_comments_attach_to_declarations() {
+    let program = parse(DOCUMENTED);
+
+    let Declaration::Struct(point) = &program.declarations[0] else { panic!("se esperaba un struct") };
+    assert_eq!(point.doc.as_deref(), Some("Punto en el plano."));
+    assert_eq!(point.fields[0].doc.as_deref(), Some("Coordenada horizontal."));
+    assert_eq!(point.fields[1].doc, None);
+
+    let Declaration::Function(add) = &program.declarations[1] else { panic!("se esperaba una función") };
+    assert_eq!(add.doc.as_deref(), Some("Suma dos enteros.\n\nDevuelve `a + b`."));
+    let Declaration::Variable(total) = &add.body.statements[0] else { panic!("se esperaba un let") };
+    assert_eq!(total.doc, None);
+
+    let Declaration::Constant(max) = &program.declarations[2] else { panic!("se esperaba una constante") };
+    assert_eq!(max.doc.as_deref(), Some("Límite superior.\nSe usa en los bucles."));
+
+    let Declaration::Variable(counter) = &program.declarations[3] else { panic!("se esperaba un let") };
+    assert_eq!(counter.doc.as_deref(), Some("Contador global."));
+}
+
+#[test]
+fn test_doc_comments_reach_the_symbol_table() {
+    let program = parse(DOCUMENTED);
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+
+    let symbols = &analyzer.symbol_table.current_scope.symbols;
+    assert_eq!(symbols["add"].doc(), Some("Suma dos enteros.\n\nDevuelve `a + b`."));
+    assert_eq!(symbols["Point"].doc(), Some("Punto en el plano."));
+    assert_eq!(symbols["MAX"].doc(), Some("Límite superior.\nSe usa en los bucles."));
+}
+
+#[test]
+fn test_interpolated_string_expression() {
+    let program = parse("let message = \"nivel ${level * 2}: ${name}\";");
+    let Declaration::Variable(decl) = &program.declarations[0] else { panic!("se esperaba una variable") };
+    let Expression::InterpolatedString(parts, _) = &decl.value else { panic!("se esperaba una cadena interpolada") };
+
+    assert_eq!(parts.len(), 4);
+    assert_eq!(parts[0], InterpolationPart::Text("nivel ".to_string()));
+    assert!(matches!(&parts[1], InterpolationPart::Expression(e) if matches!(**e, Expression::Binary { op: BinaryOp::Asterisk, .. })));
+    assert_eq!(parts[2], InterpolationPart::Text(": ".to_string()));
+    let InterpolationPart::Expression(expr) = &parts[3] else { panic!("se esperaba una expresión") };
+    let Expression::Identifier(name) = expr.as_ref() else { panic!("se esperaba un identificador") };
+    assert_eq!((name.name.as_str(), format!("{:?}", name.span)), ("name", "1:38..1:42".to_string()));
+}
+
+#[test]
+fn test_invalid_interpolations_are_syntax_errors() {
+    let (result, lex_errors) = parse_source("let a = \"${}\";\nlet b = \"${x y}\";\nlet c = 1;");
+    assert!(lex_errors.is_empty(), "{:?}", lex_errors);
+    assert_eq!(expected_vs_found(&result.errors), vec![
+        ("una expresión dentro de '${}'", "'}'", 1, 9),
+        ("'}' al final de la interpolación", "'y'", 2, 14),
+    ]);
+    // El parser se recupera y sigue con las declaraciones siguientes.
+    assert!(matches!(result.ast.declarations.last(), Some(Declaration::Variable(v)) if v.identifier.name == "c"));
+}
+
+#[test]
+fn test_interpolated_parts_are_type_checked() {
+    let program = parse(r#"
+fn show() -> Void {
+    puts("hola");
+}
+
+fn main() -> Int {
+    let n: Int = 3;
+    let ok: String = "n = ${n}, ${n > 2}, ${1.5}";
+    let bad: String = "${show()}";
+    return 0;
+}
+"#);
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let [SemanticError::InvalidInterpolation(type_name, span)] = analyzer.errors.as_slice() else {
+        panic!("se esperaba un único error de interpolación: {:?}", analyzer.errors)
+    };
+    assert_eq!((type_name.as_str(), format!("{:?}", span)), ("Void", "9:26..9:32".to_string()));
+}
+
+#[test]
+fn test_char_literals_and_type() {
+    let program = parse(r#"
+fn main() -> Int {
+    let letter: Char = 'x';
+    let wrong: Char = "x";
+    let same: Bool = letter == '\n';
+    return 0;
+}
+"#);
+    let Declaration::Function(main) = &program.declarations[0] else { panic!("se esperaba una función") };
+    let Declaration::Variable(letter) = &main.body.statements[0] else { panic!("se esperaba una variable") };
+    assert_eq!(letter.var_type, Some(Type::Char));
+    assert!(matches!(letter.value, Expression::Literal(Literal::Char('x'), _)));
+
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let [SemanticError::TypeMismatch(expected, found, span)] = analyzer.errors.as_slice() else {
+        panic!("se esperaba un único error de tipos: {:?}", analyzer.errors)
+    };
+    assert_eq!((expected.as_str(), found.as_str()), ("Char", "String"));
+    assert_eq!(format!("{:?}", span), "4:23..4:26");
+}
+
+#[test]
+fn test_every_node_has_a_span() {
+    let program = parse("fn main() -> Int {\n    let total = (1 + 2) * 3;\n    return total;\n}\n");
+    assert_eq!(format!("{:?}", program.span), "1:1..5:1");
+
+    let Declaration::Function(main) = &program.declarations[0] else { panic!("se esperaba una función") };
+    assert_eq!(format!("{:?}", main.span), "1:1..4:2");
+    assert_eq!(format!("{:?}", main.body.span), "1:18..4:2");
+
+    let Declaration::Variable(total) = &main.body.statements[0] else { panic!("se esperaba una variable") };
+    assert_eq!(format!("{:?}", total.span), "2:5..2:29");
+    let Expression::Binary { left, right, span, .. } = &total.value else { panic!("se esperaba una expresión binaria") };
+    assert_eq!(format!("{:?}", span), "2:17..2:28");
+    assert_eq!(format!("{:?}", left.span()), "2:17..2:24");
+    assert_eq!(format!("{:?}", right.span()), "2:27..2:28");
+
+    assert_eq!(format!("{:?}", main.body.statements[1].span()), "3:5..3:18");
+}
+
+#[test]
+fn test_semantic_errors_point_at_the_offending_expression() {
+    let program = parse("fn main() -> Int {\n    let x = 1 + \"uno\";\n    return true;\n}\n");
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let spans: Vec<String> = analyzer
+        .errors
+        .iter()
+        .map(|error| match error {
+            SemanticError::TypeMismatch(_, _, span) | SemanticError::ReturnTypeMismatch(_, _, span) => format!("{:?}", span),
+            other => panic!("error inesperado: {:?}", other),
+        })
+        .collect();
+    assert_eq!(spans, vec!["2:13..2:22", "3:12..3:16"]);
+}
+
+#[test]
+fn test_syntax_errors_keep_the_rest_of_the_function() {
+    let (result, _) = parse_source(r#"
+fn main() -> Int {
+    let a = 1;
+    let b = a + ;
+    let c = 3
+    return a;
+}
+
+fn helper() -> Int {
+    return 2;
+}
+"#);
+    assert_eq!(expected_vs_found(&result.errors), vec![
+        ("una expresión", "';'", 4, 17),
+        ("';' después de la declaración de la variable", "'return'", 6, 5),
+    ]);
+    assert_eq!(result.ast.declarations.len(), 2);
+
+    let Declaration::Function(main) = &result.ast.declarations[0] else { panic!("se esperaba una función") };
+    assert_eq!(main.body.statements.len(), 4);
+    let Declaration::Variable(b) = &main.body.statements[1] else { panic!("se esperaba una variable") };
+    let Expression::Binary { right, .. } = &b.value else { panic!("se esperaba una expresión binaria") };
+    assert!(matches!(**right, Expression::Error(_)));
+    assert!(matches!(main.body.statements[3], Declaration::Statement(Statement::Return(_))));
+
+    // El análisis semántico sigue adelante sin sumar errores por el hueco.
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&result.ast);
+    assert_eq!(analyzer.errors, vec![]);
+}
+
+#[test]
+fn test_unparseable_code_becomes_error_nodes() {
+    let (result, _) = parse_source("fn main() -> Int {\n    let = 5;\n    show(1, );\n    return 0;\n}\n}\nlet x = 1;\n");
+    assert_eq!(result.errors.len(), 3, "{:?}", result.errors);
+
+    let [Declaration::Function(main), Declaration::Statement(Statement::Error(stray)), Declaration::Variable(x)] =
+        result.ast.declarations.as_slice()
+    else {
+        panic!("declaraciones inesperadas: {:?}", result.ast.declarations)
+    };
+    assert_eq!(format!("{:?}", stray), "6:1..6:2");
+    assert_eq!(x.identifier.name, "x");
+
+    let [Declaration::Statement(Statement::Error(skipped)), Declaration::Statement(Statement::Expression(call)), Declaration::Statement(Statement::Return(_))] =
+        main.body.statements.as_slice()
+    else {
+        panic!("sentencias inesperadas: {:?}", main.body.statements)
+    };
+    assert_eq!(format!("{:?}", skipped), "2:5..2:13");
+    let Expression::FunctionCall { arguments, .. } = call else { panic!("se esperaba una llamada") };
+    assert!(matches!(arguments.as_slice(), [Expression::Literal(..), Expression::Error(_)]));
+}
+
+#[test]
+fn test_index_expressions_and_assignments() {
+    let program = parse("fn main() -> Int {\n    let m = [[1, 2], [3]];\n    m[0][1] = m[1][0];\n    return m[0][1];\n}\n");
+    let Declaration::Function(main) = &program.declarations[0] else { panic!("se esperaba una función") };
+
+    let Declaration::Statement(Statement::Expression(assignment)) = &main.body.statements[1] else {
+        panic!("se esperaba una expresión")
+    };
+    let Expression::Assignment { target, value, span } = assignment else {
+        panic!("se esperaba una asignación: {:?}", assignment)
+    };
+    let Expression::Index { object, index, .. } = &**target else { panic!("se esperaba un elemento: {:?}", target) };
+    assert!(matches!(&**object, Expression::Index { object, .. } if matches!(&**object, Expression::Identifier(id) if id.name == "m")));
+    assert!(matches!(**index, Expression::Literal(Literal::Int(1), _)));
+    assert_eq!(format!("{:?}", value.span()), "3:15..3:22");
+    assert_eq!(format!("{:?}", span), "3:5..3:22");
+
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    assert_eq!(analyzer.errors, vec![]);
+}
+
+#[test]
+fn test_index_types_are_checked() {
+    let program = parse(r#"
+const primes = [2, 3, 5];
+fn main() -> Int {
+    let names = ["ana", "luis"];
+    let first: String = names[0];
+    let mixed = [1, 2.5];
+    let bad = names[true];
+    names[1] = 7;
+    let n = 4;
+    let m = n[0];
+    primes[0] = 1;
+    return 0;
+}
+"#);
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let errors: Vec<String> = analyzer.errors.iter().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(errors, vec![
+        "TypeMismatch(\"Int\", \"Float\", 6:21..6:24)",
+        "TypeMismatch(\"Int\", \"Bool\", 7:21..7:25)",
+        "TypeMismatch(\"String\", \"Int\", 8:16..8:17)",
+        "InvalidIndexTarget(\"Int\", 10:13..10:14)",
+        "InvalidAssignment(\"Cannot assign to an element of constant 'primes'\", 11:5..11:11)",
+    ]);
+}
+
+#[test]
+fn test_field_assignment_targets() {
+    let program = parse("fn main() -> Int {\n    a.b.c = 1;\n    a[i].x = 2;\n    return 0;\n}\n");
+    let Declaration::Function(main) = &program.declarations[0] else { panic!("se esperaba una función") };
+
+    let targets: Vec<&Expression> = main.body.statements[..2].iter().map(|statement| {
+        let Declaration::Statement(Statement::Expression(Expression::Assignment { target, .. })) = statement else {
+            panic!("se esperaba una asignación: {:?}", statement)
+        };
+        &**target
+    }).collect();
+
+    let Expression::MemberAccess { object, property, span } = targets[0] else { panic!("se esperaba un campo") };
+    assert_eq!(property.name, "c");
+    assert_eq!(format!("{:?}", span), "2:5..2:10");
+    assert!(matches!(&**object, Expression::MemberAccess { property, .. } if property.name == "b"));
+
+    let Expression::MemberAccess { object, property, .. } = targets[1] else { panic!("se esperaba un campo") };
+    assert_eq!(property.name, "x");
+    assert!(matches!(&**object, Expression::Index { .. }));
+}
+
+#[test]
+fn test_invalid_assignment_targets_are_rejected() {
+    let (result, _) = parse_source("fn main() -> Int {\n    f().x = 1;\n    (a + b) = 2;\n    return 0;\n}\n");
+    let found: Vec<String> = result.errors.iter().map(|error| format!("{:?}", error.span())).collect();
+    assert_eq!(found, vec!["2:5..2:10", "3:5..3:12"]);
+}
+
+#[test]
+fn test_struct_fields_are_checked() {
+    let program = parse(r#"
+struct Point { x: Int, y: Int }
+const origin = Point { x = 0, y = 0 };
+fn main() -> Int {
+    let points = [Point { x = 1, y = 2 }, Point { x = 3, y = 4 }];
+    points[0].x = points[1].y;
+    points[1].y = 1.5;
+    points[0].z = 1;
+    let p = Point { x = 1 };
+    let q = Line { a = 1 };
+    let n = 3;
+    n.x = 1;
+    origin.x = 2;
+    return points[0].x;
+}
+"#);
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let errors: Vec<String> = analyzer.errors.iter().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(errors, vec![
+        "TypeMismatch(\"Int\", \"Float\", 7:19..7:22)",
+        "FieldNotFound(\"Point\", \"z\", 8:15..8:16)",
+        "MissingField(\"Point\", \"y\", 9:13..9:28)",
+        "UndefinedStruct(\"Line\", 10:13..10:17)",
+        "InvalidMemberAccess(\"value of type 'Int' has no fields\", 12:5..12:6)",
+        "InvalidAssignment(\"Cannot assign to a field of constant 'origin'\", 13:5..13:11)",
+    ]);
+}
+
+#[test]
+fn test_compound_assignments() {
+    let program = parse("fn main() -> Int {\n    points[i].x %= 2;\n    n++;\n    return a % b * c;\n}\n");
+    let Declaration::Function(main) = &program.declarations[0] else { panic!("se esperaba una función") };
+
+    let Declaration::Statement(Statement::Expression(compound)) = &main.body.statements[0] else {
+        panic!("se esperaba una expresión")
+    };
+    let Expression::CompoundAssignment { target, op, value, span } = compound else {
+        panic!("se esperaba una asignación compuesta: {:?}", compound)
+    };
+    assert!(matches!(&**target, Expression::MemberAccess { object, .. } if matches!(**object, Expression::Index { .. })));
+    assert_eq!(*op, BinaryOp::Percent);
+    assert!(matches!(**value, Expression::Literal(Literal::Int(2), _)));
+    assert_eq!(format!("{:?}", span), "2:5..2:21");
+
+    // `n++` es `n += 1`, con el literal en la posición del operador.
+    let Declaration::Statement(Statement::Expression(increment)) = &main.body.statements[1] else {
+        panic!("se esperaba una expresión")
+    };
+    let Expression::CompoundAssignment { op: BinaryOp::Plus, value, span, .. } = increment else {
+        panic!("se esperaba un incremento: {:?}", increment)
+    };
+    assert_eq!(format!("{:?}", value.span()), "3:6..3:8");
+    assert_eq!(format!("{:?}", span), "3:5..3:8");
+
+    // `%` tiene la precedencia de `*` y asocia por la izquierda.
+    let Declaration::Statement(Statement::Return(ret)) = &main.body.statements[2] else { panic!("se esperaba un return") };
+    let Expression::Binary { left, op: BinaryOp::Asterisk, .. } = &ret.value else { panic!("se esperaba un producto") };
+    assert!(matches!(**left, Expression::Binary { op: BinaryOp::Percent, .. }));
+}
+
+#[test]
+fn test_compound_assignment_types_are_checked() {
+    let program = parse(r#"
+const limit = 10;
+fn main() -> Int {
+    let n = 7;
+    let x = 2.5;
+    let s = "a";
+    n %= 4;
+    x %= 1.5;
+    n += 1.5;
+    s += "b";
+    limit -= 1;
+    let flag = true % 2;
+    return n;
+}
+"#);
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let errors: Vec<String> = analyzer.errors.iter().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(errors, vec![
+        "TypeMismatch(\"Int\", \"Float\", 9:10..9:13)",
+        "InvalidOperand(\"+\", \"String\", 10:5..10:6)",
+        "InvalidAssignment(\"Cannot assign to constant 'limit'\", 11:5..11:10)",
+        "InvalidOperand(\"%\", \"Bool\", 12:16..12:20)",
+    ]);
+}
+
+#[test]
+fn test_bitwise_precedence() {
+    let program = parse("let a = x | y ^ z & m == 0;\nlet b = 1 << n + 1 < limit;\nlet c = ~x & 1;\n");
+    let value = |index: usize| {
+        let Declaration::Variable(variable) = &program.declarations[index] else { panic!("se esperaba una variable") };
+        variable.value.clone()
+    };
+
+    // `x | (y ^ (z & m))`, comparado después con `0`.
+    let Expression::Binary { left, op: BinaryOp::DoubleEqual, .. } = value(0) else { panic!("se esperaba '=='") };
+    let Expression::Binary { right, op: BinaryOp::Bar, .. } = *left else { panic!("se esperaba '|'") };
+    let Expression::Binary { right, op: BinaryOp::Caret, .. } = *right else { panic!("se esperaba '^'") };
+    assert!(matches!(*right, Expression::Binary { op: BinaryOp::Ampersand, .. }));
+
+    // `(1 << (n + 1)) < limit`
+    let Expression::Binary { left, op: BinaryOp::Less, .. } = value(1) else { panic!("se esperaba '<'") };
+    let Expression::Binary { right, op: BinaryOp::ShiftLeft, .. } = *left else { panic!("se esperaba '<<'") };
+    assert!(matches!(*right, Expression::Binary { op: BinaryOp::Plus, .. }));
+
+    let Expression::Binary { left, op: BinaryOp::Ampersand, .. } = value(2) else { panic!("se esperaba '&'") };
+    assert!(matches!(*left, Expression::Unary { op: UnaryOp::Tilde, .. }));
+}
+
+#[test]
+fn test_bitwise_operators_require_int() {
+    let program = parse(r#"
+fn main() -> Int {
+    let mask = 0xFF & 7 | 1 << 4;
+    let a = 1.5 & 2.5;
+    let b = ~true;
+    let c = "x" >> 1;
+    let d = mask ^ 2.0;
+    return mask;
+}
+"#);
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let errors: Vec<String> = analyzer.errors.iter().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(errors, vec![
+        "InvalidOperand(\"&\", \"Float\", 4:13..4:16)",
+        "InvalidOperand(\"~\", \"Bool\", 5:14..5:18)",
+        "InvalidOperand(\">>\", \"String\", 6:13..6:16)",
+        "TypeMismatch(\"Int\", \"Float\", 7:13..7:23)",
+    ]);
+}
+
+#[test]
+fn test_labelled_loops_and_jumps() {
+    let program = parse("fn main() -> Int {\n    'outer: while (true) {\n        do { break 'outer; } until false;\n        continue;\n    }\n    return 0;\n}\n");
+    let Declaration::Function(main) = &program.declarations[0] else { panic!("se esperaba una función") };
+
+    let Declaration::Statement(Statement::While(outer)) = &main.body.statements[0] else { panic!("se esperaba un while") };
+    assert_eq!(outer.label.as_ref().map(|label| label.name.as_str()), Some("outer"));
+    assert_eq!(format!("{:?}", outer.span), "2:5..5:6");
+
+    let Declaration::Statement(Statement::DoUntil(inner)) = &outer.body.statements[0] else { panic!("se esperaba un do-until") };
+    assert_eq!(inner.label, None);
+    let Declaration::Statement(Statement::Break(jump)) = &inner.body.statements[0] else { panic!("se esperaba un break") };
+    assert_eq!(jump.label.as_ref().map(|label| format!("{} {:?}", label.name, label.span)), Some("outer 3:20..3:26".to_string()));
+    assert_eq!(format!("{:?}", jump.span), "3:14..3:27");
+
+    let Declaration::Statement(Statement::Continue(jump)) = &outer.body.statements[1] else { panic!("se esperaba un continue") };
+    assert_eq!(jump.label, None);
+}
+
+#[test]
+fn test_jumps_must_be_inside_a_matching_loop() {
+    let program = parse(r#"
+fn main() -> Int {
+    break;
+    'outer: while (true) {
+        for i in 0..3 {
+            continue 'outer;
+            break 'missing;
+        }
+        do {
+            if (true) { break; }
+            let s: Int = "x";
+        } until true;
+    }
+    continue 'outer;
+    return 0;
+}
+"#);
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let errors: Vec<String> = analyzer.errors.iter()
+        .filter(|e| !matches!(e, SemanticError::UndeclaredVariable(..)))
+        .map(|e| format!("{:?}", e))
+        .collect();
+    assert_eq!(errors, vec![
+        "JumpOutsideLoop(\"break\", 3:5..3:11)",
+        "UndefinedLabel(\"missing\", 7:19..7:27)",
+        "TypeMismatch(\"Int\", \"String\", 11:26..11:29)",
+        "JumpOutsideLoop(\"continue\", 14:5..14:21)",
+    ]);
+}
+
+#[test]
+fn test_for_loops_over_ranges_and_arrays() {
+    let program = parse("fn main() -> Int {\n    for i in 0..=n + 1 step 2 { }\n    for p in points { }\n    for p in [Point { x = 1 }] { }\n    return 0;\n}\n");
+    let Declaration::Function(main) = &program.declarations[0] else { panic!("se esperaba una función") };
+    let loops: Vec<&ForStatement> = main.body.statements[..3].iter().map(|statement| {
+        let Declaration::Statement(Statement::For(for_stmt)) = statement else { panic!("se esperaba un for: {:?}", statement) };
+        for_stmt
+    }).collect();
+
+    let Expression::Range { start, end, inclusive, step, span } = &loops[0].iterable else { panic!("se esperaba un rango") };
+    assert!(matches!(**start, Expression::Literal(Literal::Int(0), _)));
+    assert!(matches!(**end, Expression::Binary { op: BinaryOp::Plus, .. }));
+    assert!(*inclusive);
+    assert!(matches!(step.as_deref(), Some(Expression::Literal(Literal::Int(2), _))));
+    assert_eq!(format!("{:?}", span), "2:14..2:30");
+
+    // La llave abre el cuerpo del bucle, no una instancia de `points`.
+    assert!(matches!(&loops[1].iterable, Expression::Identifier(id) if id.name == "points"));
+    assert!(matches!(&loops[2].iterable, Expression::Array(elements, _) if matches!(elements[0], Expression::StructInstantiation { .. })));
+}
+
+#[test]
+fn test_for_loop_variables_are_typed() {
+    let program = parse(r#"
+fn main() -> Int {
+    let names = ["ana", "luis"];
+    let i = 10;
+    for i in 0..i {
+        let n: Int = i;
+    }
+    for name in names {
+        let n: Int = name;
+    }
+    for x in 3 {
+        let y = x + 1;
+    }
+    for f in 0.5..2 { }
+    let r = 0..3;
+    return i;
+}
+"#);
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let errors: Vec<String> = analyzer.errors.iter().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(errors, vec![
+        "TypeMismatch(\"Int\", \"String\", 9:22..9:26)",
+        "NotIterable(\"Int\", 11:14..11:15)",
+        "InvalidOperand(\"..\", \"Float\", 14:14..14:17)",
+        "RangeOutsideFor(15:13..15:17)",
+    ]);
+}
+
+#[test]
+fn test_match_arms_and_patterns() {
+    let program = parse(r#"
+fn main() -> Int {
+    match p {
+        Point { x = 0, y } => { }
+        Point { x = -5..=5, y = _ } if p.y > 0 => { }
+        _ => { }
+    }
+    match c { 'a'..'z' => { }, "x" => { }, _ => { } }
+    return 0;
+}
+"#);
+    let Declaration::Function(main) = &program.declarations[0] else { panic!("se esperaba una función") };
+    let Declaration::Statement(Statement::Match(first)) = &main.body.statements[0] else { panic!("se esperaba un match") };
+    assert!(matches!(&first.subject, Expression::Identifier(id) if id.name == "p"));
+    assert_eq!(first.arms.len(), 3);
+    assert_eq!(format!("{:?}", first.span), "3:5..7:6");
+
+    let Pattern::Struct { name, fields, span } = &first.arms[0].pattern else { panic!("se esperaba un patrón de struct") };
+    assert_eq!(name.name, "Point");
+    assert_eq!(format!("{:?}", span), "4:9..4:27");
+    assert!(matches!(fields[0].pattern, Pattern::Literal(Literal::Int(0), _)));
+    // `y` a secas enlaza el campo a una variable con el mismo nombre.
+    assert!(matches!(&fields[1].pattern, Pattern::Binding(id) if id.name == "y"));
+    assert_eq!(first.arms[0].guard, None);
+
+    let Pattern::Struct { fields, .. } = &first.arms[1].pattern else { panic!("se esperaba un patrón de struct") };
+    assert!(matches!(fields[0].pattern, Pattern::Range { start: Literal::Int(-5), end: Literal::Int(5), inclusive: true, .. }));
+    assert!(matches!(first.arms[1].guard, Some(Expression::Binary { op: BinaryOp::Greater, .. })));
+    assert!(matches!(first.arms[2].pattern, Pattern::Wildcard(_)));
+
+    let Declaration::Statement(Statement::Match(second)) = &main.body.statements[1] else { panic!("se esperaba un match") };
+    assert!(matches!(second.arms[0].pattern, Pattern::Range { start: Literal::Char('a'), end: Literal::Char('z'), inclusive: false, .. }));
+    assert!(matches!(&second.arms[1].pattern, Pattern::Literal(Literal::String(s), _) if s == "x"));
+}
+
+#[test]
+fn test_match_exhaustiveness_and_unreachable_arms() {
+    let program = parse(r#"
+struct Point { x: Int, y: Int }
+fn sign(n: Int) -> Int {
+    match n {
+        0 => { return 0; }
+        1..=9 => { return 1; }
+        5 => { return 5; }
+        m if m < 0 => { return -1; }
+        _ => { return 2; }
+        7 => { return 7; }
+    }
+}
+fn main() -> Int {
+    let p = Point { x = 1, y = 2 };
+    let b = true;
+    match b { true => { } false => { } }
+    match 'q' { 'a'..='z' => { } }
+    match p {
+        Point { x = 0, y } => { let z: Int = y; }
+        Point { x = "0" } => { }
+    }
+    match 3 { n if n => { } _ => { } }
+    match p { Point { x, y } => { } }
+    match 'q' { '\0'..='\x3F' => { } '\x40'..='\x7F' => { } }
+    return sign(p.x);
+}
+"#);
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let errors: Vec<String> = analyzer.errors.iter().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(errors, vec![
+        "UnreachablePattern(7:9..7:10)",
+        "UnreachablePattern(10:9..10:10)",
+        "NonExhaustiveMatch(\"Char\", 17:11..17:14)",
+        "TypeMismatch(\"Int\", \"String\", 20:21..20:24)",
+        "NonExhaustiveMatch(\"Point\", 18:11..18:12)",
+        "TypeMismatch(\"Bool\", \"Int\", 22:20..22:21)",
+    ]);
+}
+
+#[test]
+fn test_if_expressions_and_conditional_operator() {
+    let program = parse("let x = if a > b { a } else if a < 0 { let y = -a; y * 2 } else { b };\nlet s = c ? 1 : d ? 2 : 3;\n");
+    let initializer = |index: usize| {
+        let Declaration::Variable(variable) = &program.declarations[index] else { panic!("se esperaba una variable") };
+        variable.value.clone()
+    };
+
+    let Expression::If { condition, then_branch, else_branch, span } = initializer(0) else { panic!("se esperaba un if") };
+    assert_eq!(format!("{:?}", span), "1:9..1:70");
+    assert!(matches!(*condition, Expression::Binary { op: BinaryOp::Greater, .. }));
+    let Expression::Block { block, value } = *then_branch else { panic!("se esperaba un bloque") };
+    assert!(block.statements.is_empty());
+    assert!(matches!(*value, Expression::Identifier(id) if id.name == "a"));
+    // `else if` encadena otra expresión `if`; el bloque conserva sus sentencias antes del valor.
+    let Expression::If { then_branch, .. } = *else_branch else { panic!("se esperaba un else if") };
+    let Expression::Block { block, value } = *then_branch else { panic!("se esperaba un bloque") };
+    assert!(matches!(block.statements[..], [Declaration::Variable(_)]));
+    assert!(matches!(*value, Expression::Binary { op: BinaryOp::Asterisk, .. }));
+
+    // `?:` asocia por la derecha: `c ? 1 : (d ? 2 : 3)`.
+    let Expression::If { condition, else_branch, span, .. } = initializer(1) else { panic!("se esperaba '?:'") };
+    assert_eq!(format!("{:?}", span), "2:9..2:26");
+    assert!(matches!(*condition, Expression::Identifier(id) if id.name == "c"));
+    assert!(matches!(*else_branch, Expression::If { .. }));
+}
+
+#[test]
+fn test_nested_if_expressions_and_conditional_conditions() {
+    let program = parse(r#"
+let x = if a { if b { 1 } else { 2 } } else { 3 };
+let y = if a { let z = 1; if b { z } else if c { 2 } else { 3 } } else { 4 };
+fn main() -> Int {
+    if (a ? b : c) { } else if (c ? a : b) { }
+    while (a ? b : c) { }
+    do { } until a ? b : c;
+    return 0;
+}
+"#);
+    let initializer = |index: usize| {
+        let Declaration::Variable(variable) = &program.declarations[index] else { panic!("se esperaba una variable") };
+        variable.value.clone()
+    };
+
+    // El `if` sin paréntesis al final del bloque es su valor, no una sentencia.
+    let Expression::If { then_branch, .. } = initializer(0) else { panic!("se esperaba un if") };
+    let Expression::Block { block, value } = *then_branch else { panic!("se esperaba un bloque") };
+    assert!(block.statements.is_empty());
+    assert!(matches!(*value, Expression::If { .. }));
+
+    let Expression::If { then_branch, .. } = initializer(1) else { panic!("se esperaba un if") };
+    let Expression::Block { block, value } = *then_branch else { panic!("se esperaba un bloque") };
+    assert!(matches!(block.statements[..], [Declaration::Variable(_)]));
+    let Expression::If { else_branch, .. } = *value else { panic!("se esperaba un if anidado") };
+    assert!(matches!(*else_branch, Expression::If { .. }));
+
+    let Declaration::Function(main) = &program.declarations[2] else { panic!("se esperaba una función") };
+    let [Declaration::Statement(Statement::If(if_stmt)), Declaration::Statement(Statement::While(while_stmt)), Declaration::Statement(Statement::DoUntil(do_until)), ..] =
+        &main.body.statements[..] else { panic!("se esperaban if, while y do-until") };
+    assert!(matches!(if_stmt.condition, Expression::If { .. }));
+    assert!(matches!(while_stmt.condition, Expression::If { .. }));
+    assert!(matches!(do_until.condition, Expression::If { .. }));
+}
+
+#[test]
+fn test_if_expression_branches_must_agree() {
+    let program = parse(r#"
+fn main() -> Int {
+    let a = 3;
+    let big = if a > 2 { 1.5 } else { 0.5 };
+    let f: Float = big;
+    let bad = a > 1 ? 1 : "uno";
+    let c = a ? 1 : 2;
+    let n: Int = if true { a } else { a * 2 };
+    if (a > 1 ? a : 0) { }
+    while (a) { }
+    return n;
+}
+"#);
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let errors: Vec<String> = analyzer.errors.iter().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(errors, vec![
+        "TypeMismatch(\"Int\", \"String\", 6:27..6:32)",
+        "TypeMismatch(\"Bool\", \"Int\", 7:13..7:14)",
+        "TypeMismatch(\"Bool\", \"Int\", 9:9..9:22)",
+        "TypeMismatch(\"Bool\", \"Int\", 10:12..10:13)",
+    ]);
+}
+
+#[test]
+fn test_value_blocks_need_a_trailing_expression() {
+    let (result, _) = parse_source("let x = if ok { 1; } else { 2 };\n");
+    assert_eq!(expected_vs_found(&result.errors), vec![
+        ("la expresión con el valor del bloque (sin ';')", "'}'", 1, 20),
+    ]);
+}
+
+#[test]
+fn test_minimum_int_literal_needs_its_sign() {
+    let program = parse("let min = -9223372036854775808;\nfn f(n: Int) -> Int { match n { -0x8000_0000_0000_0000 => { } _ => { } } return 0; }\n");
+    let Declaration::Variable(min) = &program.declarations[0] else { panic!("se esperaba una variable") };
+    let Expression::Literal(Literal::Int(value), span) = &min.value else { panic!("se esperaba un literal: {:?}", min.value) };
+    assert_eq!((*value, format!("{:?}", span)), (i64::MIN, "1:11..1:31".to_string()));
+    let Declaration::Function(f) = &program.declarations[1] else { panic!("se esperaba una función") };
+    let Declaration::Statement(Statement::Match(m)) = &f.body.statements[0] else { panic!("se esperaba un match") };
+    assert!(matches!(m.arms[0].pattern, Pattern::Literal(Literal::Int(i64::MIN), _)));
+
+    // Sin el signo, o con un valor aún menor, no cabe.
+    let (result, _) = parse_source("let a = 9223372036854775808;\nlet b = -9223372036854775809;\n");
+    let found: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(found, vec![
+        "El literal numérico '9223372036854775808' está fuera de rango en la línea 1, columna 9",
+        "El literal numérico '9223372036854775809' está fuera de rango en la línea 2, columna 10",
+    ]);
+}
+
+#[test]
+fn test_enum_declarations_variants_and_patterns() {
+    let program = parse(r#"
+/// Figura geométrica.
+enum Shape {
+    Circle(Float),
+    Rect(Float, Float),
+    Empty
+}
+let s = Shape::Rect(2.0, 3.5);
+let round = s is Shape::Circle && true;
+match s { Shape::Rect(w, 0.0) => { } Shape::Empty => { } _ => { } }
+"#);
+    let Declaration::Enum(shape) = &program.declarations[0] else { panic!("se esperaba un enum") };
+    assert_eq!(shape.doc.as_deref(), Some("Figura geométrica."));
+    let variants: Vec<_> = shape.variants.iter().map(|v| (v.name.name.as_str(), v.payload.clone())).collect();
+    assert_eq!(variants, vec![
+        ("Circle", vec![Type::Float]),
+        ("Rect", vec![Type::Float, Type::Float]),
+        ("Empty", vec![]),
+    ]);
+    assert_eq!(format!("{:?}", shape.variants[1].span), "5:5..5:23");
+
+    let Declaration::Variable(s) = &program.declarations[1] else { panic!("se esperaba un let") };
+    let Expression::EnumVariant { enum_name, variant, arguments, span } = &s.value else { panic!("se esperaba una variante") };
+    assert_eq!((enum_name.name.as_str(), variant.name.as_str(), arguments.len()), ("Shape", "Rect", 2));
+    assert_eq!(format!("{:?}", span), "8:9..8:30");
+
+    // `is` liga más fuerte que `&&`.
+    let Declaration::Variable(round) = &program.declarations[2] else { panic!("se esperaba un let") };
+    let Expression::Binary { left, op: BinaryOp::DoubleAmpersand, .. } = &round.value else { panic!("se esperaba '&&'") };
+    assert!(matches!(&**left, Expression::Is { variant, .. } if variant.name == "Circle"));
+
+    let Declaration::Statement(Statement::Match(stmt)) = &program.declarations[3] else { panic!("se esperaba un match") };
+    let Pattern::Variant { variant, payload, span, .. } = &stmt.arms[0].pattern else { panic!("se esperaba una variante") };
+    assert_eq!(variant.name, "Rect");
+    assert!(matches!(payload[..], [Pattern::Binding(_), Pattern::Literal(Literal::Float(_), _)]));
+    assert_eq!(format!("{:?}", span), "10:11..10:30");
+    assert!(matches!(&stmt.arms[1].pattern, Pattern::Variant { payload, .. } if payload.is_empty()));
+}
+
+#[test]
+fn test_enum_variants_and_matches_are_checked() {
+    let program = parse(r#"
+enum Shape { Circle(Float), Rect(Float, Float), Empty }
+enum Light { Red, Green, Red }
+let g = Shape::Empty;
+fn area(n: Int) -> Float {
+    let s = Shape::Circle(1.5);
+    let t = Shape::Rect(2.0);
+    let u = Shape::Circle(1);
+    let v = Shape::Square;
+    let c = Color::Red;
+    let same: Bool = s == Shape::Empty;
+    let round = n is Shape::Circle;
+    match s {
+        Shape::Circle(r) => { return r; }
+        Shape::Rect(w, h) => { return w * h; }
+    }
+    match s {
+        Shape::Circle(r) => { return r; }
+        Shape::Circle(0.0) => { return 0.0; }
+        Shape::Rect(w, h) => { return w * h; }
+        Shape::Empty => { return 0.0; }
+    }
+}
+fn main() -> Int { return 0; }
+"#);
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let errors: Vec<String> = analyzer.errors.iter().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(errors, vec![
+        "RedeclaredVariant(\"Light\", \"Red\", 3:26..3:29)",
+        "PayloadCountMismatch(\"Shape::Rect\", 2, 1, 7:13..7:29)",
+        "TypeMismatch(\"Float\", \"Int\", 8:27..8:28)",
+        "UndefinedVariant(\"Shape\", \"Square\", 9:20..9:26)",
+        "UndefinedEnum(\"Color\", 10:13..10:18)",
+        "TypeMismatch(\"Shape\", \"Int\", 12:17..12:18)",
+        "NonExhaustiveMatch(\"Shape\", 13:11..13:12)",
+        "UnreachablePattern(19:9..19:27)",
+    ]);
+    assert_eq!(analyzer.symbol_table.lookup("g").unwrap().get_type(), Type::Enum("Shape".to_string()));
+}
+
+#[test]
+fn test_type_annotations() {
+    let program = parse(r#"
+let grid: [[Int; 3]; 2] = [[1, 2, 3], [4, 5, 6]];
+fn apply(f: fn(Int, Int) -> Bool, p: Point?, on_done: (fn() -> Void)?) -> [Shape] { }
+"#);
+    let Declaration::Variable(grid) = &program.declarations[0] else { panic!("se esperaba un let") };
+    let row = Type::FixedArray(Box::new(Type::Int), 3);
+    assert_eq!(grid.var_type, Some(Type::FixedArray(Box::new(row), 2)));
+
+    let Declaration::Function(apply) = &program.declarations[1] else { panic!("se esperaba una función") };
+    let types: Vec<String> = apply.parameters.iter().map(|p| p.param_type.to_string()).collect();
+    assert_eq!(types, vec!["fn(Int, Int) -> Bool", "Point?", "(fn() -> Void)?"]);
+    assert_eq!(apply.parameters[1].param_type, Type::Optional(Box::new(Type::Struct("Point".to_string()))));
+    assert_eq!(apply.return_type, Type::Array(Box::new(Type::Struct("Shape".to_string()))));
+
+    // Los tipos inferidos viajan como texto: `from_str` debe poder leer lo que escribe `to_string`.
+    for type_ in apply.parameters.iter().map(|p| &p.param_type).chain([&apply.return_type, grid.var_type.as_ref().unwrap()]) {
+        assert_eq!(Type::from_str(&type_.to_string()).as_ref(), Some(type_));
+    }
+    assert_eq!(Type::from_str("fn(Int) -> Int?"), Some(Type::Function(vec![Type::Int], Box::new(Type::Optional(Box::new(Type::Int))))));
+    assert_eq!(Type::from_str("[Int; x]"), None);
+}
+
+#[test]
+fn test_user_types_are_checked() {
+    let program = parse(r#"
+struct Point { x: Int, y: Int }
+enum Shape { Dot(Point), Empty }
+fn twice(f: fn(Int) -> Int, n: Int) -> Int {
+    return f(f(n));
+}
+fn inc(n: Int) -> Int { return n + 1; }
+fn first(p: Point, q: Pointt) -> Int {
+    let maybe: Int? = p.x;
+    let row: [Int; 3] = [1, 2, 3];
+    let short: [Int; 3] = [1, 2];
+    let any: [Int] = row;
+    let back: [Int; 3] = any;
+    let step: fn(Int) -> Int = inc;
+    let bad: fn(Int) -> Bool = inc;
+    maybe = 4;
+    return twice(step, p.y) + maybe;
+}
+fn main() -> Int {
+    let s: Shape = Shape::Dot(Point { x = 1, y = 2 });
+    return first(Point { x = 1, y = 2 }, 0);
+}
+"#);
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    let errors: Vec<String> = analyzer.errors.iter().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(errors, vec![
+        "UndefinedType(\"Pointt\", 8:20..8:29)",
+        "TypeMismatch(\"[Int; 3]\", \"[Int; 2]\", 11:27..11:33)",
+        "TypeMismatch(\"[Int; 3]\", \"[Int]\", 13:26..13:29)",
+        "TypeMismatch(\"fn(Int) -> Bool\", \"fn(Int) -> Int\", 15:32..15:35)",
+        "TypeMismatch(\"Int\", \"Int?\", 17:12..17:36)",
+    ]);
+
+    let root = analyzer.symbol_table.get_root_scope();
+    assert_eq!(root.symbols["inc"].get_type().to_string(), "fn(Int) -> Int");
+    let first = root.children.iter().find(|s| s.name == "function: first").unwrap();
+    assert_eq!(first.symbols["p"].get_type(), Type::Struct("Point".to_string()));
+}
+
+#[test]
+fn test_import_declarations() {
+    let (result, _) = parse_source(r#"
+import "geometry.dream";
+import shapes.circle;
+import 42;
+fn main() -> Int { import "late.dream"; return 0; }
+"#);
+    let imports: Vec<&str> = result.ast.declarations.iter()
+        .filter_map(|d| match d { Declaration::Import(import) => Some(import.path.as_str()), _ => None })
+        .collect();
+    assert_eq!(imports, vec!["geometry.dream", "shapes/circle.dream"]);
+    assert_eq!(expected_vs_found(&result.errors), vec![("la ruta del módulo a importar", "'42'", 4, 8)]);
+
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&result.ast);
+    assert!(analyzer.errors.iter().any(|e| matches!(e, SemanticError::MisplacedImport(span) if span.start.line == 5 && span.start.column == 20)));
+}
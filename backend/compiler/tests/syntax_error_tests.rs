@@ -0,0 +1,64 @@
+// Corpus de programas mal formados: cada archivo anota los errores sintácticos que debe producir.
+//
+// Las anotaciones son comentarios de la forma
+//     // error L:C-L:C: se esperaba X, se encontró Y
+// y deben coincidir, en orden, con `ParseResult.errors`.
+
+use compiler::ast::SyntaxError;
+use compiler::parser::parse_source;
+use compiler::semantic_analyzer::SemanticAnalyzer;
+use std::fs;
+use std::path::Path;
+
+const CORPUS: &str = "tests/cases/malformed";
+
+/// Formatea un error con la misma sintaxis que las anotaciones del corpus.
+fn describe(error: &SyntaxError) -> String {
+    let span = error.span();
+    format!(
+        "error {}:{}-{}:{}: se esperaba {}, se encontró {}",
+        span.start.line, span.start.column, span.end.line, span.end.column,
+        error.expected(), error.found()
+    )
+}
+
+/// Extrae las anotaciones `// error ...` del archivo.
+fn expected_errors(source: &str) -> Vec<String> {
+    source.lines()
+        .filter_map(|line| line.trim().strip_prefix("// "))
+        .filter(|annotation| annotation.starts_with("error "))
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn test_malformed_programs_report_every_error() {
+    let mut files: Vec<_> = fs::read_dir(Path::new(CORPUS))
+        .expect("No se pudo leer el corpus de programas mal formados")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "dreamc"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty());
+
+    for path in files {
+        let source = fs::read_to_string(&path).unwrap();
+        let (result, lex_errors) = parse_source(&source);
+        assert!(lex_errors.is_empty(), "{}: {:?}", path.display(), lex_errors);
+
+        let actual: Vec<String> = result.errors.iter().map(describe).collect();
+        assert!(!actual.is_empty(), "{}: no se reportó ningún error", path.display());
+        assert_eq!(actual, expected_errors(&source), "{}", path.display());
+
+        // El AST recuperado debe poder analizarse sin que el analizador entre en pánico.
+        SemanticAnalyzer::new().analyze(&result.ast);
+    }
+}
+
+#[test]
+fn test_syntax_error_messages_describe_expected_and_found() {
+    let (result, _) = parse_source("let a = 1\nlet b = 2;");
+    let error = &result.errors[0];
+    assert_eq!(error.name(), "UnexpectedToken");
+    assert_eq!(error.to_string(), "Se esperaba ';' después de la declaración de la variable, pero se encontró 'let' en la línea 2, columna 1");
+}
@@ -59,6 +59,9 @@ const clientCompiler = new compilerProto.Compiler(
   grpc.credentials.createInsecure()
 );
 
+// The editor counts columns in UTF-16 code units, like every JavaScript string.
+const POSITION_ENCODING = 'POSITION_ENCODING_UTF16';
+
 // --- ELECTRON WINDOW CREATION ---
 
 function createWindow() {
@@ -122,7 +125,7 @@ ipcMain.handle('run-compiler', async (_event, sourceCode) => {
   console.log("🚀 Received source code for compilation...");
   return new Promise((resolve, reject) => {
     // Call the 'Compile' RPC from your Compiler service
-    clientCompiler.Compile({ source: sourceCode }, (err, response) => {
+    clientCompiler.Compile({ source: sourceCode, position_encoding: POSITION_ENCODING }, (err, response) => {
       if (err) {
         console.error("❌ gRPC Compiler Error:", err);
         reject(err.message);
@@ -139,7 +142,7 @@ ipcMain.handle('run-compiler', async (_event, sourceCode) => {
 ipcMain.handle('llvm-translate', async (_event, sourceCode) => {
   console.log("🔄 Generating LLVM IR...");
   return new Promise((resolve, reject) => {
-    clientCompiler.LlvmTranslate({ source: sourceCode }, (err, response) => {
+    clientCompiler.LlvmTranslate({ source: sourceCode, position_encoding: POSITION_ENCODING }, (err, response) => {
       if (err) {
         console.error("❌ gRPC LLVM Translate Error:", err);
         reject(err.message);
@@ -155,7 +158,7 @@ ipcMain.handle('llvm-translate', async (_event, sourceCode) => {
 ipcMain.handle('llvm-optimize', async (_event, sourceCode) => {
   console.log("⚡ Optimizing LLVM IR...");
   return new Promise((resolve, reject) => {
-    clientCompiler.LlvmOptimize({ source: sourceCode }, (err, response) => {
+    clientCompiler.LlvmOptimize({ source: sourceCode, position_encoding: POSITION_ENCODING }, (err, response) => {
       if (err) {
         console.error("❌ gRPC LLVM Optimize Error:", err);
         reject(err.message);
@@ -171,7 +174,7 @@ ipcMain.handle('llvm-optimize', async (_event, sourceCode) => {
 ipcMain.handle('execute-program', async (_event, sourceCode) => {
   console.log("▶️ Executing program...");
   return new Promise((resolve, reject) => {
-    clientCompiler.Execute({ source: sourceCode }, (err, response) => {
+    clientCompiler.Execute({ source: sourceCode, position_encoding: POSITION_ENCODING }, (err, response) => {
       if (err) {
         console.error("❌ gRPC Execute Error:", err);
         reject(err.message);
@@ -186,7 +189,7 @@ ipcMain.handle('execute-program', async (_event, sourceCode) => {
 ipcMain.handle('run-lexer', async (_event, code) => {
   console.log("Received code for lexing:", code);
   return new Promise((resolve, reject) => {
-    clientLexer.Analyze({ input: code }, (err, response) => {
+    clientLexer.Analyze({ input: code, position_encoding: POSITION_ENCODING }, (err, response) => {
       if (err) {
         console.error("gRPC Lexer Error:", err);
         reject(err.message);
@@ -183,7 +183,7 @@ const SymbolTableView = ({ symbolTable, theme }) => {
                         <tbody style={{ color: theme.tertiary }}>
                             {validSymbols.map((symbol) => (
                                 <tr key={symbol.name + symbol.line + symbol.column}>
-                                    <td className="px-2 py-1 border-b" style={{ borderColor: theme.secondary, color: theme.syntax.identifier }}>{symbol.name}</td>
+                                    <td className="px-2 py-1 border-b" style={{ borderColor: theme.secondary, color: theme.syntax.identifier }} title={symbol.doc || undefined}>{symbol.name}</td>
                                     <td className="px-2 py-1 border-b" style={{ borderColor: theme.secondary, color: theme.syntax.string }}>{symbol.symbol_type}</td>
                                     <td className="px-2 py-1 border-b" style={{ borderColor: theme.secondary, color: theme.syntax.numeric }}>{symbol.data_type}</td>
                                     <td className="px-2 py-1 border-b" style={{ borderColor: theme.secondary }}>L{symbol.line}:{symbol.column}</td>